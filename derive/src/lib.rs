@@ -0,0 +1,237 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! `#[derive(SortKey)]`, used through the `derive` feature of `lazysort`.
+//!
+//! The fields to sort by are marked `#[sort_key]`, optionally with an
+//! `order = n` to compare them in an order other than that of their
+//! declaration, and `desc` to reverse them.  Fields with an `order` are
+//! compared first, lowest `order` first, and then the others as declared.
+//!
+//! The input is parsed directly from the token stream, which is all that is
+//! needed for the structs this supports: non-generic, with named fields.
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+struct Key {
+    order: Option<i64>,
+    name: String,
+    ty: String,
+    desc: bool,
+}
+
+#[proc_macro_derive(SortKey, attributes(sort_key))]
+pub fn derive_sort_key(input: TokenStream) -> TokenStream {
+    let code = match expand(input) {
+        Ok(code) => code,
+        Err(message) => format!("compile_error!({:?});", message),
+    };
+    code.parse().expect("generated code is valid")
+}
+
+fn expand(input: TokenStream) -> Result<String, String> {
+    let (name, body) = parse_struct(input)?;
+    let mut keys = Vec::new();
+    for field in split_top_level(body.into_iter().collect()) {
+        if let Some(key) = parse_field(field)? {
+            keys.push(key);
+        }
+    }
+    if keys.is_empty() {
+        return Err("no fields are marked #[sort_key]".to_string());
+    }
+    // A stable sort keeps the fields without an `order` as declared.
+    keys.sort_by_key(|k| k.order.unwrap_or(i64::MAX));
+
+    let mut cmp = String::new();
+    let mut key_types = String::new();
+    let mut key_values = String::new();
+    for (i, key) in keys.iter().enumerate() {
+        let (a, b) = if key.desc {
+            ("other", "self")
+        } else {
+            ("self", "other")
+        };
+        let compare = format!("::std::cmp::Ord::cmp(&{}.{n}, &{}.{n})", a, b, n = key.name);
+        if i == 0 {
+            cmp = compare;
+        } else {
+            cmp = format!("{}.then_with(|| {})", cmp, compare);
+        }
+        let value = format!("::std::clone::Clone::clone(&self.{})", key.name);
+        if key.desc {
+            key_types += &format!("::std::cmp::Reverse<{}>, ", key.ty);
+            key_values += &format!("::std::cmp::Reverse({}), ", value);
+        } else {
+            key_types += &format!("{}, ", key.ty);
+            key_values += &format!("{}, ", value);
+        }
+    }
+
+    Ok(format!(
+        "impl ::lazysort::SortKey for {name} {{
+            fn sort_key_cmp(&self, other: &Self) -> ::std::cmp::Ordering {{
+                {cmp}
+            }}
+        }}
+
+        impl {name} {{
+            /// The fields marked `#[sort_key]`, in the order they are compared.
+            #[allow(dead_code)]
+            pub fn sort_key(&self) -> ({key_types}) {{
+                ({key_values})
+            }}
+        }}",
+        name = name,
+        cmp = cmp,
+        key_types = key_types,
+        key_values = key_values
+    ))
+}
+
+/// The name and the fields of the struct.
+fn parse_struct(input: TokenStream) -> Result<(String, TokenStream), String> {
+    let mut tokens = input.into_iter();
+    while let Some(token) = tokens.next() {
+        let word = match token {
+            TokenTree::Ident(ref ident) => ident.to_string(),
+            _ => continue,
+        };
+        match &word[..] {
+            "struct" => {}
+            "enum" | "union" => return Err("SortKey can only be derived for structs".to_string()),
+            _ => continue,
+        }
+        let name = match tokens.next() {
+            Some(TokenTree::Ident(ident)) => ident.to_string(),
+            _ => return Err("expected the name of the struct".to_string()),
+        };
+        return match tokens.next() {
+            Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Brace => {
+                Ok((name, group.stream()))
+            }
+            Some(TokenTree::Punct(ref punct)) if punct.as_char() == '<' => {
+                Err("SortKey cannot be derived for generic structs".to_string())
+            }
+            _ => Err("SortKey can only be derived for structs with named fields".to_string()),
+        };
+    }
+    Err("SortKey can only be derived for structs".to_string())
+}
+
+/// Split on the commas that are not within a group or a type's angle
+/// brackets.
+fn split_top_level(tokens: Vec<TokenTree>) -> Vec<Vec<TokenTree>> {
+    let mut parts = Vec::new();
+    let mut part = Vec::new();
+    let mut depth = 0usize;
+    let mut after_dash = false;
+    for token in tokens {
+        if let TokenTree::Punct(ref punct) = token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                // The `>` of `->` closes nothing.
+                '>' if !after_dash => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    parts.push(std::mem::take(&mut part));
+                    after_dash = false;
+                    continue;
+                }
+                _ => {}
+            }
+            after_dash = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+        } else {
+            after_dash = false;
+        }
+        part.push(token);
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+fn parse_field(tokens: Vec<TokenTree>) -> Result<Option<Key>, String> {
+    let mut key = None;
+    let mut tokens = tokens.into_iter().peekable();
+
+    // Attributes.
+    while let Some(TokenTree::Punct(punct)) = tokens.peek() {
+        if punct.as_char() != '#' {
+            break;
+        }
+        tokens.next();
+        let attribute = match tokens.next() {
+            Some(TokenTree::Group(group)) => group.stream(),
+            _ => return Err("expected an attribute".to_string()),
+        };
+        let mut attribute = attribute.into_iter();
+        match attribute.next() {
+            Some(TokenTree::Ident(ref ident)) if ident.to_string() == "sort_key" => {}
+            _ => continue,
+        }
+        let mut options = Key {
+            order: None,
+            name: String::new(),
+            ty: String::new(),
+            desc: false,
+        };
+        if let Some(TokenTree::Group(group)) = attribute.next() {
+            for option in split_top_level(group.stream().into_iter().collect()) {
+                parse_option(&option, &mut options)?;
+            }
+        }
+        key = Some(options);
+    }
+
+    // Visibility.
+    if let Some(TokenTree::Ident(ident)) = tokens.peek() {
+        if ident.to_string() == "pub" {
+            tokens.next();
+            if let Some(TokenTree::Group(group)) = tokens.peek() {
+                if group.delimiter() == Delimiter::Parenthesis {
+                    tokens.next();
+                }
+            }
+        }
+    }
+
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected a field name".to_string()),
+    };
+    match tokens.next() {
+        Some(TokenTree::Punct(ref punct)) if punct.as_char() == ':' => {}
+        _ => return Err(format!("expected `:` after field `{}`", name)),
+    }
+    let ty: TokenStream = tokens.collect();
+
+    Ok(key.map(|mut key| {
+        key.name = name;
+        key.ty = ty.to_string();
+        key
+    }))
+}
+
+fn parse_option(option: &[TokenTree], key: &mut Key) -> Result<(), String> {
+    let text: Vec<String> = option.iter().map(|t| t.to_string()).collect();
+    match text.iter().map(|s| &s[..]).collect::<Vec<&str>>()[..] {
+        ["asc"] => key.desc = false,
+        ["desc"] => key.desc = true,
+        ["order", "=", n] => match n.parse() {
+            Ok(n) => key.order = Some(n),
+            Err(_) => return Err(format!("`order` must be an integer, not `{}`", n)),
+        },
+        _ => return Err(format!("unknown sort_key option `{}`", text.join(" "))),
+    }
+    Ok(())
+}
@@ -0,0 +1,385 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! The building blocks of the lazy sorts, for building other lazy structures.
+//!
+//! All of these work in the layout the lazy sorts use: the lowest values are
+//! kept at the highest indexes, so that the next value can be popped off the
+//! end of a `Vec`.  A range of the data is given as `(lower, upper)`, where
+//! `lower` is the index of the end that will hold the lowest values and so is
+//! never less than `upper`; both ends are included.
+//!
+//! A `Work` list records the ranges of the data still to be sorted, one after
+//! the other, with the range holding the next values on top.  `pop_next`
+//! partitions only as far as it must to find the next value.
+
+use std::cmp::Ordering;
+
+use super::{finish, make_work, qsort, settle as settle_work, Stack};
+use super::{pivot, Thresholds, WorkStack};
+
+/// Check a range before handing it to code that does not bounds check.
+fn check_range<T>(data: &[T], lower: usize, upper: usize) {
+    assert!(
+        upper <= lower && lower < data.len(),
+        "range ({}, {}) out of bounds for {} values",
+        lower,
+        upper,
+        data.len()
+    );
+}
+
+/// Partition `data[upper..=lower]` around the value at `pivot`: values
+/// greater than it are moved to the lower indexes, and the rest to the higher
+/// indexes.  Returns the index the pivot value ends at.
+///
+/// # Panics
+///
+/// If the range, or the pivot, is out of bounds.
+pub fn partition<T, F>(data: &mut [T], lower: usize, upper: usize, pivot: usize, by: F) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    check_range(data, lower, upper);
+    assert!(
+        upper <= pivot && pivot <= lower,
+        "pivot {} outside range ({}, {})",
+        pivot,
+        lower,
+        upper
+    );
+    super::partition(&by, data, lower, upper, pivot)
+}
+
+/// Partition `data[upper..=lower]` three ways around the value at `pivot`:
+/// greater values at the lower indexes, then the values equal to it, then
+/// the lower values.  Returns the range `(lower, upper)` of the equal values,
+/// which need no further sorting.  This does better than `partition` when
+/// there are many equal values.
+///
+/// # Panics
+///
+/// If the range, or the pivot, is out of bounds.
+pub fn partition3<T, F>(
+    data: &mut [T],
+    lower: usize,
+    upper: usize,
+    pivot: usize,
+    by: F,
+) -> (usize, usize)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    check_range(data, lower, upper);
+    assert!(
+        upper <= pivot && pivot <= lower,
+        "pivot {} outside range ({}, {})",
+        pivot,
+        lower,
+        upper
+    );
+    three_way(&by, data, lower, upper, pivot)
+}
+
+pub(crate) fn three_way<T, F>(
+    by: &F,
+    data: &mut [T],
+    lower: usize,
+    upper: usize,
+    pivot: usize,
+) -> (usize, usize)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    // The pivot is kept at `upper` while the values are sorted around it:
+    // (upper, greater) are greater, [greater, i) are equal, and (less, lower]
+    // are lower than the pivot.
+    data.swap(upper, pivot);
+    let mut greater = upper + 1;
+    let mut i = upper + 1;
+    let mut less = lower;
+    while i <= less {
+        match by(&data[i], &data[upper]) {
+            Ordering::Greater => {
+                data.swap(i, greater);
+                greater += 1;
+                i += 1;
+            }
+            Ordering::Equal => i += 1,
+            Ordering::Less => {
+                data.swap(i, less);
+                less -= 1;
+            }
+        }
+    }
+    data.swap(upper, greater - 1);
+    #[cfg(feature = "debug-viz")]
+    super::viz::note(lower, upper, &[greater - 1, i - 1]);
+    (i - 1, greater - 1)
+}
+
+/// Partition `data[upper..=lower]` until the value at `n` is the one that
+/// would be there were the range sorted, with greater values at the lower
+/// indexes and lower values at the higher indexes.
+///
+/// # Panics
+///
+/// If the range is out of bounds, or `n` is not in it.
+pub fn select<T, F>(data: &mut [T], lower: usize, upper: usize, n: usize, by: F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    check_range(data, lower, upper);
+    assert!(
+        upper <= n && n <= lower,
+        "index {} outside range ({}, {})",
+        n,
+        lower,
+        upper
+    );
+    select_with(&by, data, lower, upper, n, false)
+}
+
+/// As `select`, but pivoting on the median of medians, so that it takes
+/// linear time in the worst case rather than only on average.
+///
+/// # Panics
+///
+/// If the range is out of bounds, or `n` is not in it.
+pub fn select_deterministic<T, F>(data: &mut [T], lower: usize, upper: usize, n: usize, by: F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    check_range(data, lower, upper);
+    assert!(
+        upper <= n && n <= lower,
+        "index {} outside range ({}, {})",
+        n,
+        lower,
+        upper
+    );
+    select_with(&by, data, lower, upper, n, true)
+}
+
+/// The index of a pivot for `data[upper..=lower]`, the median of the medians
+/// of its groups of five, which has roughly three tenths of the values or more
+/// on either side of it.  The values of the range are reordered to find it.
+///
+/// # Panics
+///
+/// If the range is out of bounds.
+pub fn median_of_medians<T, F>(data: &mut [T], lower: usize, upper: usize, by: F) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    check_range(data, lower, upper);
+    median_of_medians_by(&by, data, lower, upper)
+}
+
+pub(crate) fn select_with<T, F>(
+    by: &F,
+    data: &mut [T],
+    mut lower: usize,
+    mut upper: usize,
+    n: usize,
+    deterministic: bool,
+) where
+    F: Fn(&T, &T) -> Ordering,
+{
+    while lower > upper {
+        if deterministic {
+            // Three ways, as runs of equal values would otherwise undo the
+            // guarantee of the pivot.
+            let p = median_of_medians_by(by, data, lower, upper);
+            let (eq_lower, eq_upper) = three_way(by, data, lower, upper, p);
+            if n < eq_upper {
+                lower = eq_upper - 1;
+            } else if n > eq_lower {
+                upper = eq_lower + 1;
+            } else {
+                return;
+            }
+        } else {
+            let p = super::partition(by, data, lower, upper, pivot(lower, upper));
+            match p.cmp(&n) {
+                Ordering::Equal => return,
+                Ordering::Greater => lower = p - 1,
+                Ordering::Less => upper = p + 1,
+            }
+        }
+    }
+}
+
+pub(crate) fn median_of_medians_by<T, F>(
+    by: &F,
+    data: &mut [T],
+    lower: usize,
+    upper: usize,
+) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = lower - upper + 1;
+    let mut medians = 0;
+    let mut start = upper;
+    while start <= lower {
+        let end = (start + 5).min(lower + 1);
+        for i in start + 1..end {
+            let mut j = i;
+            while j > start && by(&data[j - 1], &data[j]) == Ordering::Greater {
+                data.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        data.swap(upper + medians, start + (end - start - 1) / 2);
+        medians += 1;
+        start = end;
+    }
+    if len <= 5 {
+        return upper;
+    }
+    let middle = upper + medians / 2;
+    select_with(by, data, upper + medians - 1, upper, middle, true);
+    middle
+}
+
+/// The ranges of some data still to be sorted.
+#[derive(Clone)]
+pub struct Work {
+    stack: WorkStack,
+}
+
+impl Work {
+    /// The work to sort `len` values from scratch.
+    pub fn new(len: usize) -> Self {
+        Work {
+            stack: make_work(len),
+        }
+    }
+
+    /// No work: the data is already fully sorted.
+    pub fn sorted() -> Self {
+        Work {
+            stack: WorkStack::new(),
+        }
+    }
+
+    /// Whether the data is fully sorted.
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Add the range `(lower, upper)`, which must come directly after the
+    /// ranges already added, to hold the next values.
+    pub fn push(&mut self, lower: usize, upper: usize) {
+        self.stack.push((lower, upper))
+    }
+}
+
+/// Remove and return the next value of `data`, the lowest by `by`, doing only
+/// as much of the work as that needs.
+///
+/// # Panics
+///
+/// If `work` does not describe `data`.
+pub fn pop_next<T, F>(data: &mut Vec<T>, work: &mut Work, by: F) -> Option<T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    match work.stack.pop() {
+        Some((lower, upper)) => {
+            assert!(
+                lower + 1 == data.len() && upper <= lower,
+                "work does not match {} values",
+                data.len()
+            );
+            Some(qsort(&by, data, &mut work.stack, lower, upper))
+        }
+        None => data.pop(),
+    }
+}
+
+/// Partition until the next value of `data` is in place at its end, without
+/// removing it, and return it.
+///
+/// # Panics
+///
+/// If `work` does not describe `data`.
+pub fn settle<'a, T, F>(data: &'a mut [T], work: &mut Work, by: F) -> Option<&'a T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if let Some((lower, upper)) = work.stack.pop() {
+        assert!(
+            lower + 1 == data.len() && upper <= lower,
+            "work does not match {} values",
+            data.len()
+        );
+        work.stack.push((lower, upper));
+        settle_work(&by, data, &mut work.stack, Thresholds::default().small_sort);
+    }
+    data.last()
+}
+
+/// Finish the work, leaving `data` fully sorted, the lowest value last.
+pub fn sort_remaining<T, F>(data: &mut [T], work: &mut Work, by: F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let small_sort = Thresholds::default().small_sort;
+    finish(&by, data, &mut work.stack, small_sort)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::{
+        median_of_medians, partition3, pop_next, select, select_deterministic, settle, Work,
+    };
+
+    #[test]
+    fn algo_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 20)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        let mut data = before.clone();
+        let (lower, upper) = partition3(&mut data, 999, 0, 500, Ord::cmp);
+        let p = data[lower];
+        assert!(data[upper..=lower].iter().all(|&x| x == p));
+        assert!(data[..upper].iter().all(|&x| x > p));
+        assert!(data[lower + 1..].iter().all(|&x| x < p));
+
+        let mut data = before.clone();
+        select(&mut data, 999, 0, 900, Ord::cmp);
+        assert_eq!(expected[99], data[900]);
+
+        let mut data = before.clone();
+        select_deterministic(&mut data, 999, 0, 900, Ord::cmp);
+        assert_eq!(expected[99], data[900]);
+        let mut data: Vec<u32> = (0..1000).collect();
+        let p = median_of_medians(&mut data, 999, 0, Ord::cmp);
+        assert!((300..700).contains(&data[p]));
+
+        let mut data = before;
+        let mut work = Work::new(data.len());
+        assert_eq!(Some(&expected[0]), settle(&mut data, &mut work, Ord::cmp));
+        let mut after = Vec::new();
+        while let Some(x) = pop_next(&mut data, &mut work, Ord::cmp) {
+            after.push(x);
+        }
+        assert_eq!(expected, after);
+        assert!(work.is_empty());
+    }
+}
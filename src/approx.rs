@@ -0,0 +1,185 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy approximate sorting.
+//!
+//! The quicksort stops partitioning once a range holds no more than `epsilon`
+//! of the values, and yields that range in whatever order partitioning left
+//! it.  Every value is yielded within `epsilon * n` places of where it would
+//! be in sorted order, so at most a fraction `epsilon` of all pairs are out of
+//! order, and only `O(n log(1 / epsilon))` comparisons are needed to consume
+//! everything.
+
+use std::cmp::Ordering;
+
+use super::{make_work, partition, pivot, Stack, WorkStack};
+
+/// The largest range that is left unsorted, for `len` values.
+fn block(epsilon: f64, len: usize) -> usize {
+    ((epsilon * len as f64) as usize).max(1)
+}
+
+fn next<T, F>(by: &F, data: &mut Vec<T>, work: &mut WorkStack, block: usize) -> Option<T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    loop {
+        let (lower, upper) = work.pop()?;
+        if lower - upper < block {
+            if lower > upper {
+                work.push((lower - 1, upper));
+            }
+            return data.pop();
+        }
+        let p = partition(by, data, lower, upper, pivot(lower, upper));
+        if p > upper {
+            work.push((p - 1, upper));
+        }
+        work.push((p, p));
+        if lower > p {
+            work.push((lower, p + 1));
+        }
+    }
+}
+
+pub struct ApproxSortIterator<T> {
+    data: Vec<T>,
+    work: WorkStack,
+    block: usize,
+}
+
+pub struct ApproxSortIteratorBy<T, F> {
+    data: Vec<T>,
+    work: WorkStack,
+    block: usize,
+    by: F,
+}
+
+pub trait SortedApprox {
+    type Item;
+
+    /// Sort into nearly sorted order: each value is yielded within
+    /// `epsilon * n` places of its position in sorted order.  An `epsilon` of
+    /// zero sorts exactly.
+    fn approx_sorted(self, epsilon: f64) -> ApproxSortIterator<Self::Item>
+    where
+        Self::Item: Ord;
+
+    fn approx_sorted_by<F>(self, epsilon: f64, by: F) -> ApproxSortIteratorBy<Self::Item, F>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering;
+}
+
+impl<T, I> SortedApprox for I
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn approx_sorted(self, epsilon: f64) -> ApproxSortIterator<T>
+    where
+        T: Ord,
+    {
+        let data: Vec<T> = self.collect();
+        ApproxSortIterator {
+            work: make_work(data.len()),
+            block: block(epsilon, data.len()),
+            data,
+        }
+    }
+
+    fn approx_sorted_by<F>(self, epsilon: f64, by: F) -> ApproxSortIteratorBy<T, F>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let data: Vec<T> = self.collect();
+        ApproxSortIteratorBy {
+            work: make_work(data.len()),
+            block: block(epsilon, data.len()),
+            data,
+            by,
+        }
+    }
+}
+
+impl<T> Iterator for ApproxSortIterator<T>
+where
+    T: Ord,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        next(&Ord::cmp, &mut self.data, &mut self.work, self.block)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<T, F> Iterator for ApproxSortIteratorBy<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        next(&self.by, &mut self.data, &mut self.work, self.block)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::SortedApprox;
+
+    #[test]
+    fn approx_sorted_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..10000).map(|_| rng.gen_range(0, 1000000)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        let mut after: Vec<u32> = before.iter().cloned().approx_sorted(0.01).collect();
+        after.sort();
+        assert_eq!(expected, after);
+
+        let exact: Vec<u32> = before.into_iter().approx_sorted(0.0).collect();
+        assert_eq!(expected, exact);
+    }
+
+    #[test]
+    fn approx_sorted_by_test() {
+        // Each value is no more than 100 places, a tenth of the values, from
+        // where it belongs.
+        let before: Vec<usize> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let after: Vec<usize> = before
+            .into_iter()
+            .approx_sorted_by(0.1, |a, b| b.cmp(a))
+            .collect();
+        for (i, x) in after.into_iter().enumerate() {
+            let place = 999 - x;
+            assert!(i.max(place) - i.min(place) < 100);
+        }
+    }
+}
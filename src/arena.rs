@@ -0,0 +1,223 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy sorts that reuse their buffers.
+//!
+//! A program that sorts many small batches, one after another, would
+//! otherwise allocate a data buffer and a work list for each.  An arena
+//! keeps the buffers of finished sorts and hands them to the next, cleared
+//! but with their capacity intact, and the sorts partition them exactly as
+//! the owned iterators partition their own.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+
+use super::{qsort, SortedIterator};
+
+/// A pool of the buffers used by lazy sorts.  Iterators created by an arena
+/// borrow their data and work buffers from it, and give them back when they
+/// are dropped, so repeated sorts stop allocating once the pool is warm.
+///
+/// An arena is not `Sync`; use one per thread.
+pub struct SortArena<T> {
+    data: RefCell<Vec<Vec<T>>>,
+    work: RefCell<Vec<Vec<(usize, usize)>>>,
+}
+
+impl<T> Default for SortArena<T> {
+    fn default() -> Self {
+        SortArena::new()
+    }
+}
+
+impl<T> SortArena<T> {
+    pub fn new() -> Self {
+        SortArena {
+            data: RefCell::new(Vec::new()),
+            work: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Drop all pooled buffers, releasing their memory.
+    pub fn clear(&self) {
+        self.data.borrow_mut().clear();
+        self.work.borrow_mut().clear();
+    }
+
+    fn buffers<I>(&self, iter: I) -> (Vec<T>, Vec<(usize, usize)>)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut data = self.data.borrow_mut().pop().unwrap_or_default();
+        data.extend(iter);
+        let mut work = self.work.borrow_mut().pop().unwrap_or_default();
+        if !data.is_empty() {
+            work.push((data.len() - 1, 0));
+        }
+        (data, work)
+    }
+
+    fn give_back(&self, mut data: Vec<T>, mut work: Vec<(usize, usize)>) {
+        data.clear();
+        work.clear();
+        self.data.borrow_mut().push(data);
+        self.work.borrow_mut().push(work);
+    }
+
+    pub fn sorted<I>(&self, iter: I) -> ArenaSortIterator<'_, T>
+    where
+        T: Ord,
+        I: IntoIterator<Item = T>,
+    {
+        let (data, work) = self.buffers(iter);
+        ArenaSortIterator {
+            arena: self,
+            data,
+            work,
+        }
+    }
+
+    pub fn sorted_by<I, F>(&self, iter: I, by: F) -> ArenaSortIteratorBy<'_, T, F>
+    where
+        I: IntoIterator<Item = T>,
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let (data, work) = self.buffers(iter);
+        ArenaSortIteratorBy {
+            arena: self,
+            data,
+            work,
+            by,
+        }
+    }
+}
+
+pub struct ArenaSortIterator<'a, T: 'a> {
+    arena: &'a SortArena<T>,
+    data: Vec<T>,
+    work: Vec<(usize, usize)>,
+}
+
+pub struct ArenaSortIteratorBy<'a, T: 'a, F> {
+    arena: &'a SortArena<T>,
+    data: Vec<T>,
+    work: Vec<(usize, usize)>,
+    by: F,
+}
+
+impl<'a, T> Iterator for ArenaSortIterator<'a, T>
+where
+    T: Ord,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let (lower, upper) = self.work.pop()?;
+        Some(qsort(
+            &Ord::cmp,
+            &mut self.data,
+            &mut self.work,
+            lower,
+            upper,
+        ))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T> SortedIterator for ArenaSortIterator<'a, T> where T: Ord {}
+
+impl<'a, T, F> Iterator for ArenaSortIteratorBy<'a, T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let (lower, upper) = self.work.pop()?;
+        Some(qsort(
+            &self.by,
+            &mut self.data,
+            &mut self.work,
+            lower,
+            upper,
+        ))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T, F> SortedIterator for ArenaSortIteratorBy<'a, T, F> where F: Fn(&T, &T) -> Ordering {}
+
+impl<'a, T> Drop for ArenaSortIterator<'a, T> {
+    fn drop(&mut self) {
+        self.arena.give_back(
+            std::mem::take(&mut self.data),
+            std::mem::take(&mut self.work),
+        );
+    }
+}
+
+impl<'a, T, F> Drop for ArenaSortIteratorBy<'a, T, F> {
+    fn drop(&mut self) {
+        self.arena.give_back(
+            std::mem::take(&mut self.data),
+            std::mem::take(&mut self.work),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SortArena;
+
+    #[test]
+    fn arena_sorted_test() {
+        let arena = SortArena::new();
+        let before: Vec<u64> = vec![9, 7, 1, 1, 6, 3, 1, 4, 22];
+
+        let first: Vec<u64> = arena.sorted(before.iter().cloned()).take(3).collect();
+        assert_eq!(vec![1, 1, 1], first);
+
+        let capacity = arena.data.borrow()[0].capacity();
+        assert!(capacity >= before.len());
+
+        let second: Vec<u64> = arena
+            .sorted_by(before.iter().cloned(), |a, b| b.cmp(a))
+            .collect();
+        assert_eq!(vec![22, 9, 7, 6, 4, 3, 1, 1, 1], second);
+        assert_eq!(1, arena.data.borrow().len());
+        assert_eq!(capacity, arena.data.borrow()[0].capacity());
+    }
+
+    #[test]
+    fn arena_nested_test() {
+        let arena = SortArena::new();
+        let mut a = arena.sorted(vec![3, 1, 2]);
+        let b: Vec<i32> = arena.sorted(vec![6, 5, 4]).collect();
+        assert_eq!(Some(1), a.next());
+        assert_eq!(vec![4, 5, 6], b);
+        drop(a);
+        assert_eq!(2, arena.data.borrow().len());
+
+        arena.clear();
+        assert_eq!(0, arena.data.borrow().len());
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Sources that are already in order.
+//!
+//! Iterators over a `BTreeMap` or `BTreeSet`, or over data sorted earlier,
+//! need no sorting at all.  Wrapping one with `assume_sorted` streams it
+//! unchanged, but marks it as sorted wherever the crate needs sorted input.
+
+use super::SortedIterator;
+
+/// An iterator asserted to yield its values in order.
+#[derive(Clone, Debug)]
+pub struct PreSortedIterator<I> {
+    iter: I,
+}
+
+impl<I> PreSortedIterator<I> {
+    /// The wrapped iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+pub trait AssumeSorted: Iterator + Sized {
+    /// Treat this iterator as already sorted.  Nothing is checked: if the
+    /// values are not in order, neither will be the output of anything built
+    /// on it.
+    fn assume_sorted(self) -> PreSortedIterator<Self> {
+        PreSortedIterator { iter: self }
+    }
+}
+
+impl<I> AssumeSorted for I where I: Iterator {}
+
+impl<I> Iterator for PreSortedIterator<I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I> SortedIterator for PreSortedIterator<I> where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::AssumeSorted;
+
+    #[test]
+    fn assume_sorted_test() {
+        let set: BTreeSet<u32> = vec![5, 1, 4].into_iter().collect();
+        let after: Vec<&u32> = set.iter().assume_sorted().collect();
+        assert_eq!(vec![&1, &4, &5], after);
+    }
+}
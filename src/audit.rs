@@ -0,0 +1,239 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Catching comparators that are not a total order.
+//!
+//! An inconsistent comparator may pass unnoticed: the lazy quicksort does not
+//! fail, it just yields its values in some order that is not sorted.  Where the
+//! standard library's sorts are used instead, by `Strategy::Eager` and by an
+//! adaptive sort once it sorts the rest, they may panic.  `ComparatorAuditor`
+//! wraps a comparator and checks each comparison it makes: that comparing the
+//! values the other way round gives the opposite answer, and that the answer
+//! agrees with the comparisons against a handful of values it has seen
+//! recently.  This costs several extra comparisons for each one made, so is
+//! meant for tests and debugging.
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::cmp::Ordering::{Equal, Greater, Less};
+use std::fmt;
+use std::rc::Rc;
+
+use super::Compare;
+
+/// The number of recent values each comparison is checked against.
+const SAMPLE: usize = 8;
+
+/// A comparison that contradicts another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation<T> {
+    /// `a` compared `order` to `b`, but `b` did not compare the opposite way
+    /// to `a`.
+    Antisymmetry { a: T, b: T, order: Ordering },
+    /// `a <= b` and `b <= c`, with at least one strict, but not `a < c`; or
+    /// the same the other way round.
+    Transitivity { a: T, b: T, c: T },
+}
+
+impl<T: fmt::Debug> fmt::Display for Violation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Antisymmetry {
+                ref a,
+                ref b,
+                order,
+            } => write!(
+                f,
+                "{:?} compares {:?} to {:?}, but not the other way round",
+                a, order, b
+            ),
+            Violation::Transitivity {
+                ref a,
+                ref b,
+                ref c,
+            } => write!(
+                f,
+                "{:?}, {:?} and {:?} are not ordered consistently",
+                a, b, c
+            ),
+        }
+    }
+}
+
+struct State<T> {
+    recent: Vec<T>,
+    next: usize,
+    violation: Option<Violation<T>>,
+}
+
+/// A comparator that checks the comparisons of another.  Clones share what
+/// they have seen, so one can be given to a sort and another kept to ask
+/// afterwards whether anything was wrong.
+pub struct ComparatorAuditor<T, C> {
+    inner: C,
+    state: Rc<RefCell<State<T>>>,
+    panic: bool,
+}
+
+impl<T, C: Clone> Clone for ComparatorAuditor<T, C> {
+    fn clone(&self) -> Self {
+        ComparatorAuditor {
+            inner: self.inner.clone(),
+            state: self.state.clone(),
+            panic: self.panic,
+        }
+    }
+}
+
+impl<T, C> ComparatorAuditor<T, C>
+where
+    T: Clone,
+    C: Compare<T>,
+{
+    pub fn new(inner: C) -> Self {
+        ComparatorAuditor {
+            inner,
+            state: Rc::new(RefCell::new(State {
+                recent: Vec::with_capacity(SAMPLE),
+                next: 0,
+                violation: None,
+            })),
+            panic: false,
+        }
+    }
+
+    /// Panic, with the offending values, as soon as a violation is found,
+    /// rather than only recording it.
+    pub fn panic_on_violation(mut self) -> Self
+    where
+        T: fmt::Debug,
+    {
+        self.panic = true;
+        self
+    }
+
+    /// The first violation found, if any.
+    pub fn violation(&self) -> Option<Violation<T>> {
+        self.state.borrow().violation.clone()
+    }
+
+    fn check(&self, a: &T, b: &T, order: Ordering) -> Option<Violation<T>> {
+        if self.inner.compare(b, a) != order.reverse() {
+            return Some(Violation::Antisymmetry {
+                a: a.clone(),
+                b: b.clone(),
+                order,
+            });
+        }
+        let state = self.state.borrow();
+        for c in state.recent.iter() {
+            let bc = self.inner.compare(b, c);
+            let ac = self.inner.compare(a, c);
+            if !transitive(order, bc, ac) {
+                return Some(Violation::Transitivity {
+                    a: a.clone(),
+                    b: b.clone(),
+                    c: c.clone(),
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Whether `a ? c` can be `ac` given that `a ? b` is `ab` and `b ? c` is `bc`.
+fn transitive(ab: Ordering, bc: Ordering, ac: Ordering) -> bool {
+    match (ab, bc) {
+        (Equal, Equal) => ac == Equal,
+        (Less, Greater) | (Greater, Less) => true,
+        (Less, _) | (_, Less) => ac == Less,
+        (Greater, _) | (_, Greater) => ac == Greater,
+    }
+}
+
+impl<T, C> Compare<T> for ComparatorAuditor<T, C>
+where
+    T: Clone + fmt::Debug,
+    C: Compare<T>,
+{
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        let order = self.inner.compare(a, b);
+        if self.state.borrow().violation.is_none() {
+            if let Some(violation) = self.check(a, b, order) {
+                if self.panic {
+                    panic!("inconsistent comparator: {}", violation);
+                }
+                self.state.borrow_mut().violation = Some(violation);
+            }
+        }
+        let mut state = self.state.borrow_mut();
+        if state.recent.len() < SAMPLE {
+            state.recent.push(a.clone());
+        } else {
+            let next = state.next;
+            state.recent[next] = a.clone();
+            state.next = (next + 1) % SAMPLE;
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::super::compare::Natural;
+    use super::super::{Compare, SortedBy};
+    use super::{ComparatorAuditor, Violation};
+
+    #[test]
+    fn comparator_auditor_test() {
+        let auditor = ComparatorAuditor::new(Natural);
+        let after: Vec<u32> = vec![3, 1, 2, 5, 4]
+            .sorted_by(|a: &u32, b: &u32| auditor.compare(a, b))
+            .collect();
+        assert_eq!(vec![1, 2, 3, 4, 5], after);
+        assert_eq!(None, auditor.violation());
+
+        // Claims every value is less than every other.
+        let auditor = ComparatorAuditor::new(|_: &u32, _: &u32| Ordering::Less);
+        let _: Vec<u32> = vec![3, 1, 2]
+            .sorted_by(|a: &u32, b: &u32| auditor.compare(a, b))
+            .collect();
+        match auditor.violation() {
+            Some(Violation::Antisymmetry { order, .. }) => assert_eq!(Ordering::Less, order),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Rock, paper, scissors.
+        let beats = |a: &u32, b: &u32| match (a + 3 - b) % 3 {
+            0 => Ordering::Equal,
+            1 => Ordering::Greater,
+            _ => Ordering::Less,
+        };
+        let auditor = ComparatorAuditor::new(beats);
+        let _: Vec<u32> = (0..30)
+            .map(|i| i % 3)
+            .sorted_by(|a: &u32, b: &u32| auditor.compare(a, b))
+            .collect();
+        assert!(matches!(
+            auditor.violation(),
+            Some(Violation::Transitivity { .. })
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "inconsistent comparator")]
+    fn comparator_auditor_panic_test() {
+        let auditor =
+            ComparatorAuditor::new(|_: &u32, _: &u32| Ordering::Greater).panic_on_violation();
+        auditor.compare(&1, &2);
+    }
+}
@@ -0,0 +1,333 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! `lazysort` - sort lines of text, like `sort(1)`, using the external sorter.
+//!
+//! With `--take N` only the first `N` lines are ever fully sorted, which makes
+//! `lazysort --take N` much cheaper than `sort | head -n N` on large inputs.
+
+extern crate lazysort;
+
+use std::cmp::Ordering;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process;
+
+use lazysort::external::ExternalSorter;
+
+const USAGE: &str = "Usage: lazysort [OPTION]... [FILE]...
+Sort lines of text from the FILEs, or standard input, to standard output.
+
+  -k, --key N            sort by the Nth field (1-based) instead of the whole line
+  -t, --separator C      fields are separated by C rather than whitespace
+  -n, --numeric          compare keys as numbers
+  -V, --natural          compare runs of digits within keys as numbers
+  -r, --reverse          reverse the order
+  -S, --memory SIZE      buffer at most SIZE bytes before spilling to disk,
+                         with an optional K, M or G suffix (default 64M)
+  -T, --temp-dir DIR     create temporary files in DIR
+      --take N           output only the first N lines
+  -h, --help             display this help and exit";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Text,
+    Numeric,
+    Natural,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    key: Option<usize>,
+    separator: Option<char>,
+    mode: Mode,
+    reverse: bool,
+    memory: usize,
+    temp_dir: Option<String>,
+    take: Option<usize>,
+    files: Vec<String>,
+}
+
+fn parse_size(s: &str) -> Result<usize, String> {
+    let (digits, scale) = match s.chars().last() {
+        Some('K') | Some('k') => (&s[..s.len() - 1], 1 << 10),
+        Some('M') | Some('m') => (&s[..s.len() - 1], 1 << 20),
+        Some('G') | Some('g') => (&s[..s.len() - 1], 1 << 30),
+        _ => (s, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+        .ok_or_else(|| format!("invalid size: {}", s))
+}
+
+fn parse_args<I>(args: I) -> Result<Option<Options>, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = Options {
+        key: None,
+        separator: None,
+        mode: Mode::Text,
+        reverse: false,
+        memory: 64 << 20,
+        temp_dir: None,
+        take: None,
+        files: Vec::new(),
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-k" | "--key" => {
+                let n = value(&arg)?;
+                match n.parse::<usize>() {
+                    Ok(n) if n > 0 => options.key = Some(n),
+                    _ => return Err(format!("invalid field number: {}", n)),
+                }
+            }
+            "-t" | "--separator" => {
+                let c = value(&arg)?;
+                let mut chars = c.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => options.separator = Some(c),
+                    _ => return Err(format!("separator must be one character: {}", c)),
+                }
+            }
+            "-n" | "--numeric" => options.mode = Mode::Numeric,
+            "-V" | "--natural" => options.mode = Mode::Natural,
+            "-r" | "--reverse" => options.reverse = true,
+            "-S" | "--memory" => options.memory = parse_size(&value(&arg)?)?,
+            "-T" | "--temp-dir" => options.temp_dir = Some(value(&arg)?),
+            "--take" => {
+                let n = value(&arg)?;
+                options.take = Some(n.parse().map_err(|_| format!("invalid count: {}", n))?);
+            }
+            "-" => options.files.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => options.files.push(arg),
+        }
+    }
+    Ok(Some(options))
+}
+
+fn field(line: &str, key: Option<usize>, separator: Option<char>) -> &str {
+    match key {
+        None => line,
+        Some(n) => match separator {
+            Some(c) => line.split(c).nth(n - 1).unwrap_or(""),
+            None => line.split_whitespace().nth(n - 1).unwrap_or(""),
+        },
+    }
+}
+
+/// A finite number.  "nan" and "inf" parse as `f64`, but are treated as text,
+/// as NaN has no place in a total order.
+fn number(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().filter(|x| x.is_finite())
+}
+
+/// Numbers first, in numeric order, then anything that is not a number.
+fn numeric_cmp(a: &str, b: &str) -> Ordering {
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+/// Compare runs of digits by their numeric value and everything else by
+/// character, so that "file9" sorts before "file10".
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().cloned(), b.peek().cloned()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut x_digits = String::new();
+                while let Some(c) = a.peek().cloned().filter(char::is_ascii_digit) {
+                    x_digits.push(c);
+                    a.next();
+                }
+                let mut y_digits = String::new();
+                while let Some(c) = b.peek().cloned().filter(char::is_ascii_digit) {
+                    y_digits.push(c);
+                    b.next();
+                }
+                let x_trimmed = x_digits.trim_start_matches('0');
+                let y_trimmed = y_digits.trim_start_matches('0');
+                let order = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed));
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn comparator(options: &Options) -> impl Fn(&String, &String) -> Ordering {
+    let key = options.key;
+    let separator = options.separator;
+    let mode = options.mode;
+    let reverse = options.reverse;
+    move |a: &String, b: &String| {
+        let (x, y) = (field(a, key, separator), field(b, key, separator));
+        let order = match mode {
+            Mode::Text => x.cmp(y),
+            Mode::Numeric => numeric_cmp(x, y),
+            Mode::Natural => natural_cmp(x, y),
+        };
+        if reverse {
+            order.reverse()
+        } else {
+            order
+        }
+    }
+}
+
+fn open(files: &[String]) -> io::Result<Vec<Box<dyn BufRead>>> {
+    if files.is_empty() {
+        return Ok(vec![Box::new(BufReader::new(io::stdin()))]);
+    }
+    files
+        .iter()
+        .map(|name| -> io::Result<Box<dyn BufRead>> {
+            if name == "-" {
+                Ok(Box::new(BufReader::new(io::stdin())))
+            } else {
+                Ok(Box::new(BufReader::new(File::open(name)?)))
+            }
+        })
+        .collect()
+}
+
+fn run(options: &Options) -> io::Result<()> {
+    let mut sorter = ExternalSorter::new(options.memory);
+    if let Some(ref dir) = options.temp_dir {
+        sorter = sorter.temp_dir(dir);
+    }
+
+    let mut error = None;
+    let sorted = {
+        let lines = open(&options.files)?
+            .into_iter()
+            .flat_map(BufRead::lines)
+            .map_while(|line| match line {
+                Ok(line) => Some(line),
+                Err(e) => {
+                    error = Some(e);
+                    None
+                }
+            });
+        sorter.sort_by(lines, comparator(options))?
+    };
+    if let Some(e) = error {
+        return Err(e);
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    for line in sorted.take(options.take.unwrap_or(usize::MAX)) {
+        writeln!(out, "{}", line?)?;
+    }
+    out.flush()
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("lazysort: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = run(&options) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("lazysort: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering::{Equal, Greater, Less};
+
+    use super::{comparator, natural_cmp, numeric_cmp, parse_args, Mode};
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_args_test() {
+        let options = parse_args(args("-k 2 -t , -n -r -S 4K --take 10 a.txt -"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(2), options.key);
+        assert_eq!(Some(','), options.separator);
+        assert_eq!(Mode::Numeric, options.mode);
+        assert!(options.reverse);
+        assert_eq!(4096, options.memory);
+        assert_eq!(Some(10), options.take);
+        assert_eq!(args("a.txt -"), options.files);
+
+        assert_eq!(None, parse_args(args("--help")).unwrap());
+        assert!(parse_args(args("-k 0")).is_err());
+        assert!(parse_args(args("--bogus")).is_err());
+    }
+
+    #[test]
+    fn numeric_cmp_test() {
+        assert_eq!(Less, numeric_cmp("9", "10"));
+        assert_eq!(Less, numeric_cmp("-1.5", "1"));
+        assert_eq!(Less, numeric_cmp("100", "abc"));
+        assert_eq!(Equal, numeric_cmp("2", "2.0"));
+        assert_eq!(Less, numeric_cmp("17713", "nan"));
+        assert_eq!(Greater, numeric_cmp("nan", "-1"));
+        assert_eq!(Less, numeric_cmp("inf", "nan"));
+    }
+
+    #[test]
+    fn natural_cmp_test() {
+        assert_eq!(Less, natural_cmp("file9", "file10"));
+        assert_eq!(Greater, natural_cmp("file10b", "file10a"));
+        assert_eq!(Equal, natural_cmp("a007", "a7"));
+        assert_eq!(Less, natural_cmp("a", "a1"));
+    }
+
+    #[test]
+    fn key_comparator_test() {
+        let options = parse_args(args("-k 2 -n")).unwrap().unwrap();
+        let cmp = comparator(&options);
+        assert_eq!(Less, cmp(&"x 9".to_string(), &"a 10".to_string()));
+    }
+}
@@ -0,0 +1,159 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Keeping the lowest `k` values of a stream.
+//!
+//! Values are buffered until there are `2k` of them, and then the lowest `k`
+//! are selected and the rest dropped, so each insert costs O(1) amortized.
+//! The highest value kept by the last selection is a threshold below which a
+//! new value must fall to be buffered at all, which for a long stream soon
+//! rejects almost every value with a single comparison.
+
+use std::cmp::Ordering::Less;
+use std::slice;
+
+use super::compare::Natural;
+use super::Compare;
+
+/// The lowest `k` values ever inserted, by a `Compare`.
+pub struct BoundedTopK<T, C = Natural> {
+    data: Vec<T>,
+    k: usize,
+    // Whether `data[k - 1]` is the highest of the values kept.
+    pruned: bool,
+    by: C,
+}
+
+impl<T: Ord> BoundedTopK<T> {
+    pub fn new(k: usize) -> Self {
+        BoundedTopK::with_comparator(k, Natural)
+    }
+}
+
+impl<T, C> BoundedTopK<T, C>
+where
+    C: Compare<T>,
+{
+    pub fn with_comparator(k: usize, by: C) -> Self {
+        BoundedTopK {
+            data: Vec::new(),
+            k,
+            pruned: false,
+            by,
+        }
+    }
+
+    /// The most values kept.
+    pub fn capacity(&self) -> usize {
+        self.k
+    }
+
+    /// The number of values that would be kept now.
+    pub fn len(&self) -> usize {
+        self.data.len().min(self.k)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Offer a value, which is kept if it is among the lowest `k` so far.
+    pub fn insert(&mut self, value: T) {
+        if self.k == 0 {
+            return;
+        }
+        if self.pruned && self.by.compare(&value, &self.data[self.k - 1]) != Less {
+            return;
+        }
+        self.data.push(value);
+        if self.data.len() >= 2 * self.k {
+            self.prune();
+        }
+    }
+
+    /// Drop all but the lowest `k` values.
+    fn prune(&mut self) {
+        if self.data.len() > self.k {
+            let by = &self.by;
+            self.data
+                .select_nth_unstable_by(self.k - 1, |a, b| by.compare(a, b));
+            self.data.truncate(self.k);
+            self.pruned = true;
+        }
+    }
+
+    fn sort(&mut self) {
+        self.prune();
+        let by = &self.by;
+        self.data.sort_unstable_by(|a, b| by.compare(a, b));
+    }
+
+    /// The values kept, lowest first.
+    pub fn iter_sorted(&mut self) -> slice::Iter<'_, T> {
+        self.sort();
+        self.data.iter()
+    }
+
+    /// The values kept, lowest first.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        self.sort();
+        self.data
+    }
+}
+
+impl<T, C> Extend<T> for BoundedTopK<T, C>
+where
+    C: Compare<T>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::super::compare::{Natural, Reverse};
+    use super::BoundedTopK;
+
+    #[test]
+    fn bounded_top_k_test() {
+        let mut rng = rand::thread_rng();
+        let values: Vec<u64> = (0..10000).map(|_| rng.gen_range(0, 100000)).collect();
+        let mut expected = values.clone();
+        expected.sort();
+
+        let mut lowest = BoundedTopK::new(10);
+        lowest.extend(values.iter().cloned());
+        assert_eq!(10, lowest.len());
+        assert_eq!(
+            &expected[..10],
+            &lowest.iter_sorted().cloned().collect::<Vec<u64>>()[..]
+        );
+        lowest.insert(0);
+        assert_eq!(0, lowest.into_sorted_vec()[0]);
+
+        let mut highest = BoundedTopK::with_comparator(3, Reverse(Natural));
+        highest.extend(values.iter().cloned());
+        let mut wanted: Vec<u64> = expected.iter().rev().take(3).cloned().collect();
+        assert_eq!(wanted, highest.into_sorted_vec());
+
+        let mut none = BoundedTopK::new(0);
+        none.insert(1u64);
+        assert!(none.is_empty());
+        wanted.clear();
+        assert_eq!(wanted, none.into_sorted_vec());
+    }
+}
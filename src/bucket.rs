@@ -0,0 +1,274 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy bucket sort for numeric keys over a known range.
+//!
+//! The values are distributed, in place, into one bucket per value by
+//! where their key falls in the range, and each bucket is only sorted when
+//! consumption reaches it.  For uniformly distributed keys buckets hold a
+//! handful of values each, so the whole sort is close to linear.
+
+use std::ops::Range;
+
+use super::SortedIterator;
+
+/// The state shared by both bucket iterators.  The values are held in
+/// reverse, so the lowest bucket is at the end of `data` and on top of
+/// `starts`.
+struct Buckets<T> {
+    data: Vec<T>,
+    // The start of each bucket, each ending where the one above it starts.
+    starts: Vec<usize>,
+    // Whether the top bucket has been sorted.
+    sorted: bool,
+}
+
+impl<T> Buckets<T> {
+    fn new<F>(mut data: Vec<T>, range: Range<f64>, key: &F) -> Self
+    where
+        F: Fn(&T) -> f64,
+    {
+        assert!(
+            range.start.is_finite() && range.end.is_finite() && range.start < range.end,
+            "bucket range {:?} is not a finite, non-empty range",
+            range
+        );
+        let n = data.len();
+        if n == 0 {
+            return Buckets {
+                data,
+                starts: Vec::new(),
+                sorted: false,
+            };
+        }
+        let scale = n as f64 / (range.end - range.start);
+        // Bucket `b` of the reversed data holds the keys of bucket `n - 1 - b`
+        // in the natural order.  Keys outside the range go in the first or
+        // last bucket, which is still correct as each bucket is sorted by the
+        // key itself.
+        let bucket = |value: &T| {
+            let k = key(value);
+            let b = if k.is_nan() {
+                if k.is_sign_negative() {
+                    0.0
+                } else {
+                    n as f64
+                }
+            } else {
+                (k - range.start) * scale
+            };
+            n - 1 - (b.max(0.0) as usize).min(n - 1)
+        };
+
+        let mut counts = vec![0usize; n];
+        for value in data.iter() {
+            counts[bucket(value)] += 1;
+        }
+        let mut heads = Vec::with_capacity(n);
+        let mut tails = Vec::with_capacity(n);
+        let mut offset = 0;
+        for count in counts.iter() {
+            heads.push(offset);
+            offset += count;
+            tails.push(offset);
+        }
+
+        // American flag sort: swap each value into its bucket.
+        for b in 0..n {
+            while heads[b] < tails[b] {
+                let target = bucket(&data[heads[b]]);
+                if target == b {
+                    heads[b] += 1;
+                } else {
+                    data.swap(heads[b], heads[target]);
+                    heads[target] += 1;
+                }
+            }
+        }
+
+        let mut starts = Vec::new();
+        let mut offset = 0;
+        for count in counts {
+            if count > 0 {
+                starts.push(offset);
+                offset += count;
+            }
+        }
+        Buckets {
+            data,
+            starts,
+            sorted: false,
+        }
+    }
+
+    fn next<F>(&mut self, key: &F) -> Option<T>
+    where
+        F: Fn(&T) -> f64,
+    {
+        let start = *self.starts.last()?;
+        if !self.sorted {
+            self.data[start..].sort_unstable_by(|a, b| key(b).total_cmp(&key(a)));
+            self.sorted = true;
+        }
+        let value = self.data.pop();
+        if self.data.len() == start {
+            self.starts.pop();
+            self.sorted = false;
+        }
+        value
+    }
+}
+
+pub struct BucketSortIterator<T> {
+    buckets: Buckets<T>,
+}
+
+pub struct BucketSortIteratorByKey<T, F> {
+    buckets: Buckets<T>,
+    key: F,
+}
+
+pub trait SortedBucketed {
+    type Item;
+
+    /// Sort values that mostly fall within `range`.  Values outside of the
+    /// range are still sorted correctly, but less efficiently.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty or reversed, or either end is not finite.
+    fn sorted_bucketed(self, range: Range<f64>) -> BucketSortIterator<Self::Item>
+    where
+        Self::Item: Copy + Into<f64>;
+
+    /// Sort by a key that mostly falls within `range`.  Keys are compared with
+    /// `f64::total_cmp`.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty or reversed, or either end is not finite.
+    fn sorted_bucketed_by_key<F>(
+        self,
+        range: Range<f64>,
+        key: F,
+    ) -> BucketSortIteratorByKey<Self::Item, F>
+    where
+        F: Fn(&Self::Item) -> f64;
+}
+
+impl<T, I> SortedBucketed for I
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn sorted_bucketed(self, range: Range<f64>) -> BucketSortIterator<T>
+    where
+        T: Copy + Into<f64>,
+    {
+        BucketSortIterator {
+            buckets: Buckets::new(self.collect(), range, &|x: &T| (*x).into()),
+        }
+    }
+
+    fn sorted_bucketed_by_key<F>(self, range: Range<f64>, key: F) -> BucketSortIteratorByKey<T, F>
+    where
+        F: Fn(&T) -> f64,
+    {
+        BucketSortIteratorByKey {
+            buckets: Buckets::new(self.collect(), range, &key),
+            key,
+        }
+    }
+}
+
+impl<T> Iterator for BucketSortIterator<T>
+where
+    T: Copy + Into<f64>,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.buckets.next(&|x: &T| (*x).into())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.buckets.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<T> SortedIterator for BucketSortIterator<T> where T: Copy + Into<f64> {}
+
+impl<T, F> Iterator for BucketSortIteratorByKey<T, F>
+where
+    F: Fn(&T) -> f64,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.buckets.next(&self.key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.buckets.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<T, F> SortedIterator for BucketSortIteratorByKey<T, F> where F: Fn(&T) -> f64 {}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::SortedBucketed;
+
+    #[test]
+    fn sorted_bucketed_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<f64> = (0..10000).map(|_| rng.gen_range(0.0, 1.0)).collect();
+        let mut expected = before.clone();
+        expected.sort_by(f64::total_cmp);
+        let after: Vec<f64> = before.into_iter().sorted_bucketed(0.0..1.0).collect();
+        assert_eq!(expected, after);
+    }
+
+    #[test]
+    fn sorted_bucketed_by_key_test() {
+        // Keys well outside the declared range are still ordered.
+        let before = vec![
+            (5.0, 'a'),
+            (-3.0, 'b'),
+            (0.5, 'c'),
+            (f64::NAN, 'd'),
+            (0.2, 'e'),
+        ];
+        let after: Vec<char> = before
+            .into_iter()
+            .sorted_bucketed_by_key(0.0..1.0, |x| x.0)
+            .map(|x| x.1)
+            .collect();
+        assert_eq!(vec!['b', 'e', 'c', 'a', 'd'], after);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a finite, non-empty range")]
+    fn sorted_bucketed_reversed_test() {
+        // Reversed, the scale would be negative and the buckets in reverse.
+        let _ = vec![0.2, 0.7, 0.5].into_iter().sorted_bucketed(1.0..0.0);
+    }
+}
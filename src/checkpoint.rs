@@ -0,0 +1,189 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Saving and resuming a partly done sort.
+//!
+//! The whole state of a lazy sort is its remaining values, in the order it
+//! has partitioned them, and its work list of the ranges still to be sorted.
+//! `checkpoint` hands both out as plain vectors, which can be written out in
+//! any format, and `resume` (or `resume_with`, given the comparator again)
+//! carries on from them exactly where the sort left off.
+
+use super::{
+    make_work, Compare, LazySortIterator, LazySortIteratorBy, LazySortIteratorPartialFirst,
+    LazySortIteratorPartialLast, Partitioning, Shrink, Stack, Strategy, Thresholds, WorkStack,
+};
+
+/// The state of a partly done lazy sort.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint<T> {
+    /// The values not yet yielded, the lowest at the end.
+    pub data: Vec<T>,
+    /// The ranges of `data` still to be sorted, as `(lower, upper)` index
+    /// pairs with `lower >= upper`, the range holding the next values last.
+    /// When this is empty `data` is fully sorted.
+    pub work: Vec<(usize, usize)>,
+}
+
+impl<T> Checkpoint<T> {
+    /// A checkpoint of a sort that has not started.
+    pub fn new(data: Vec<T>) -> Self {
+        let mut work = make_work(data.len());
+        Checkpoint {
+            data,
+            work: drain(&mut work),
+        }
+    }
+}
+
+fn drain(work: &mut WorkStack) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    while let Some(range) = work.pop() {
+        ranges.push(range);
+    }
+    ranges.reverse();
+    ranges
+}
+
+/// # Panics
+///
+/// If the ranges do not cover the data, one after the other.
+fn rebuild(len: usize, ranges: Vec<(usize, usize)>) -> WorkStack {
+    let mut work = WorkStack::new();
+    let mut next = 0;
+    for (lower, upper) in ranges {
+        assert!(
+            upper == next && lower >= upper,
+            "range ({}, {}) does not follow on from index {}",
+            lower,
+            upper,
+            next
+        );
+        next = lower + 1;
+        work.push((lower, upper));
+    }
+    assert!(
+        work.is_empty() || next == len,
+        "ranges end at {} of {} values",
+        next,
+        len
+    );
+    work
+}
+
+macro_rules! add_checkpoint {
+    ($name:ident, $bound:path) => {
+        impl<T> $name<T>
+        where
+            T: $bound,
+        {
+            /// Stop sorting, keeping the state to resume from.  The strategy,
+            /// partitioning, thresholds and shrink settings are not kept.
+            pub fn checkpoint(mut self) -> Checkpoint<T> {
+                Checkpoint {
+                    work: drain(&mut self.work),
+                    data: self.data,
+                }
+            }
+
+            /// Carry on from a checkpoint.
+            ///
+            /// # Panics
+            ///
+            /// If the checkpoint's ranges do not cover its data.
+            pub fn resume(checkpoint: Checkpoint<T>) -> Self {
+                $name {
+                    work: rebuild(checkpoint.data.len(), checkpoint.work),
+                    data: checkpoint.data,
+                    shrink: Shrink::never(),
+                    strategy: Strategy::Lazy,
+                    partitioning: Partitioning::SinglePivot,
+                    thresholds: Thresholds::default(),
+                    taken: 0,
+                }
+            }
+        }
+    };
+}
+
+add_checkpoint!(LazySortIterator, Ord);
+add_checkpoint!(LazySortIteratorPartialFirst, PartialOrd);
+add_checkpoint!(LazySortIteratorPartialLast, PartialOrd);
+
+impl<T, F> LazySortIteratorBy<T, F>
+where
+    F: Compare<T>,
+{
+    /// Stop sorting, keeping the state to resume from.  The comparator,
+    /// strategy, partitioning, thresholds and shrink settings are not kept.
+    pub fn checkpoint(mut self) -> Checkpoint<T> {
+        Checkpoint {
+            work: drain(&mut self.work),
+            data: self.data,
+        }
+    }
+
+    /// Carry on from a checkpoint, with the comparator the sort was started
+    /// with.
+    ///
+    /// # Panics
+    ///
+    /// If the checkpoint's ranges do not cover its data.
+    pub fn resume_with(checkpoint: Checkpoint<T>, by: F) -> Self {
+        LazySortIteratorBy {
+            work: rebuild(checkpoint.data.len(), checkpoint.work),
+            data: checkpoint.data,
+            shrink: Shrink::never(),
+            strategy: Strategy::Lazy,
+            partitioning: Partitioning::SinglePivot,
+            thresholds: Thresholds::default(),
+            taken: 0,
+            by,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LazySortIterator, LazySortIteratorBy, Sorted, SortedBy};
+    use super::Checkpoint;
+
+    #[test]
+    fn checkpoint_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let mut iter = before.iter().cloned().sorted();
+        let mut after: Vec<u64> = iter.by_ref().take(10).collect();
+        let checkpoint = iter.checkpoint();
+        assert_eq!(990, checkpoint.data.len());
+        after.extend(LazySortIterator::resume(checkpoint.clone()));
+        assert_eq!((0..1000).collect::<Vec<u64>>(), after);
+
+        let iter = LazySortIteratorBy::resume_with(checkpoint, |a: &u64, b: &u64| a.cmp(b));
+        assert_eq!(vec![10, 11], iter.take(2).collect::<Vec<u64>>());
+
+        let iter = vec![3, 1, 2].sorted_by(|a: &u64, b: &u64| b.cmp(a));
+        let resumed =
+            LazySortIteratorBy::resume_with(iter.checkpoint(), |a: &u64, b: &u64| b.cmp(a));
+        assert_eq!(vec![3, 2, 1], resumed.collect::<Vec<u64>>());
+        assert_eq!(
+            vec![1, 2],
+            LazySortIterator::resume(Checkpoint::new(vec![2, 1])).collect::<Vec<u64>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn checkpoint_invalid_test() {
+        LazySortIterator::resume(Checkpoint {
+            data: vec![1, 2, 3],
+            work: vec![(1, 0)],
+        });
+    }
+}
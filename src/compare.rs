@@ -0,0 +1,228 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Comparators as values.
+//!
+//! A closure's type can't be named, so neither can an iterator sorted by one.
+//! A comparator can instead be a type implementing `Compare`, usually a unit
+//! struct, which costs nothing at runtime and gives the iterator a type that
+//! can be written down.  Every `Fn(&T, &T) -> Ordering` is a `Compare<T>`.
+//!
+//! Comparators combine with `Reverse`, `ByKey` and `Then`, which are resolved
+//! entirely at compile time.
+
+use std::cmp::Ordering;
+
+pub trait Compare<T: ?Sized> {
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+impl<T: ?Sized, F> Compare<T> for F
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    #[inline(always)]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// A comparator over several keys, each given as a closure-like `|x| key`
+/// followed by `asc` or `desc`:
+///
+/// ```text
+/// staff.sorted_by(sort_by_keys!(|x| x.dept asc, |x| x.salary desc))
+/// ```
+///
+/// Each key is compared in turn while the previous keys are equal.  A key
+/// is borrowed where it can be, so `|x| x.name` compares the `String`s in
+/// place rather than moving them.  Pass the comparator directly to a sort,
+/// from which it takes its types.
+#[macro_export]
+macro_rules! sort_by_keys {
+    (@munch [$($done:tt)*] [$($key:tt)*] asc, $($rest:tt)+) => {
+        $crate::sort_by_keys!(@munch [$($done)* (asc $($key)*)] [] $($rest)+)
+    };
+    (@munch [$($done:tt)*] [$($key:tt)*] desc, $($rest:tt)+) => {
+        $crate::sort_by_keys!(@munch [$($done)* (desc $($key)*)] [] $($rest)+)
+    };
+    (@munch [$($done:tt)*] [$($key:tt)*] asc $(,)?) => {
+        $crate::sort_by_keys!(@build $($done)* (asc $($key)*))
+    };
+    (@munch [$($done:tt)*] [$($key:tt)*] desc $(,)?) => {
+        $crate::sort_by_keys!(@build $($done)* (desc $($key)*))
+    };
+    (@munch [$($done:tt)*] [$($key:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::sort_by_keys!(@munch [$($done)*] [$($key)* $next] $($rest)*)
+    };
+    (@build $(($dir:ident | $x:ident | $($body:tt)+))+) => {
+        |a: &_, b: &_| {
+            let order = ::std::cmp::Ordering::Equal;
+            $(
+                let order = order.then_with(|| {
+                    $crate::sort_by_keys!(@compare $dir a b $x ($($body)+))
+                });
+            )+
+            order
+        }
+    };
+    (@compare asc $a:ident $b:ident $x:ident ($($body:tt)+)) => {{
+        let $x = $a;
+        let first = &($($body)+);
+        let $x = $b;
+        let second = &($($body)+);
+        ::std::cmp::Ord::cmp(first, second)
+    }};
+    (@compare desc $a:ident $b:ident $x:ident ($($body:tt)+)) => {
+        $crate::sort_by_keys!(@compare asc $b $a $x ($($body)+))
+    };
+    ($($keys:tt)+) => {
+        $crate::sort_by_keys!(@munch [] [] $($keys)+)
+    };
+}
+
+/// The natural order of `Ord`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Natural;
+
+impl<T: Ord + ?Sized> Compare<T> for Natural {
+    #[inline(always)]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// The order of `C`, reversed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reverse<C>(pub C);
+
+impl<T: ?Sized, C> Compare<T> for Reverse<C>
+where
+    C: Compare<T>,
+{
+    #[inline(always)]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.0.compare(b, a)
+    }
+}
+
+/// Order by the key `F` extracts from each value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByKey<F>(pub F);
+
+impl<T: ?Sized, K, F> Compare<T> for ByKey<F>
+where
+    K: Ord,
+    F: Fn(&T) -> K,
+{
+    #[inline(always)]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        (self.0)(a).cmp(&(self.0)(b))
+    }
+}
+
+/// Order by `A`, and values that `A` finds equal by `B`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Then<A, B>(pub A, pub B);
+
+impl<T: ?Sized, A, B> Compare<T> for Then<A, B>
+where
+    A: Compare<T>,
+    B: Compare<T>,
+{
+    #[inline(always)]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        match self.0.compare(a, b) {
+            Ordering::Equal => self.1.compare(a, b),
+            order => order,
+        }
+    }
+}
+
+/// Types that are ordered by some of their fields.  With the `derive`
+/// feature this can be derived, marking the fields with `#[sort_key]`.
+pub trait SortKey {
+    fn sort_key_cmp(&self, other: &Self) -> Ordering;
+}
+
+/// The order of `SortKey`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BySortKey;
+
+impl<T: SortKey + ?Sized> Compare<T> for BySortKey {
+    #[inline(always)]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.sort_key_cmp(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::super::{LazySortIteratorBy, SortedBy};
+    use super::{ByKey, Compare, Natural, Reverse, Then};
+
+    struct ByLength;
+
+    impl Compare<String> for ByLength {
+        fn compare(&self, a: &String, b: &String) -> Ordering {
+            a.len().cmp(&b.len())
+        }
+    }
+
+    #[test]
+    fn sorted_with_test() {
+        let before: Vec<String> = vec!["ccc", "a", "bb"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let iter: LazySortIteratorBy<String, ByLength> = before.sorted_with(ByLength);
+        assert_eq!(vec!["a", "bb", "ccc"], iter.collect::<Vec<String>>());
+
+        let after: Vec<u32> = vec![3, 1, 2].sorted_with(Natural).collect();
+        assert_eq!(vec![1, 2, 3], after);
+        let after: Vec<u32> = vec![3, 1, 2].sorted_with(Reverse(Natural)).collect();
+        assert_eq!(vec![3, 2, 1], after);
+
+        let before = vec![(1, 'b'), (0, 'c'), (1, 'a')];
+        let order = Then(
+            Reverse(ByKey(|x: &(u32, char)| x.0)),
+            ByKey(|x: &(u32, char)| x.1),
+        );
+        let after: Vec<(u32, char)> = before.sorted_with(order).collect();
+        assert_eq!(vec![(1, 'a'), (1, 'b'), (0, 'c')], after);
+        assert_eq!(
+            Ordering::Less,
+            (|a: &u32, b: &u32| b.cmp(a)).compare(&2, &1)
+        );
+    }
+
+    #[test]
+    fn sort_by_keys_test() {
+        let staff = [
+            ("ops".to_string(), 100, "cy"),
+            ("dev".to_string(), 100, "bo"),
+            ("dev".to_string(), 300, "al"),
+            ("dev".to_string(), 100, "al"),
+        ];
+        let after: Vec<&str> = staff
+            .iter()
+            .sorted_by(sort_by_keys!(|x| x.0 asc, |x| x.1 desc, |x| x.2 asc))
+            .map(|x| x.2)
+            .collect();
+        assert_eq!(vec!["al", "al", "bo", "cy"], after);
+
+        let after: Vec<u32> = vec![12, 3, 21, 30]
+            .sorted_by(sort_by_keys!(|x| x % 10 desc, |x| *x asc,))
+            .collect();
+        assert_eq!(vec![3, 12, 21, 30], after);
+    }
+}
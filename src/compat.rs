@@ -0,0 +1,148 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Methods named as in `itertools`, so that code moving from
+//! `Itertools::sorted*` mostly needs only its imports changed.
+//!
+//! Every sort here is unstable: where `itertools` promises that equal values
+//! keep their order, these do not.
+
+use std::cmp::Ordering;
+use std::iter::Take;
+
+use super::{Compare, LazySortIterator, LazySortIteratorBy, Sorted, SortedBy, SortedIterator};
+
+/// Compares the cached keys of `sorted_by_cached_key`.
+struct CachedKeyOrder;
+
+impl<K: Ord, T> Compare<(K, T)> for CachedKeyOrder {
+    #[inline(always)]
+    fn compare(&self, a: &(K, T), b: &(K, T)) -> Ordering {
+        a.0.cmp(&b.0)
+    }
+}
+
+/// A sort by a key that is computed once per value, rather than once per
+/// comparison.
+pub struct CachedKeySortIterator<T, K> {
+    iter: LazySortIteratorBy<(K, T), CachedKeyOrder>,
+}
+
+impl<T, K> Iterator for CachedKeySortIterator<T, K>
+where
+    K: Ord,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, K> SortedIterator for CachedKeySortIterator<T, K> where K: Ord {}
+
+pub trait ItertoolsCompat: Iterator + Sized {
+    fn sorted_unstable(self) -> LazySortIterator<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        self.sorted()
+    }
+
+    fn sorted_unstable_by<F>(self, by: F) -> LazySortIteratorBy<Self::Item, F>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering,
+    {
+        self.sorted_by(by)
+    }
+
+    fn sorted_by_key<K, F>(
+        self,
+        key: F,
+    ) -> LazySortIteratorBy<Self::Item, impl Fn(&Self::Item, &Self::Item) -> Ordering>
+    where
+        K: Ord,
+        F: Fn(&Self::Item) -> K,
+    {
+        self.sorted_by(move |a, b| key(a).cmp(&key(b)))
+    }
+
+    fn sorted_unstable_by_key<K, F>(
+        self,
+        key: F,
+    ) -> LazySortIteratorBy<Self::Item, impl Fn(&Self::Item, &Self::Item) -> Ordering>
+    where
+        K: Ord,
+        F: Fn(&Self::Item) -> K,
+    {
+        self.sorted_by_key(key)
+    }
+
+    /// As `sorted_by_key`, calling `key` only once for each value.  This is
+    /// faster when the key is expensive to compute.
+    fn sorted_by_cached_key<K, F>(self, key: F) -> CachedKeySortIterator<Self::Item, K>
+    where
+        K: Ord,
+        F: Fn(&Self::Item) -> K,
+    {
+        CachedKeySortIterator {
+            iter: self.map(|x| (key(&x), x)).sorted_with(CachedKeyOrder),
+        }
+    }
+
+    /// The `k` lowest values, lowest first.  Only as much of the sort is done
+    /// as is needed to find them.
+    fn k_smallest(self, k: usize) -> Take<LazySortIterator<Self::Item>>
+    where
+        Self::Item: Ord,
+    {
+        self.sorted().take(k)
+    }
+}
+
+impl<I> ItertoolsCompat for I where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::prelude::*;
+
+    #[test]
+    fn itertools_compat_test() {
+        let before = ["pear", "fig", "apple", "kiwi"];
+        let by_len: Vec<&str> = before
+            .iter()
+            .cloned()
+            .sorted_by_key(|s| (s.len(), *s))
+            .collect();
+        assert_eq!(vec!["fig", "kiwi", "pear", "apple"], by_len);
+
+        let calls = ::std::cell::Cell::new(0);
+        let cached: Vec<&str> = before
+            .iter()
+            .cloned()
+            .sorted_by_cached_key(|s| {
+                calls.set(calls.get() + 1);
+                s.len()
+            })
+            .take(1)
+            .collect();
+        assert_eq!(vec!["fig"], cached);
+        assert_eq!(4, calls.get());
+
+        let smallest: Vec<u32> = vec![9, 3, 7, 1, 8].into_iter().k_smallest(2).collect();
+        assert_eq!(vec![1, 3], smallest);
+    }
+}
@@ -0,0 +1,220 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Starting a lazy sort without holding up an executor.
+//!
+//! Collecting a large iterator, and the first partition of everything it
+//! yielded, each take time linear in the number of values, which inside a
+//! single `poll` stalls every other task on the thread.  `CooperativeSort` is
+//! a `Future` that does both a bounded batch at a time: each `poll` takes or
+//! compares at most that many values, then wakes its own task and returns
+//! `Pending`, so the executor runs others in between.  It resolves to the
+//! lazy sort, whose later partitions are each smaller than the first.
+
+use std::cmp::Ordering;
+use std::cmp::Ordering::Greater;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::{make_work, LazySortIteratorBy, Stack};
+
+type NaturalOrder<T> = fn(&T, &T) -> Ordering;
+
+/// Collects `source` and partitions the values around a pivot, `batch`
+/// values per `poll`, then resolves to their lazy sort.
+pub struct CooperativeSort<I, F>
+where
+    I: Iterator,
+{
+    source: Option<I>,
+    data: Vec<I::Item>,
+    by: Option<F>,
+    batch: usize,
+    // The partition in progress, of the whole buffer around the pivot at its
+    // end: the next index to compare, and where the next value above the
+    // pivot goes.
+    next: usize,
+    above: usize,
+}
+
+impl<I, F> CooperativeSort<I, F>
+where
+    I: Iterator,
+    F: Fn(&I::Item, &I::Item) -> Ordering,
+{
+    fn new(source: I, batch: usize, by: F) -> Self {
+        CooperativeSort {
+            source: Some(source),
+            data: Vec::new(),
+            by: Some(by),
+            batch: batch.max(1),
+            next: 0,
+            above: 0,
+        }
+    }
+
+    /// Take up to `budget` values from the source, returning what is left
+    /// of the budget.  Once it runs dry the pivot is put in place.
+    fn collect(&mut self, mut budget: usize) -> usize {
+        if let Some(ref mut source) = self.source {
+            while budget > 0 {
+                match source.next() {
+                    Some(value) => self.data.push(value),
+                    None => break,
+                }
+                budget -= 1;
+            }
+            if budget == 0 {
+                return 0;
+            }
+        } else {
+            return budget;
+        }
+        self.source = None;
+        if let Some(lower) = self.data.len().checked_sub(1) {
+            self.data.swap(lower, lower / 2);
+        }
+        budget
+    }
+
+    /// Compare up to `budget` values with the pivot, returning whether the
+    /// partition is done.
+    fn partition(&mut self, budget: usize) -> bool {
+        let lower = match self.data.len().checked_sub(1) {
+            Some(lower) => lower,
+            None => return true,
+        };
+        let by = self.by.as_ref().expect("Not yet resolved");
+        let end = lower.min(self.next.saturating_add(budget));
+        while self.next < end {
+            if by(&self.data[self.next], &self.data[lower]) == Greater {
+                self.data.swap(self.next, self.above);
+                self.above += 1;
+            }
+            self.next += 1;
+        }
+        self.next == lower
+    }
+
+    fn resolve(&mut self) -> LazySortIteratorBy<I::Item, F> {
+        let by = self
+            .by
+            .take()
+            .expect("CooperativeSort polled after completion");
+        let data = mem::take(&mut self.data);
+        let mut iter = LazySortIteratorBy::new(Vec::new(), by);
+        let mut work = make_work(0);
+        if let Some(lower) = data.len().checked_sub(1) {
+            // The values above the pivot, the pivot, then those below it,
+            // which hold the next values and are partitioned first.
+            let p = self.above;
+            iter.data = data;
+            iter.data.swap(p, lower);
+            if p > 0 {
+                work.push((p - 1, 0));
+            }
+            work.push((p, p));
+            if lower > p {
+                work.push((lower, p + 1));
+            }
+        }
+        iter.work = work;
+        iter
+    }
+}
+
+// Nothing is pinned in place: the values are only ever moved through `&mut`.
+impl<I, F> Unpin for CooperativeSort<I, F> where I: Iterator {}
+
+impl<I, F> Future for CooperativeSort<I, F>
+where
+    I: Iterator,
+    F: Fn(&I::Item, &I::Item) -> Ordering,
+{
+    type Output = LazySortIteratorBy<I::Item, F>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let budget = this.collect(this.batch);
+        if this.source.is_none() && this.partition(budget) {
+            return Poll::Ready(this.resolve());
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+pub trait SortedCooperatively: Iterator + Sized {
+    /// A future that collects these values and starts their lazy sort,
+    /// taking or comparing at most `batch` of them each time it is polled.
+    fn sorted_cooperatively(self, batch: usize) -> CooperativeSort<Self, NaturalOrder<Self::Item>>
+    where
+        Self::Item: Ord,
+    {
+        self.sorted_cooperatively_by(batch, Ord::cmp)
+    }
+
+    fn sorted_cooperatively_by<F>(self, batch: usize, by: F) -> CooperativeSort<Self, F>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering,
+    {
+        CooperativeSort::new(self, batch, by)
+    }
+}
+
+impl<I> SortedCooperatively for I where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+
+    use super::SortedCooperatively;
+
+    /// Poll `future` to completion, returning its output and the number of
+    /// times it was pending.
+    fn block_on<G: Future + Unpin>(mut future: G) -> (G::Output, usize) {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut pending = 0;
+        loop {
+            match Pin::new(&mut future).poll(&mut cx) {
+                Poll::Ready(output) => return (output, pending),
+                Poll::Pending => pending += 1,
+            }
+        }
+    }
+
+    #[test]
+    fn sorted_cooperatively_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let (iter, pending) = block_on(before.iter().cloned().sorted_cooperatively(100));
+        // Ten polls to collect the values and ten more to partition them.
+        assert_eq!(19, pending);
+        assert_eq!((0..1000).collect::<Vec<u64>>(), iter.collect::<Vec<u64>>());
+
+        let (iter, _) = block_on(
+            before
+                .into_iter()
+                .sorted_cooperatively_by(7, |a, b| b.cmp(a)),
+        );
+        assert_eq!(
+            (0..1000).rev().collect::<Vec<u64>>(),
+            iter.collect::<Vec<u64>>()
+        );
+
+        for n in 0..4 {
+            let (iter, _) = block_on((0..n).rev().sorted_cooperatively(0));
+            assert_eq!((0..n).collect::<Vec<u32>>(), iter.collect::<Vec<u32>>());
+        }
+    }
+}
@@ -0,0 +1,344 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Counting sort for values, or keys, from a small domain.
+//!
+//! A single pass over the input counts each value, and the sorted sequence
+//! is then replayed from the counts without a single comparison.
+
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+use super::SortedIterator;
+
+/// A type whose values map to, and back from, the indexes `0..DOMAIN` in
+/// order.
+pub trait CountingKey: Copy {
+    /// The number of distinct values.
+    const DOMAIN: usize;
+
+    fn index(self) -> usize;
+
+    fn from_index(index: usize) -> Self;
+}
+
+macro_rules! counting_key {
+    ($t:ty, $min:expr) => {
+        impl CountingKey for $t {
+            const DOMAIN: usize = 1 << (8 * std::mem::size_of::<$t>());
+
+            #[inline]
+            fn index(self) -> usize {
+                (self as isize - $min as isize) as usize
+            }
+
+            #[inline]
+            fn from_index(index: usize) -> Self {
+                (index as isize + $min as isize) as $t
+            }
+        }
+    };
+}
+
+counting_key!(u8, 0);
+counting_key!(u16, 0);
+counting_key!(i8, i8::MIN);
+counting_key!(i16, i16::MIN);
+
+impl CountingKey for bool {
+    const DOMAIN: usize = 2;
+
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    #[inline]
+    fn from_index(index: usize) -> Self {
+        index != 0
+    }
+}
+
+impl CountingKey for Ordering {
+    const DOMAIN: usize = 3;
+
+    #[inline]
+    fn index(self) -> usize {
+        (self as i8 + 1) as usize
+    }
+
+    #[inline]
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => Ordering::Less,
+            1 => Ordering::Equal,
+            _ => Ordering::Greater,
+        }
+    }
+}
+
+/// A key with a handful of values, such as a fieldless enum, each mapped to
+/// its position in `0..VARIANTS`.
+pub trait SmallDomain {
+    const VARIANTS: usize;
+
+    fn variant(&self) -> usize;
+}
+
+impl SmallDomain for bool {
+    const VARIANTS: usize = 2;
+
+    #[inline]
+    fn variant(&self) -> usize {
+        self.index()
+    }
+}
+
+impl SmallDomain for Ordering {
+    const VARIANTS: usize = 3;
+
+    #[inline]
+    fn variant(&self) -> usize {
+        self.index()
+    }
+}
+
+/// Yields values in order from a count of each value.
+pub struct CountingSortIterator<T> {
+    counts: Vec<usize>,
+    index: usize,
+    remaining: usize,
+    marker: PhantomData<T>,
+}
+
+/// Yields values in order of a key in `0..domain`.  Values with equal keys
+/// are yielded in the order they were given.
+pub struct CountingSortIteratorByKey<T> {
+    data: Vec<Option<T>>,
+    order: Vec<usize>,
+    next: usize,
+}
+
+pub trait SortedCounting {
+    type Item;
+
+    fn sorted_counting(self) -> CountingSortIterator<Self::Item>
+    where
+        Self::Item: CountingKey;
+
+    /// Sort by a key that is always less than `domain`.  Panics if a key is
+    /// out of range.
+    fn sorted_counting_by_key<F>(
+        self,
+        domain: usize,
+        key: F,
+    ) -> CountingSortIteratorByKey<Self::Item>
+    where
+        F: Fn(&Self::Item) -> usize;
+
+    /// Sort by a key from a small domain.  Values with equal keys keep the
+    /// order they were given in.
+    fn sorted_by_domain<K, F>(self, key: F) -> CountingSortIteratorByKey<Self::Item>
+    where
+        K: SmallDomain,
+        F: Fn(&Self::Item) -> K;
+}
+
+impl<T, I> SortedCounting for I
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn sorted_counting(self) -> CountingSortIterator<T>
+    where
+        T: CountingKey,
+    {
+        let mut counts = vec![0usize; T::DOMAIN];
+        let mut remaining = 0;
+        for value in self {
+            counts[value.index()] += 1;
+            remaining += 1;
+        }
+        CountingSortIterator {
+            counts,
+            index: 0,
+            remaining,
+            marker: PhantomData,
+        }
+    }
+
+    fn sorted_counting_by_key<F>(self, domain: usize, key: F) -> CountingSortIteratorByKey<T>
+    where
+        F: Fn(&T) -> usize,
+    {
+        let mut data = Vec::new();
+        let mut keys = Vec::new();
+        let mut offsets = vec![0usize; domain + 1];
+        for value in self {
+            let k = key(&value);
+            assert!(k < domain, "Key {} out of range 0..{}", k, domain);
+            offsets[k + 1] += 1;
+            keys.push(k);
+            data.push(Some(value));
+        }
+        for k in 0..domain {
+            offsets[k + 1] += offsets[k];
+        }
+        let mut order = vec![0usize; data.len()];
+        for (i, k) in keys.into_iter().enumerate() {
+            order[offsets[k]] = i;
+            offsets[k] += 1;
+        }
+        CountingSortIteratorByKey {
+            data,
+            order,
+            next: 0,
+        }
+    }
+
+    fn sorted_by_domain<K, F>(self, key: F) -> CountingSortIteratorByKey<T>
+    where
+        K: SmallDomain,
+        F: Fn(&T) -> K,
+    {
+        self.sorted_counting_by_key(K::VARIANTS, |x| key(x).variant())
+    }
+}
+
+impl<T> Iterator for CountingSortIterator<T>
+where
+    T: CountingKey,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        while self.counts[self.index] == 0 {
+            self.index += 1;
+        }
+        self.counts[self.index] -= 1;
+        self.remaining -= 1;
+        Some(T::from_index(self.index))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> SortedIterator for CountingSortIterator<T> where T: CountingKey {}
+
+impl<T> Iterator for CountingSortIteratorByKey<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let i = *self.order.get(self.next)?;
+        self.next += 1;
+        self.data[i].take()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.order.len() - self.next;
+        (l, Some(l))
+    }
+}
+
+impl<T> SortedIterator for CountingSortIteratorByKey<T> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use std::cmp::Ordering;
+
+    use super::{SmallDomain, SortedCounting};
+
+    #[test]
+    fn sorted_counting_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u8> = (0..1000).map(|_| rng.gen()).collect();
+        let mut expected = before.clone();
+        expected.sort();
+        let after: Vec<u8> = before.into_iter().sorted_counting().collect();
+        assert_eq!(expected, after);
+
+        let after: Vec<i16> = vec![3i16, -7, 0, i16::MIN, 3]
+            .into_iter()
+            .sorted_counting()
+            .collect();
+        assert_eq!(vec![i16::MIN, -7, 0, 3, 3], after);
+    }
+
+    #[test]
+    fn sorted_counting_by_key_test() {
+        let before = vec![(2, 'a'), (0, 'b'), (2, 'c'), (1, 'd'), (0, 'e')];
+        let after: Vec<char> = before
+            .into_iter()
+            .sorted_counting_by_key(3, |x| x.0)
+            .map(|x| x.1)
+            .collect();
+        assert_eq!(vec!['b', 'e', 'd', 'a', 'c'], after);
+    }
+
+    #[derive(Clone, Copy)]
+    enum Status {
+        Todo,
+        Doing,
+        Done,
+    }
+
+    impl SmallDomain for Status {
+        const VARIANTS: usize = 3;
+
+        fn variant(&self) -> usize {
+            *self as usize
+        }
+    }
+
+    #[test]
+    fn sorted_by_domain_test() {
+        let before = vec![
+            (Status::Done, 1),
+            (Status::Todo, 2),
+            (Status::Doing, 3),
+            (Status::Done, 4),
+            (Status::Todo, 5),
+        ];
+        let after: Vec<u32> = before
+            .into_iter()
+            .sorted_by_domain(|x| x.0)
+            .map(|x| x.1)
+            .collect();
+        assert_eq!(vec![2, 5, 3, 1, 4], after);
+
+        let after: Vec<bool> = vec![true, false, true]
+            .into_iter()
+            .sorted_counting()
+            .collect();
+        assert_eq!(vec![false, true, true], after);
+        let after: Vec<Ordering> = vec![Ordering::Greater, Ordering::Less, Ordering::Equal]
+            .into_iter()
+            .sorted_counting()
+            .collect();
+        assert_eq!(
+            vec![Ordering::Less, Ordering::Equal, Ordering::Greater],
+            after
+        );
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazily sorted draining of a `Vec`.
+//!
+//! The vector is partitioned in place, exactly as the owned iterators
+//! partition their own buffer, and each value is popped from its end as it
+//! is yielded.  Whatever has not been yielded is simply still in the vector.
+
+use std::cmp::Ordering;
+
+use super::{make_work, qsort, SortedIterator, Stack, WorkStack};
+
+pub struct VecDrainSorted<'a, T: 'a> {
+    data: &'a mut Vec<T>,
+    work: WorkStack,
+}
+
+pub struct VecDrainSortedBy<'a, T: 'a, F> {
+    data: &'a mut Vec<T>,
+    work: WorkStack,
+    by: F,
+}
+
+pub trait LazyDrainSorted<T> {
+    /// Remove and yield the values in sorted order.  If the iterator is
+    /// dropped early the values not yet yielded are left in the vector, in an
+    /// unspecified order.
+    fn lazy_drain_sorted(&mut self) -> VecDrainSorted<'_, T>
+    where
+        T: Ord;
+
+    fn lazy_drain_sorted_by<F>(&mut self, by: F) -> VecDrainSortedBy<'_, T, F>
+    where
+        F: Fn(&T, &T) -> Ordering;
+}
+
+impl<T> LazyDrainSorted<T> for Vec<T> {
+    fn lazy_drain_sorted(&mut self) -> VecDrainSorted<'_, T>
+    where
+        T: Ord,
+    {
+        VecDrainSorted {
+            work: make_work(self.len()),
+            data: self,
+        }
+    }
+
+    fn lazy_drain_sorted_by<F>(&mut self, by: F) -> VecDrainSortedBy<'_, T, F>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        VecDrainSortedBy {
+            work: make_work(self.len()),
+            data: self,
+            by,
+        }
+    }
+}
+
+impl<'a, T> Iterator for VecDrainSorted<'a, T>
+where
+    T: Ord,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let (lower, upper) = self.work.pop()?;
+        Some(qsort(&Ord::cmp, self.data, &mut self.work, lower, upper))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T> SortedIterator for VecDrainSorted<'a, T> where T: Ord {}
+
+impl<'a, T, F> Iterator for VecDrainSortedBy<'a, T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let (lower, upper) = self.work.pop()?;
+        Some(qsort(&self.by, self.data, &mut self.work, lower, upper))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T, F> SortedIterator for VecDrainSortedBy<'a, T, F> where F: Fn(&T, &T) -> Ordering {}
+
+#[cfg(test)]
+mod tests {
+    use super::LazyDrainSorted;
+
+    #[test]
+    fn lazy_drain_sorted_test() {
+        let mut values: Vec<u32> = (0..100).map(|i| (i * 37) % 100).collect();
+        let smallest: Vec<u32> = values.lazy_drain_sorted().take(3).collect();
+        assert_eq!(vec![0, 1, 2], smallest);
+        assert_eq!(97, values.len());
+        values.sort();
+        assert_eq!((3..100).collect::<Vec<u32>>(), values);
+
+        let largest: Vec<u32> = values.lazy_drain_sorted_by(|a, b| b.cmp(a)).collect();
+        assert_eq!((3..100).rev().collect::<Vec<u32>>(), largest);
+        assert!(values.is_empty());
+    }
+}
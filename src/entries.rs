@@ -0,0 +1,128 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy sorting of the entries of a map by their values.
+//!
+//! The common case is the entries with the highest counts, which is
+//! `counts.sorted_by_value_desc().take(n)`.  A map is consumed for its
+//! entries, or borrowed for references to them.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+
+use super::compare::Reverse;
+use super::{Compare, LazySortIteratorBy};
+
+/// Order `(key, value)` pairs by their values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByValue;
+
+impl<K, V> Compare<(K, V)> for ByValue
+where
+    V: Ord,
+{
+    #[inline(always)]
+    fn compare(&self, a: &(K, V), b: &(K, V)) -> Ordering {
+        a.1.cmp(&b.1)
+    }
+}
+
+pub trait SortedByValue {
+    type Key;
+    type Value;
+
+    /// The entries, lowest value first.
+    fn sorted_by_value(self) -> LazySortIteratorBy<(Self::Key, Self::Value), ByValue>
+    where
+        Self::Value: Ord;
+
+    /// The entries, highest value first.
+    fn sorted_by_value_desc(self) -> LazySortIteratorBy<(Self::Key, Self::Value), Reverse<ByValue>>
+    where
+        Self::Value: Ord;
+}
+
+macro_rules! sorted_by_value {
+    () => {
+        fn sorted_by_value(self) -> LazySortIteratorBy<(Self::Key, Self::Value), ByValue>
+        where
+            Self::Value: Ord,
+        {
+            LazySortIteratorBy::new(self.into_iter().collect(), ByValue)
+        }
+
+        fn sorted_by_value_desc(
+            self,
+        ) -> LazySortIteratorBy<(Self::Key, Self::Value), Reverse<ByValue>>
+        where
+            Self::Value: Ord,
+        {
+            LazySortIteratorBy::new(self.into_iter().collect(), Reverse(ByValue))
+        }
+    };
+}
+
+impl<K, V, S> SortedByValue for HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Key = K;
+    type Value = V;
+
+    sorted_by_value!();
+}
+
+impl<'a, K, V, S> SortedByValue for &'a HashMap<K, V, S>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    type Key = &'a K;
+    type Value = &'a V;
+
+    sorted_by_value!();
+}
+
+impl<K, V> SortedByValue for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    sorted_by_value!();
+}
+
+impl<'a, K, V> SortedByValue for &'a BTreeMap<K, V> {
+    type Key = &'a K;
+    type Value = &'a V;
+
+    sorted_by_value!();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use super::SortedByValue;
+
+    #[test]
+    fn sorted_by_value_test() {
+        let mut counts = HashMap::new();
+        for word in "a b c a b a d".split(' ') {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+        let top: Vec<(&&str, &u32)> = (&counts).sorted_by_value_desc().take(2).collect();
+        assert_eq!(vec![(&"a", &3), (&"b", &2)], top);
+
+        let counts: BTreeMap<&str, u32> = counts.into_iter().collect();
+        let after: Vec<(&str, u32)> = counts.sorted_by_value().skip(2).collect();
+        assert_eq!(vec![("b", 2), ("a", 3)], after);
+    }
+}
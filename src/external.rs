@@ -0,0 +1,932 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Sorting of data sets larger than the available memory.
+//!
+//! Values are buffered until the memory budget is reached, each full buffer is
+//! sorted and spilled to a temporary "run" file, and the runs are then lazily
+//! merged.  The final buffer never touches the disk; it takes part in the merge
+//! as an ordinary lazy sort, so only as much of it is sorted as is consumed.
+//!
+//! A run file is only opened to be read.  If there are more runs than can be
+//! merged at once, `MAX_FAN_IN`, they are first merged that many at a time
+//! into longer runs, in as many passes as it takes, so the number of files
+//! open at once stays bounded however small the budget.
+
+use std::cmp::Ordering;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use super::{make_work, qsort, Compare, LazySortIteratorBy, SortedIterator, Stack, WorkStack};
+
+/// Values that can be written to, and read back from, a run file.
+pub trait Spill: Sized {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()>;
+
+    /// Read the next value, returning `None` at a clean end of file.
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Option<Self>>;
+
+    /// The approximate number of bytes of memory this value occupies, used to
+    /// measure buffers against the memory budget.
+    fn memory_size(&self) -> usize {
+        mem::size_of::<Self>()
+    }
+}
+
+/// Fill `buf`, returning `false` if the reader was already at end of file.
+fn read_exact_or_eof<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < buf.len() {
+        match r.read(&mut buf[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+macro_rules! spill_number {
+    ($($t:ty),*) => {
+        $(
+            impl Spill for $t {
+                fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                    w.write_all(&self.to_le_bytes())
+                }
+
+                fn read_from<R: Read>(r: &mut R) -> io::Result<Option<Self>> {
+                    let mut buf = [0u8; mem::size_of::<$t>()];
+                    if read_exact_or_eof(r, &mut buf)? {
+                        Ok(Some(<$t>::from_le_bytes(buf)))
+                    } else {
+                        Ok(None)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+spill_number!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+impl Spill for Vec<u8> {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (self.len() as u64).write_to(w)?;
+        w.write_all(self)
+    }
+
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Option<Self>> {
+        match u64::read_from(r)? {
+            Some(len) => {
+                let mut buf = vec![0u8; len as usize];
+                r.read_exact(&mut buf)?;
+                Ok(Some(buf))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        mem::size_of::<Self>() + self.len()
+    }
+}
+
+impl Spill for String {
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (self.len() as u64).write_to(w)?;
+        w.write_all(self.as_bytes())
+    }
+
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Option<Self>> {
+        match Vec::<u8>::read_from(r)? {
+            Some(bytes) => String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            None => Ok(None),
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        mem::size_of::<Self>() + self.len()
+    }
+}
+
+impl<A, B> Spill for (A, B)
+where
+    A: Spill,
+    B: Spill,
+{
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.0.write_to(w)?;
+        self.1.write_to(w)
+    }
+
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Option<Self>> {
+        match A::read_from(r)? {
+            Some(a) => match B::read_from(r)? {
+                Some(b) => Ok(Some((a, b))),
+                None => Err(io::ErrorKind::UnexpectedEof.into()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        self.0.memory_size() + self.1.memory_size()
+    }
+}
+
+/// The most run files merged, and so open, at once.
+pub const MAX_FAN_IN: usize = 64;
+
+/// The comparator used by `ExternalSorter::sort`.
+pub type NaturalOrder<T> = fn(&T, &T) -> Ordering;
+
+/// Orders lines, held as `String`s, by a comparator of `&str`; see
+/// `ExternalSorter::sort_lines_by`.
+#[derive(Clone, Copy, Debug)]
+pub struct LineOrder<F>(pub F);
+
+impl<F> Compare<String> for LineOrder<F>
+where
+    F: Fn(&str, &str) -> Ordering,
+{
+    #[inline(always)]
+    fn compare(&self, a: &String, b: &String) -> Ordering {
+        (self.0)(a, b)
+    }
+}
+
+/// How values are written to, and read back from, run files.
+pub trait SpillCodec<T> {
+    fn encode<W: Write>(&self, value: &T, w: &mut W) -> io::Result<()>;
+
+    /// Read the next value, returning `None` at a clean end of file.
+    fn decode<R: Read>(&self, r: &mut R) -> io::Result<Option<T>>;
+
+    /// The approximate number of bytes of memory `value` occupies.
+    fn memory_size(&self, value: &T) -> usize {
+        let _ = value;
+        mem::size_of::<T>()
+    }
+}
+
+impl<T, C> SpillCodec<T> for &C
+where
+    C: SpillCodec<T>,
+{
+    fn encode<W: Write>(&self, value: &T, w: &mut W) -> io::Result<()> {
+        (**self).encode(value, w)
+    }
+
+    fn decode<R: Read>(&self, r: &mut R) -> io::Result<Option<T>> {
+        (**self).decode(r)
+    }
+
+    fn memory_size(&self, value: &T) -> usize {
+        (**self).memory_size(value)
+    }
+}
+
+/// The codec used by default, which delegates to the value's `Spill`
+/// implementation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultCodec;
+
+impl<T> SpillCodec<T> for DefaultCodec
+where
+    T: Spill,
+{
+    fn encode<W: Write>(&self, value: &T, w: &mut W) -> io::Result<()> {
+        value.write_to(w)
+    }
+
+    fn decode<R: Read>(&self, r: &mut R) -> io::Result<Option<T>> {
+        T::read_from(r)
+    }
+
+    fn memory_size(&self, value: &T) -> usize {
+        value.memory_size()
+    }
+}
+
+/// Writes the in-memory representation of fixed-size `Copy` values directly,
+/// with no encoding.  The run files are only ever read back by the process
+/// that wrote them, so every value read is one that was written.
+#[derive(Clone, Copy, Debug)]
+pub struct RawCodec(());
+
+impl RawCodec {
+    /// # Safety
+    ///
+    /// The values sorted with this codec must not contain padding bytes, as
+    /// these are uninitialised and cannot be written out.
+    ///
+    /// The bytes read back from the run files are taken to be values without
+    /// any check.  A file that is corrupted, or changed by another process
+    /// while the sort runs, gives whatever bytes it then holds: for a `bool`,
+    /// a `char`, an enum or a reference these may not be a valid value at
+    /// all, which is undefined behaviour.  Unless the run directory is
+    /// trusted not to change under the sort, use this codec only for types
+    /// that every bit pattern is valid for, such as integers and arrays of
+    /// them.  A file cut short within a value is an error; one cut between
+    /// values just yields fewer of them.
+    pub unsafe fn new() -> Self {
+        RawCodec(())
+    }
+}
+
+impl<T> SpillCodec<T> for RawCodec
+where
+    T: Copy,
+{
+    fn encode<W: Write>(&self, value: &T, w: &mut W) -> io::Result<()> {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>())
+        };
+        w.write_all(bytes)
+    }
+
+    fn decode<R: Read>(&self, r: &mut R) -> io::Result<Option<T>> {
+        let mut value = mem::MaybeUninit::<T>::uninit();
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, mem::size_of::<T>())
+        };
+        if read_exact_or_eof(r, bytes)? {
+            Ok(Some(unsafe { value.assume_init() }))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Wraps run files as they are written and read, so that runs can be
+/// compressed on disk.  Implement this to plug in a compression format such as
+/// lz4 or zstd; the default, `Uncompressed`, writes values as they are.
+///
+/// Values are written and read a few bytes at a time, so both the encoder and
+/// the decoder should buffer.
+pub trait Compression {
+    fn encoder(&self, file: File) -> io::Result<Box<dyn Encoder>>;
+
+    fn decoder(&self, file: File) -> io::Result<Box<dyn Read>>;
+}
+
+/// A writer that must be explicitly finished once a run is complete, for
+/// formats that write a trailer.
+pub trait Encoder: Write {
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+impl Encoder for BufWriter<File> {
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+/// Run files hold the values exactly as written by `Spill::write_to`.
+pub struct Uncompressed;
+
+impl Compression for Uncompressed {
+    fn encoder(&self, file: File) -> io::Result<Box<dyn Encoder>> {
+        Ok(Box::new(BufWriter::new(file)))
+    }
+
+    fn decoder(&self, file: File) -> io::Result<Box<dyn Read>> {
+        Ok(Box::new(file))
+    }
+}
+
+static RUN_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A sorted run on disk, deleted when dropped.  The file is closed until
+/// `open` is called to read it.
+struct Run<T> {
+    path: PathBuf,
+    reader: Option<BufReader<Box<dyn Read>>>,
+    remaining: usize,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T> Run<T> {
+    fn spill<C>(
+        dir: &Path,
+        compression: &dyn Compression,
+        codec: &C,
+        data: &[T],
+    ) -> io::Result<Self>
+    where
+        C: SpillCodec<T>,
+    {
+        Self::create(dir, compression, |w| {
+            for value in data {
+                codec.encode(value, w)?;
+            }
+            Ok(data.len())
+        })
+    }
+
+    /// Merge `runs` into one longer run.
+    fn merge<F, C>(
+        dir: &Path,
+        compression: &dyn Compression,
+        codec: &C,
+        by: &F,
+        mut runs: Vec<Run<T>>,
+    ) -> io::Result<Self>
+    where
+        F: Compare<T>,
+        C: SpillCodec<T>,
+    {
+        for run in runs.iter_mut() {
+            run.open(compression)?;
+        }
+        let mut merge = Merge {
+            memory: Vec::new(),
+            work: WorkStack::new(),
+            remaining: runs.iter().map(|run| run.remaining).sum(),
+            runs,
+            heads: Vec::new(),
+            heap: Vec::new(),
+            error: None,
+            by: |a: &T, b: &T| by.compare(a, b),
+            codec,
+        };
+        merge.start()?;
+        Self::create(dir, compression, |w| {
+            let mut written = 0;
+            while let Some(value) = merge.next() {
+                codec.encode(&value?, w)?;
+                written += 1;
+            }
+            Ok(written)
+        })
+    }
+
+    /// Write a new run file with `write`, which returns the number of values
+    /// written.
+    fn create<G>(dir: &Path, compression: &dyn Compression, write: G) -> io::Result<Self>
+    where
+        G: FnOnce(&mut Box<dyn Encoder>) -> io::Result<usize>,
+    {
+        let (path, file) = loop {
+            let path = dir.join(format!(
+                "lazysort-{}-{}.run",
+                process::id(),
+                RUN_COUNTER.fetch_add(1, AtomicOrdering::Relaxed)
+            ));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        let written = compression.encoder(file).and_then(|mut writer| {
+            let written = write(&mut writer)?;
+            writer.finish()?;
+            Ok(written)
+        });
+        match written {
+            Ok(written) => Ok(Run {
+                path,
+                reader: None,
+                remaining: written,
+                _marker: std::marker::PhantomData,
+            }),
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                Err(e)
+            }
+        }
+    }
+
+    fn open(&mut self, compression: &dyn Compression) -> io::Result<()> {
+        let file = File::open(&self.path)?;
+        self.reader = Some(BufReader::new(compression.decoder(file)?));
+        Ok(())
+    }
+
+    fn next<C>(&mut self, codec: &C) -> io::Result<Option<T>>
+    where
+        C: SpillCodec<T>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let reader = self.reader.as_mut().expect("Run opened before it is read");
+        match codec.decode(reader)? {
+            Some(value) => {
+                self.remaining -= 1;
+                Ok(Some(value))
+            }
+            None => Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+}
+
+impl<T> Drop for Run<T> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Sorts values within a memory budget, spilling sorted runs to temporary
+/// files when the budget is exceeded.
+pub struct ExternalSorter {
+    budget: usize,
+    temp_dir: PathBuf,
+    compression: Box<dyn Compression>,
+}
+
+impl ExternalSorter {
+    /// Create a sorter that buffers at most `budget` bytes (as measured by
+    /// `SpillCodec::memory_size`) before spilling a run to disk.
+    pub fn new(budget: usize) -> Self {
+        ExternalSorter {
+            budget,
+            temp_dir: env::temp_dir(),
+            compression: Box::new(Uncompressed),
+        }
+    }
+
+    /// Set the directory in which run files are created, the system temporary
+    /// directory by default.
+    pub fn temp_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.temp_dir = dir.into();
+        self
+    }
+
+    /// Set how run files are compressed, `Uncompressed` by default.
+    pub fn compression<C>(mut self, compression: C) -> Self
+    where
+        C: Compression + 'static,
+    {
+        self.compression = Box::new(compression);
+        self
+    }
+
+    pub fn sort<T, I>(&self, iter: I) -> io::Result<ExternalSortIterator<T, NaturalOrder<T>>>
+    where
+        T: Ord + Spill,
+        I: IntoIterator<Item = T>,
+    {
+        self.sort_by(iter, Ord::cmp)
+    }
+
+    pub fn sort_by<T, I, F>(&self, iter: I, by: F) -> io::Result<ExternalSortIterator<T, F>>
+    where
+        T: Spill,
+        I: IntoIterator<Item = T>,
+        F: Fn(&T, &T) -> Ordering,
+    {
+        self.sort_by_codec(iter, by, DefaultCodec)
+    }
+
+    /// Sort the lines read from `reader`, without their line endings.
+    pub fn sort_lines<R>(
+        &self,
+        reader: R,
+    ) -> io::Result<ExternalSortIterator<String, NaturalOrder<String>>>
+    where
+        R: BufRead,
+    {
+        self.sort_lines_with(reader, Ord::cmp)
+    }
+
+    /// Sort the lines read from `reader` by `by`, which compares them as
+    /// `&str`, so that it can compare any part of them in place:
+    /// `|a, b| a.split('\t').nth(1).cmp(&b.split('\t').nth(1))` orders them by
+    /// their second field.
+    pub fn sort_lines_by<R, F>(
+        &self,
+        reader: R,
+        by: F,
+    ) -> io::Result<ExternalSortIterator<String, LineOrder<F>>>
+    where
+        R: BufRead,
+        F: Fn(&str, &str) -> Ordering,
+    {
+        self.sort_lines_with(reader, LineOrder(by))
+    }
+
+    fn sort_lines_with<R, F>(&self, reader: R, by: F) -> io::Result<ExternalSortIterator<String, F>>
+    where
+        R: BufRead,
+        F: Compare<String>,
+    {
+        let mut error = None;
+        let sorted = {
+            let lines = reader.lines().map_while(|line| match line {
+                Ok(line) => Some(line),
+                Err(e) => {
+                    error = Some(e);
+                    None
+                }
+            });
+            self.sort_with_codec(lines, by, DefaultCodec)?
+        };
+        match error {
+            Some(e) => Err(e),
+            None => Ok(sorted),
+        }
+    }
+
+    /// Sort using `codec` to write values to, and read them from, the run
+    /// files.
+    pub fn sort_by_codec<T, I, F, C>(
+        &self,
+        iter: I,
+        by: F,
+        codec: C,
+    ) -> io::Result<ExternalSortIterator<T, F, C>>
+    where
+        I: IntoIterator<Item = T>,
+        F: Fn(&T, &T) -> Ordering,
+        C: SpillCodec<T>,
+    {
+        self.sort_with_codec(iter, by, codec)
+    }
+
+    /// As `sort_by_codec`, for any `Compare`.
+    fn sort_with_codec<T, I, F, C>(
+        &self,
+        iter: I,
+        by: F,
+        codec: C,
+    ) -> io::Result<ExternalSortIterator<T, F, C>>
+    where
+        I: IntoIterator<Item = T>,
+        F: Compare<T>,
+        C: SpillCodec<T>,
+    {
+        let mut runs = Vec::new();
+        let mut buffer = Vec::new();
+        let mut used = 0;
+        let mut len = 0;
+
+        for value in iter {
+            used += codec.memory_size(&value);
+            len += 1;
+            buffer.push(value);
+            if used >= self.budget {
+                buffer.sort_unstable_by(|a, b| by.compare(a, b));
+                runs.push(Run::spill(
+                    &self.temp_dir,
+                    &*self.compression,
+                    &codec,
+                    &buffer,
+                )?);
+                buffer.clear();
+                used = 0;
+            }
+        }
+
+        if runs.is_empty() {
+            return Ok(ExternalSortIterator {
+                inner: Inner::Memory(LazySortIteratorBy::new(buffer, by)),
+            });
+        }
+
+        // The oldest runs are merged first, and the merged run queued behind
+        // the rest, so each pass over the values merges runs of about the
+        // same length.
+        while runs.len() > MAX_FAN_IN {
+            let batch: Vec<Run<T>> = runs.drain(..MAX_FAN_IN).collect();
+            let merged = Run::merge(&self.temp_dir, &*self.compression, &codec, &by, batch)?;
+            runs.push(merged);
+        }
+        for run in runs.iter_mut() {
+            run.open(&*self.compression)?;
+        }
+
+        let mut merge = Merge {
+            work: make_work(buffer.len()),
+            memory: buffer,
+            runs,
+            heads: Vec::new(),
+            heap: Vec::new(),
+            remaining: len,
+            error: None,
+            by,
+            codec,
+        };
+        merge.start()?;
+        Ok(ExternalSortIterator {
+            inner: Inner::Merge(merge),
+        })
+    }
+}
+
+/// The k-way merge of the spilled runs and the in-memory remainder.  The
+/// in-memory values are the source with index `runs.len()`.
+struct Merge<T, F, C> {
+    memory: Vec<T>,
+    work: WorkStack,
+    runs: Vec<Run<T>>,
+    heads: Vec<Option<T>>,
+    heap: Vec<usize>,
+    remaining: usize,
+    error: Option<io::Error>,
+    by: F,
+    codec: C,
+}
+
+impl<T, F, C> Merge<T, F, C>
+where
+    F: Compare<T>,
+    C: SpillCodec<T>,
+{
+    fn start(&mut self) -> io::Result<()> {
+        for source in 0..=self.runs.len() {
+            let head = self.pull(source)?;
+            if head.is_some() {
+                self.heap.push(source);
+            }
+            self.heads.push(head);
+        }
+        for i in (0..self.heap.len() / 2).rev() {
+            self.sift_down(i);
+        }
+        Ok(())
+    }
+
+    fn pull(&mut self, source: usize) -> io::Result<Option<T>> {
+        if source < self.runs.len() {
+            self.runs[source].next(&self.codec)
+        } else {
+            let by = &self.by;
+            Ok(match self.work.pop() {
+                Some((lower, upper)) => Some(qsort(
+                    &|a: &T, b: &T| by.compare(a, b),
+                    &mut self.memory,
+                    &mut self.work,
+                    lower,
+                    upper,
+                )),
+                None => None,
+            })
+        }
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        match (&self.heads[self.heap[a]], &self.heads[self.heap[b]]) {
+            (Some(x), Some(y)) => self.by.compare(x, y) == Ordering::Less,
+            _ => false,
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let left = 2 * i + 1;
+            let right = left + 1;
+            let mut smallest = i;
+            if left < self.heap.len() && self.less(left, smallest) {
+                smallest = left;
+            }
+            if right < self.heap.len() && self.less(right, smallest) {
+                smallest = right;
+            }
+            if smallest == i {
+                return;
+            }
+            self.heap.swap(i, smallest);
+            i = smallest;
+        }
+    }
+
+    fn next(&mut self) -> Option<io::Result<T>> {
+        if let Some(e) = self.error.take() {
+            self.heap.clear();
+            self.remaining = 0;
+            return Some(Err(e));
+        }
+        let source = *self.heap.first()?;
+        let value = self.heads[source].take().expect("Head of active source");
+        match self.pull(source) {
+            Ok(Some(head)) => self.heads[source] = Some(head),
+            Ok(None) => {
+                self.heap.swap_remove(0);
+            }
+            Err(e) => {
+                self.heap.swap_remove(0);
+                self.error = Some(e);
+            }
+        }
+        self.sift_down(0);
+        self.remaining -= 1;
+        Some(Ok(value))
+    }
+}
+
+enum Inner<T, F, C> {
+    Memory(LazySortIteratorBy<T, F>),
+    Merge(Merge<T, F, C>),
+}
+
+/// Yields the sorted values of an `ExternalSorter`.  Reading a run file can
+/// fail, so each value is wrapped in an `io::Result`; after an error the
+/// iterator ends.
+pub struct ExternalSortIterator<T, F, C = DefaultCodec> {
+    inner: Inner<T, F, C>,
+}
+
+impl<T, F, C> Iterator for ExternalSortIterator<T, F, C>
+where
+    F: Compare<T>,
+    C: SpillCodec<T>,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<io::Result<T>> {
+        match self.inner {
+            Inner::Memory(ref mut iter) => iter.next().map(Ok),
+            Inner::Merge(ref mut merge) => merge.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.inner {
+            Inner::Memory(ref iter) => iter.size_hint(),
+            Inner::Merge(ref merge) => (0, Some(merge.remaining)),
+        }
+    }
+}
+
+impl<T, F, C> SortedIterator for ExternalSortIterator<T, F, C>
+where
+    F: Compare<T>,
+    C: SpillCodec<T>,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use std::fs::File;
+    use std::io::{self, BufWriter, Read, Write};
+
+    use super::{Compression, Encoder, ExternalSortIterator, ExternalSorter, LineOrder, RawCodec};
+
+    fn check(budget: usize, before: Vec<u64>) {
+        let mut expected = before.clone();
+        expected.sort();
+        let after: Vec<u64> = ExternalSorter::new(budget)
+            .sort(before)
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(expected, after);
+    }
+
+    #[test]
+    fn in_memory_test() {
+        check(1 << 20, vec![9, 7, 1, 1, 6, 3, 1, 4, 22]);
+    }
+
+    #[test]
+    fn spilled_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u64> = (0..5000).map(|_| rng.gen_range(0, 1000)).collect();
+        check(8 * 300, before);
+    }
+
+    #[test]
+    fn many_runs_test() {
+        // A run for every two values, far more than are merged at once.
+        let before: Vec<u64> = (0..5000).map(|x| (x * 7919) % 5000).collect();
+        check(16, before);
+    }
+
+    #[test]
+    fn empty_test() {
+        check(16, vec![]);
+    }
+
+    #[test]
+    fn spilled_strings_by_test() {
+        let before: Vec<String> = vec!["a", "cat", "sat", "on", "the", "mat"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let after: Vec<String> = ExternalSorter::new(1)
+            .sort_by(before, |a, b| b.cmp(a))
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(vec!["the", "sat", "on", "mat", "cat", "a"], after);
+    }
+
+    struct Xor<W>(W);
+
+    impl<W: Write> Write for Xor<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let flipped: Vec<u8> = buf.iter().map(|b| b ^ 0xff).collect();
+            self.0.write_all(&flipped)?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.flush()
+        }
+    }
+
+    impl<R: Read> Read for Xor<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.read(buf)?;
+            for b in &mut buf[..n] {
+                *b ^= 0xff;
+            }
+            Ok(n)
+        }
+    }
+
+    impl Encoder for Xor<BufWriter<File>> {
+        fn finish(mut self: Box<Self>) -> io::Result<()> {
+            self.flush()
+        }
+    }
+
+    struct XorCompression;
+
+    impl Compression for XorCompression {
+        fn encoder(&self, file: File) -> io::Result<Box<dyn Encoder>> {
+            Ok(Box::new(Xor(BufWriter::new(file))))
+        }
+
+        fn decoder(&self, file: File) -> io::Result<Box<dyn Read>> {
+            Ok(Box::new(Xor(file)))
+        }
+    }
+
+    #[test]
+    fn compressed_test() {
+        let before: Vec<u32> = (0..1000).map(|x| (x * 7919) % 1000).collect();
+        let after: Vec<u32> = ExternalSorter::new(4 * 64)
+            .compression(XorCompression)
+            .sort(before)
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!((0..1000).collect::<Vec<u32>>(), after);
+    }
+
+    #[test]
+    fn raw_codec_test() {
+        let before: Vec<(u32, char)> = vec![(3, 'c'), (1, 'a'), (4, 'd'), (1, 'b'), (2, 'z')];
+        let after: Vec<(u32, char)> = ExternalSorter::new(8)
+            .sort_by_codec(before, |a, b| a.cmp(b), unsafe { RawCodec::new() })
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(
+            vec![(1, 'a'), (1, 'b'), (2, 'z'), (3, 'c'), (4, 'd')],
+            after
+        );
+    }
+
+    #[test]
+    fn sort_lines_test() {
+        let input = "the\ncat\nsat\non\nthe\r\nmat\n";
+        let after: Vec<String> = ExternalSorter::new(16)
+            .sort_lines(input.as_bytes())
+            .unwrap()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(vec!["cat", "mat", "on", "sat", "the", "the"], after);
+
+        let by_length: Vec<String> = ExternalSorter::new(16)
+            .sort_lines_by(input.as_bytes(), |a, b| a.len().cmp(&b.len()))
+            .unwrap()
+            .take(1)
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(vec!["on"], by_length);
+
+        // The comparator may borrow from the lines it compares.
+        let input = "b\t2\na\t3\nc\t1\n";
+        let iter: ExternalSortIterator<String, LineOrder<_>> = ExternalSorter::new(8)
+            .sort_lines_by(input.as_bytes(), |a, b| {
+                a.split('\t').nth(1).cmp(&b.split('\t').nth(1))
+            })
+            .unwrap();
+        let by_field: Vec<String> = iter.map(|x| x.unwrap()).collect();
+        assert_eq!(vec!["c\t1", "b\t2", "a\t3"], by_field);
+    }
+}
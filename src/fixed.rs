@@ -0,0 +1,378 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy sorting without heap allocation.
+//!
+//! Both the values and the work list are held in arrays of a fixed capacity
+//! `N`, so these iterators can be used where there is no allocator.  The work
+//! list never needs more than `N` entries, as each entry covers at least one
+//! of the remaining values.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice;
+
+use super::{qsort, SortedIterator, Stack};
+
+/// A stack of at most `N` values held inline.
+pub(crate) struct ArrayStack<T, const N: usize> {
+    values: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayStack<T, N> {
+    pub(crate) fn new() -> Self {
+        ArrayStack {
+            values: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    fn from_array(values: [T; N]) -> Self {
+        ArrayStack {
+            values: values.map(MaybeUninit::new),
+            len: N,
+        }
+    }
+
+    pub(crate) fn try_push(&mut self, value: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(value);
+        }
+        self.values[self.len] = MaybeUninit::new(value);
+        self.len += 1;
+        Ok(())
+    }
+}
+
+impl<T, const N: usize> Stack<T> for ArrayStack<T, N> {
+    #[inline]
+    fn push(&mut self, value: T) {
+        if self.try_push(value).is_err() {
+            panic!("ArrayStack capacity exceeded");
+        }
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { self.values[self.len].as_ptr().read() })
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayStack<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.values.as_ptr() as *const T, self.len) }
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayStack<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.values.as_mut_ptr() as *mut T, self.len) }
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayStack<T, N> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(&mut **self as *mut [T]) }
+    }
+}
+
+/// Returned when more values are given than a fixed-capacity iterator can
+/// hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError {
+    capacity: usize,
+}
+
+impl CapacityError {
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "more than {} values to sort", self.capacity)
+    }
+}
+
+impl Error for CapacityError {}
+
+fn collect<T, I, const N: usize>(iter: I) -> Result<ArrayStack<T, N>, CapacityError>
+where
+    I: Iterator<Item = T>,
+{
+    let mut data = ArrayStack::new();
+    for value in iter {
+        if data.try_push(value).is_err() {
+            return Err(CapacityError { capacity: N });
+        }
+    }
+    Ok(data)
+}
+
+fn make_work<const N: usize>(len: usize) -> ArrayStack<(usize, usize), N> {
+    let mut work = ArrayStack::new();
+    if len > 0 {
+        work.push((len - 1, 0));
+    }
+    work
+}
+
+pub struct FixedLazySortIterator<T, const N: usize> {
+    data: ArrayStack<T, N>,
+    work: ArrayStack<(usize, usize), N>,
+}
+
+pub struct FixedLazySortIteratorBy<T, F, const N: usize> {
+    data: ArrayStack<T, N>,
+    work: ArrayStack<(usize, usize), N>,
+    by: F,
+}
+
+pub trait SortedFixed {
+    type Item;
+
+    /// Sort into a buffer of capacity `N`, failing if there are more than
+    /// `N` values.
+    fn sorted_fixed<const N: usize>(
+        self,
+    ) -> Result<FixedLazySortIterator<Self::Item, N>, CapacityError>
+    where
+        Self::Item: Ord;
+
+    fn sorted_fixed_by<F, const N: usize>(
+        self,
+        by: F,
+    ) -> Result<FixedLazySortIteratorBy<Self::Item, F, N>, CapacityError>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering;
+}
+
+impl<T, I> SortedFixed for I
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn sorted_fixed<const N: usize>(self) -> Result<FixedLazySortIterator<T, N>, CapacityError>
+    where
+        T: Ord,
+    {
+        let data = collect(self)?;
+        let work = make_work(data.len());
+        Ok(FixedLazySortIterator { data, work })
+    }
+
+    fn sorted_fixed_by<F, const N: usize>(
+        self,
+        by: F,
+    ) -> Result<FixedLazySortIteratorBy<T, F, N>, CapacityError>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let data = collect(self)?;
+        let work = make_work(data.len());
+        Ok(FixedLazySortIteratorBy { data, work, by })
+    }
+}
+
+/// The lazy sort of an owned array, which needs no allocation and cannot
+/// exceed its capacity.
+pub type LazySortArray<T, const N: usize> = FixedLazySortIterator<T, N>;
+
+pub type LazySortArrayBy<T, F, const N: usize> = FixedLazySortIteratorBy<T, F, N>;
+
+pub trait SortedArray<T, const N: usize> {
+    fn into_lazy_sorted(self) -> LazySortArray<T, N>
+    where
+        T: Ord;
+
+    fn into_lazy_sorted_by<F>(self, by: F) -> LazySortArrayBy<T, F, N>
+    where
+        F: Fn(&T, &T) -> Ordering;
+}
+
+impl<T, const N: usize> SortedArray<T, N> for [T; N] {
+    fn into_lazy_sorted(self) -> LazySortArray<T, N>
+    where
+        T: Ord,
+    {
+        FixedLazySortIterator {
+            data: ArrayStack::from_array(self),
+            work: make_work(N),
+        }
+    }
+
+    fn into_lazy_sorted_by<F>(self, by: F) -> LazySortArrayBy<T, F, N>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        FixedLazySortIteratorBy {
+            data: ArrayStack::from_array(self),
+            work: make_work(N),
+            by,
+        }
+    }
+}
+
+impl<T, const N: usize> Iterator for FixedLazySortIterator<T, N>
+where
+    T: Ord,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let (lower, upper) = self.work.pop()?;
+        Some(qsort(
+            &Ord::cmp,
+            &mut self.data,
+            &mut self.work,
+            lower,
+            upper,
+        ))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<T, const N: usize> SortedIterator for FixedLazySortIterator<T, N> where T: Ord {}
+
+impl<T, F, const N: usize> Iterator for FixedLazySortIteratorBy<T, F, N>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        let (lower, upper) = self.work.pop()?;
+        Some(qsort(
+            &self.by,
+            &mut self.data,
+            &mut self.work,
+            lower,
+            upper,
+        ))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<T, F, const N: usize> SortedIterator for FixedLazySortIteratorBy<T, F, N> where
+    F: Fn(&T, &T) -> Ordering
+{
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use std::rc::Rc;
+
+    use super::super::LazySortSlice;
+    use super::{CapacityError, SortedArray, SortedFixed};
+
+    #[test]
+    fn sorted_fixed_test() {
+        let expected: Vec<u64> = vec![1u64, 1, 1, 3, 4, 6, 7, 9, 22];
+        let before: Vec<u64> = vec![9u64, 7, 1, 1, 6, 3, 1, 4, 22];
+        let after: Vec<u64> = before.into_iter().sorted_fixed::<9>().unwrap().collect();
+
+        assert_eq!(expected, after);
+    }
+
+    #[test]
+    fn sorted_fixed_by_test() {
+        let before: Vec<&str> = vec!["a", "cat", "sat", "on", "the", "mat"];
+        let after: Vec<&str> = before
+            .into_iter()
+            .sorted_fixed_by::<_, 8>(|a, b| b.cmp(a))
+            .unwrap()
+            .collect();
+
+        assert_eq!(vec!["the", "sat", "on", "mat", "cat", "a"], after);
+    }
+
+    #[test]
+    fn sorted_fixed_random_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 50)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+        let after: Vec<u32> = before.into_iter().sorted_fixed::<1000>().unwrap().collect();
+
+        assert_eq!(expected, after);
+    }
+
+    #[test]
+    fn lazy_sorted_array_test() {
+        let before = [9u32, 7, 1, 1, 6, 3, 1, 4, 22];
+        let after: Vec<u32> = before.into_lazy_sorted().take(4).collect();
+        assert_eq!(vec![1, 1, 1, 3], after);
+
+        let after: Vec<u32> = before
+            .into_lazy_sorted_by(|a, b| b.cmp(a))
+            .take(2)
+            .collect();
+        assert_eq!(vec![22, 9], after);
+
+        let empty: [u32; 0] = [];
+        assert_eq!(None, empty.into_lazy_sorted().next());
+
+        // The borrowing sort of slices does not get in the way.
+        let words = [String::from("b"), String::from("a")];
+        let refs: Vec<&String> = words.lazy_sorted().collect();
+        assert_eq!(vec!["a", "b"], refs);
+        let owned: Vec<String> = words.into_lazy_sorted().collect();
+        assert_eq!(vec!["a", "b"], owned);
+    }
+
+    #[test]
+    fn capacity_test() {
+        let err = (0..5).sorted_fixed::<4>().err().unwrap();
+        assert_eq!(CapacityError { capacity: 4 }, err);
+        assert_eq!(0, (0..0).sorted_fixed::<0>().unwrap().count());
+    }
+
+    #[test]
+    fn drop_test() {
+        let value = Rc::new(());
+        {
+            let mut iter = vec![value.clone(), value.clone(), value.clone()]
+                .into_iter()
+                .sorted_fixed::<4>()
+                .unwrap();
+            iter.next();
+        }
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+}
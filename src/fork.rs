@@ -0,0 +1,86 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Cheap branching of a sort.
+//!
+//! Forks of a sort share its state through an `Arc`, so forking copies
+//! nothing.  A fork that is advanced while the state is still shared takes a
+//! copy of it first; any other fork is then left holding the only reference,
+//! and goes on without copying.  So however many values are previewed ahead
+//! of the main consumer, the remaining values are copied at most once for
+//! each fork that diverges.
+
+use std::sync::Arc;
+
+use super::SortedIterator;
+
+/// A sort that can be forked.
+#[derive(Clone)]
+pub struct Forkable<I> {
+    iter: Arc<I>,
+}
+
+impl<I> Forkable<I>
+where
+    I: Iterator + Clone,
+{
+    /// Another iterator over the values not yet yielded, sharing the state
+    /// of this one until either is advanced.
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+}
+
+pub trait IntoForkable: Iterator + Clone + Sized {
+    fn into_forkable(self) -> Forkable<Self> {
+        Forkable {
+            iter: Arc::new(self),
+        }
+    }
+}
+
+impl<I> IntoForkable for I where I: SortedIterator + Clone {}
+
+impl<I> Iterator for Forkable<I>
+where
+    I: Iterator + Clone,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        Arc::make_mut(&mut self.iter).next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I> SortedIterator for Forkable<I> where I: SortedIterator + Clone {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Sorted;
+    use super::IntoForkable;
+
+    #[test]
+    fn fork_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let mut main = before.sorted().into_forkable();
+        assert_eq!(Some(0), main.next());
+
+        let preview: Vec<u64> = main.fork().take(3).collect();
+        assert_eq!(vec![1, 2, 3], preview);
+        let rest: Vec<u64> = main.collect();
+        assert_eq!((1..1000).collect::<Vec<u64>>(), rest);
+    }
+}
@@ -0,0 +1,327 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Adaptors over sorted output.
+//!
+//! In sorted output equal values are adjacent, so they can be grouped by
+//! looking only one value ahead; each group is found only as it is needed,
+//! and the sort behind it does no more work than that requires.
+//!
+//! `top_k_per_group` works the other way round, over unsorted input: values
+//! are grouped by key first, and each group is then lazily sorted only as far
+//! as its first `k` values.
+
+use std::collections::{btree_map, BTreeMap};
+use std::iter::Take;
+
+use super::compare::Natural;
+use super::{Compare, LazySortIteratorBy, SortedIterator};
+
+/// Yields each run of equal values of a sorted iterator as a `Vec`.
+pub struct EqualGroupsBy<I, F>
+where
+    I: Iterator,
+{
+    iter: I,
+    next: Option<I::Item>,
+    eq: F,
+}
+
+pub type EqualGroups<I> =
+    EqualGroupsBy<I, fn(&<I as Iterator>::Item, &<I as Iterator>::Item) -> bool>;
+
+/// Which of the values with the same key to keep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keep {
+    First,
+    Last,
+}
+
+/// Yields one value for each run of values with equal keys.
+pub struct DedupByKey<I, F, M>
+where
+    I: Iterator,
+{
+    iter: I,
+    next: Option<I::Item>,
+    key: F,
+    merge: M,
+}
+
+pub type DedupKeep<I, F> =
+    DedupByKey<I, F, fn(<I as Iterator>::Item, <I as Iterator>::Item) -> <I as Iterator>::Item>;
+
+pub trait GroupSorted: SortedIterator + Sized {
+    /// Group runs of values that are equal.
+    fn equal_groups(self) -> EqualGroups<Self>
+    where
+        Self::Item: PartialEq,
+    {
+        self.equal_groups_by(PartialEq::eq)
+    }
+
+    /// Group runs of values for which `eq` holds between each value and the
+    /// first of its group.
+    fn equal_groups_by<F>(self, eq: F) -> EqualGroupsBy<Self, F>
+    where
+        F: FnMut(&Self::Item, &Self::Item) -> bool,
+    {
+        EqualGroupsBy {
+            iter: self,
+            next: None,
+            eq,
+        }
+    }
+
+    /// Keep only the first or last of each run of values with the same key.
+    fn dedup_by_key_sorted<K, F>(self, key: F, keep: Keep) -> DedupKeep<Self, F>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        fn first<T>(a: T, _: T) -> T {
+            a
+        }
+        fn last<T>(_: T, b: T) -> T {
+            b
+        }
+        let merge = match keep {
+            Keep::First => first,
+            Keep::Last => last,
+        };
+        self.dedup_by_key_merged(key, merge)
+    }
+
+    /// Combine each run of values with the same key into one, by folding
+    /// them in order with `merge`.
+    fn dedup_by_key_merged<K, F, M>(self, key: F, merge: M) -> DedupByKey<Self, F, M>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+        M: FnMut(Self::Item, Self::Item) -> Self::Item,
+    {
+        DedupByKey {
+            iter: self,
+            next: None,
+            key,
+            merge,
+        }
+    }
+}
+
+impl<I> GroupSorted for I where I: SortedIterator {}
+
+impl<I, F> Iterator for EqualGroupsBy<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> bool,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Vec<I::Item>> {
+        let first = self.next.take().or_else(|| self.iter.next())?;
+        let mut group = vec![first];
+        for value in self.iter.by_ref() {
+            if (self.eq)(&group[0], &value) {
+                group.push(value);
+            } else {
+                self.next = Some(value);
+                break;
+            }
+        }
+        Some(group)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.next.is_some() as usize;
+        let (low, high) = self.iter.size_hint();
+        (
+            (low + pending).min(1),
+            high.and_then(|h| h.checked_add(pending)),
+        )
+    }
+}
+
+impl<I, K, F, M> Iterator for DedupByKey<I, F, M>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+    M: FnMut(I::Item, I::Item) -> I::Item,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let mut acc = self.next.take().or_else(|| self.iter.next())?;
+        let k = (self.key)(&acc);
+        for value in self.iter.by_ref() {
+            if (self.key)(&value) == k {
+                acc = (self.merge)(acc, value);
+            } else {
+                self.next = Some(value);
+                break;
+            }
+        }
+        Some(acc)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.next.is_some() as usize;
+        let (low, high) = self.iter.size_hint();
+        (
+            (low + pending).min(1),
+            high.and_then(|h| h.checked_add(pending)),
+        )
+    }
+}
+
+impl<I, K, F, M> SortedIterator for DedupByKey<I, F, M>
+where
+    I: SortedIterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+    M: FnMut(I::Item, I::Item) -> I::Item,
+{
+}
+
+/// Yields the lowest `k` values of each group, lowest first, with the groups
+/// in the order of their keys.
+pub struct TopKPerGroupBy<K, T, F> {
+    groups: btree_map::IntoValues<K, Vec<T>>,
+    group: Option<Take<LazySortIteratorBy<T, F>>>,
+    k: usize,
+    by: F,
+}
+
+pub type TopKPerGroup<K, T> = TopKPerGroupBy<K, T, Natural>;
+
+pub trait GroupedTopK: Iterator + Sized {
+    /// The `k` lowest values with each key, as with `GROUP BY key LIMIT k`.
+    fn top_k_per_group<K, F>(self, key: F, k: usize) -> TopKPerGroup<K, Self::Item>
+    where
+        K: Ord,
+        F: FnMut(&Self::Item) -> K,
+        Self::Item: Ord,
+    {
+        self.top_k_per_group_with(key, k, Natural)
+    }
+
+    /// As `top_k_per_group`, ordering the values within each group by `by`.
+    /// `Reverse` gives the highest `k` values instead.
+    fn top_k_per_group_with<K, F, C>(
+        self,
+        mut key: F,
+        k: usize,
+        by: C,
+    ) -> TopKPerGroupBy<K, Self::Item, C>
+    where
+        K: Ord,
+        F: FnMut(&Self::Item) -> K,
+        C: Compare<Self::Item> + Clone,
+    {
+        let mut groups: BTreeMap<K, Vec<Self::Item>> = BTreeMap::new();
+        for value in self {
+            groups.entry(key(&value)).or_default().push(value);
+        }
+        TopKPerGroupBy {
+            groups: groups.into_values(),
+            group: None,
+            k,
+            by,
+        }
+    }
+}
+
+impl<I> GroupedTopK for I where I: Iterator {}
+
+impl<K, T, F> Iterator for TopKPerGroupBy<K, T, F>
+where
+    F: Compare<T> + Clone,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(group) = &mut self.group {
+                if let Some(value) = group.next() {
+                    return Some(value);
+                }
+            }
+            let values = self.groups.next()?;
+            self.group = Some(LazySortIteratorBy::new(values, self.by.clone()).take(self.k));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::compare::{Natural, Reverse};
+    use super::super::{Sorted, SortedBy};
+    use super::{GroupSorted, GroupedTopK, Keep};
+
+    #[test]
+    fn equal_groups_test() {
+        let groups: Vec<Vec<u32>> = vec![3, 1, 2, 1, 3, 3].sorted().equal_groups().collect();
+        assert_eq!(vec![vec![1, 1], vec![2], vec![3, 3, 3]], groups);
+
+        let groups: Vec<Vec<&str>> = vec!["bb", "a", "cc", "d"]
+            .sorted_by(|a, b| a.len().cmp(&b.len()))
+            .equal_groups_by(|a, b| a.len() == b.len())
+            .map(|mut g| {
+                g.sort();
+                g
+            })
+            .collect();
+        assert_eq!(vec![vec!["a", "d"], vec!["bb", "cc"]], groups);
+    }
+
+    #[test]
+    fn dedup_by_key_sorted_test() {
+        // (id, version): the latest version of each id.
+        let records = vec![(2, 1), (1, 3), (2, 5), (1, 1), (3, 2)];
+        let latest: Vec<(u32, u32)> = records
+            .clone()
+            .sorted()
+            .dedup_by_key_sorted(|r| r.0, Keep::Last)
+            .collect();
+        assert_eq!(vec![(1, 3), (2, 5), (3, 2)], latest);
+
+        let totals: Vec<(u32, u32)> = records
+            .sorted()
+            .dedup_by_key_merged(|r| r.0, |a, b| (a.0, a.1 + b.1))
+            .collect();
+        assert_eq!(vec![(1, 4), (2, 6), (3, 2)], totals);
+    }
+
+    #[test]
+    fn top_k_per_group_test() {
+        let scores = vec![
+            ("b", 7),
+            ("a", 3),
+            ("b", 1),
+            ("a", 9),
+            ("c", 4),
+            ("a", 5),
+            ("b", 2),
+        ];
+        let lowest: Vec<(&str, u32)> = scores.iter().cloned().top_k_per_group(|s| s.0, 2).collect();
+        assert_eq!(
+            vec![("a", 3), ("a", 5), ("b", 1), ("b", 2), ("c", 4)],
+            lowest
+        );
+
+        let highest: Vec<u32> = scores
+            .into_iter()
+            .map(|s| s.1)
+            .top_k_per_group_with(|v| v % 2, 1, Reverse(Natural))
+            .collect();
+        assert_eq!(vec![4, 9], highest);
+    }
+}
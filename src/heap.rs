@@ -0,0 +1,1030 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Priority queues.
+//!
+//! `Heap` is a lazy binomial heap, `PairingHeap` a pairing heap and
+//! `DaryHeap` an array-backed heap with `D` children per node.  All implement
+//! `PriorityQueue`, through which `sorted_via_queue` sorts with any of them.
+//! `MinMaxHeap` gives access to both the lowest and the highest value, for
+//! `extremes` among others, and `KeyedHeap` queues keys whose priorities can
+//! change.  `OnlineSorter` puts a `Heap` behind the names of a sort.
+//!
+//! # Binomial heap
+//!
+//! Values are added as single-node trees, and trees are only combined, two
+//! of each order at a time, when the minimum is taken.  With `n` values in
+//! the heap:
+//!
+//! * `add` is O(1).
+//! * `peek` is O(1), once the minimum has been found.
+//! * `take` is O(log n) amortized.
+//! * `meld` and `append` are O(log m) amortized, for `m` values in the other
+//!   heap.
+//! * `decrease_key` and `remove` are O(1) amortized.
+//!
+//! `add` returns a `Handle` to the value, which can be used to give it a new,
+//! lower, value with `decrease_key`.  The old value is not moved, but left in
+//! its tree as a stale entry, dropped when that tree is reached, and the new
+//! value is inserted as a tree of its own.  `remove` leaves a stale entry in
+//! the same way.  Once stale entries outnumber the values in the heap, it is
+//! rebuilt without them, in time linear in both; so however many updates are
+//! made, the heap holds at most twice as many entries as values, and `take`
+//! stays O(log n) amortized in the number of values.
+//!
+//! The heap is a min-heap with respect to its comparator: `take` returns the
+//! value that compares lowest.
+
+use std::cmp::Ordering;
+use std::cmp::Ordering::Greater;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::{self, AtomicUsize};
+
+use super::SortedIterator;
+
+mod dary;
+mod keyed;
+mod minmax;
+mod online;
+mod pairing;
+
+pub use self::dary::DaryHeap;
+pub use self::keyed::KeyedHeap;
+pub use self::minmax::{Extremes, IntoIterSorted as MinMaxIntoIterSorted, MinMaxHeap};
+pub use self::online::OnlineSorter;
+pub use self::pairing::PairingHeap;
+
+/// The operations common to the heaps of this module.  Each is a min-heap
+/// with respect to its comparator.
+pub trait PriorityQueue<T> {
+    fn add(&mut self, value: T);
+
+    /// Remove and return the lowest value.
+    fn take(&mut self) -> Option<T>;
+
+    /// The lowest value, without removing it.
+    fn peek(&mut self) -> Option<&T>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A heap ordered by `Ord`, as made by `Heap::new_min`.
+pub type MinHeap<T> = Heap<T, fn(&T, &T) -> Ordering>;
+
+const MAX_ORDERS: usize = usize::BITS as usize;
+
+/// The source of handle table ids, which are unique across all heaps so that
+/// a melded heap can keep the tables of both.
+static NEXT_TABLE: AtomicUsize = AtomicUsize::new(0);
+
+fn next_table_id() -> usize {
+    NEXT_TABLE.fetch_add(1, atomic::Ordering::Relaxed)
+}
+
+/// Refers to a value added to a heap, until that value is taken.  Handles
+/// remain valid when the heap is melded with another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    table: usize,
+    slot: usize,
+    generation: u32,
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    // Bumped each time the slot is freed, which invalidates its handle.
+    generation: u32,
+    // Bumped each time the slot is given a new value, so that only the
+    // newest tree holding the slot is live.
+    version: u32,
+    live: bool,
+}
+
+/// The slots behind the handles issued by one heap.
+#[derive(Clone)]
+struct Table {
+    id: usize,
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    live: usize,
+}
+
+impl Table {
+    fn new() -> Self {
+        Table {
+            id: next_table_id(),
+            slots: Vec::new(),
+            free: Vec::new(),
+            live: 0,
+        }
+    }
+
+    fn allocate(&mut self) -> usize {
+        self.live += 1;
+        match self.free.pop() {
+            Some(i) => {
+                let slot = &mut self.slots[i];
+                slot.version = slot.version.wrapping_add(1);
+                slot.live = true;
+                i
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    version: 0,
+                    live: true,
+                });
+                self.slots.len() - 1
+            }
+        }
+    }
+}
+
+/// A heap-ordered tree, whose order is its number of children.  The children
+/// of a tree of order `k` have the orders `0..k`, so it holds `2^k` values.
+#[derive(Clone)]
+struct Tree<T> {
+    value: T,
+    // The slot this value belongs to, and its version when it was added.
+    table: usize,
+    slot: usize,
+    version: u32,
+    children: Vec<Tree<T>>,
+}
+
+impl<T> Tree<T> {
+    fn new(value: T, table: usize, slot: usize, version: u32) -> Self {
+        Tree {
+            value,
+            table,
+            slot,
+            version,
+            children: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn order(&self) -> usize {
+        self.children.len()
+    }
+
+    fn retable(&mut self, from: usize, to: usize) {
+        if self.table == from {
+            self.table = to;
+        }
+        for child in self.children.iter_mut() {
+            child.retable(from, to);
+        }
+    }
+}
+
+/// A min-heap of values ordered by the comparator `F`.
+///
+/// A clone of a heap holds clones of its values, and the handles of the
+/// original refer to the same values in the clone.
+#[derive(Clone)]
+pub struct Heap<T, F> {
+    trees: Vec<Tree<T>>,
+    // The index of the tree with the lowest root, if known.
+    min: Option<usize>,
+    // The first table is this heap's own; any others came from melded heaps.
+    tables: Vec<Table>,
+    // The number of live values.
+    len: usize,
+    // The number of entries in the trees whose values have been replaced or
+    // removed.
+    stale: usize,
+    by: F,
+}
+
+impl<T> MinHeap<T>
+where
+    T: Ord,
+{
+    /// An empty heap from which the lowest value is taken first.
+    pub fn new_min() -> Self {
+        Heap::new(Ord::cmp)
+    }
+}
+
+impl<T, F> Heap<T, F> {
+    /// The number of values in the heap.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of values the heap can hold without reallocating its list
+    /// of trees or its handles.
+    pub fn capacity(&self) -> usize {
+        let table = &self.tables[0];
+        let trees = self.trees.capacity() - self.trees.len();
+        let slots = table.slots.capacity() - table.slots.len() + table.free.len();
+        self.len + trees.min(slots)
+    }
+
+    /// Reserve space for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        self.trees.reserve(additional);
+        let table = &mut self.tables[0];
+        table
+            .slots
+            .reserve(additional.saturating_sub(table.free.len()));
+    }
+
+    /// Drop all values, invalidating all handles.
+    pub fn clear(&mut self) {
+        self.trees.clear();
+        self.min = None;
+        self.tables.truncate(1);
+        let table = &mut self.tables[0];
+        for (i, slot) in table.slots.iter_mut().enumerate() {
+            if slot.live {
+                slot.live = false;
+                slot.generation = slot.generation.wrapping_add(1);
+                table.free.push(i);
+            }
+        }
+        table.live = 0;
+        self.len = 0;
+        self.stale = 0;
+    }
+
+    /// Remove all values, in no particular order, keeping the allocation of
+    /// the heap.  Any values not yielded are dropped with the iterator.
+    pub fn drain(&mut self) -> Drain<'_, T, F> {
+        Drain { heap: self }
+    }
+
+    fn is_live(&self, tree: &Tree<T>) -> bool {
+        match self.tables.iter().find(|t| t.id == tree.table) {
+            Some(table) => {
+                let slot = &table.slots[tree.slot];
+                slot.live && slot.version == tree.version
+            }
+            None => false,
+        }
+    }
+
+    /// Free a slot whose value has left the heap.  Tables from melded heaps
+    /// are dropped once none of their slots are live.
+    fn release(&mut self, table: usize, slot: usize) {
+        let i = match self.tables.iter().position(|t| t.id == table) {
+            Some(i) => i,
+            None => return,
+        };
+        let t = &mut self.tables[i];
+        let s = &mut t.slots[slot];
+        s.live = false;
+        s.generation = s.generation.wrapping_add(1);
+        t.free.push(slot);
+        t.live -= 1;
+        self.len -= 1;
+        if t.live == 0 && i > 0 {
+            self.tables.swap_remove(i);
+        }
+    }
+}
+
+impl<T, F> Heap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    /// An empty heap from which the value that compares lowest by `by` is
+    /// taken first.
+    pub fn new(by: F) -> Self {
+        Heap {
+            trees: Vec::new(),
+            min: None,
+            tables: vec![Table::new()],
+            len: 0,
+            stale: 0,
+            by,
+        }
+    }
+
+    /// Add a value to the heap.  O(1).
+    pub fn add(&mut self, value: T) -> Handle {
+        self.len += 1;
+        let table = &mut self.tables[0];
+        let slot = table.allocate();
+        let handle = Handle {
+            table: table.id,
+            slot,
+            generation: table.slots[slot].generation,
+        };
+        let tree = Tree::new(value, table.id, slot, table.slots[slot].version);
+        self.push_tree(tree);
+        handle
+    }
+
+    /// Replace the value referred to by `handle` with a lower one.  O(1)
+    /// amortized.
+    ///
+    /// The value is given back if `handle` no longer refers to a value in
+    /// the heap.  A value that is not lower is still placed correctly.  The
+    /// old value is not dropped until its tree is reached, or the heap is
+    /// rebuilt.
+    pub fn decrease_key(&mut self, handle: Handle, value: T) -> Result<(), T> {
+        let version = match self.slot_mut(handle) {
+            Some(slot) => {
+                slot.version = slot.version.wrapping_add(1);
+                slot.version
+            }
+            None => return Err(value),
+        };
+        self.push_tree(Tree::new(value, handle.table, handle.slot, version));
+        self.add_stale();
+        Ok(())
+    }
+
+    /// Remove the value referred to by `handle` from the heap, returning
+    /// whether there was one.  O(1) amortized.
+    ///
+    /// The value itself is dropped later, when its tree is reached or the
+    /// heap is rebuilt.
+    pub fn remove(&mut self, handle: Handle) -> bool {
+        if self.slot_mut(handle).is_none() {
+            return false;
+        }
+        self.release(handle.table, handle.slot);
+        self.add_stale();
+        true
+    }
+
+    /// Count an entry left behind by `decrease_key` or `remove`, and rebuild
+    /// the heap without any such entries once they outnumber the values.
+    /// Each rebuild takes time linear in the stale entries it drops, at
+    /// least as many as the values it keeps, so it adds O(1) amortized to
+    /// each update.
+    fn add_stale(&mut self) {
+        self.stale += 1;
+        if self.stale > self.len {
+            self.compact();
+        }
+    }
+
+    /// Rebuild the heap from the live values, as single-node trees.
+    fn compact(&mut self) {
+        let mut trees = mem::take(&mut self.trees);
+        self.trees.reserve(self.len);
+        while let Some(mut tree) = trees.pop() {
+            trees.append(&mut tree.children);
+            if self.is_live(&tree) {
+                self.trees.push(tree);
+            }
+        }
+        self.stale = 0;
+        self.min = None;
+    }
+
+    fn push_tree(&mut self, tree: Tree<T>) {
+        let i = self.trees.len();
+        self.trees.push(tree);
+        self.min = match self.min {
+            Some(m) if (self.by)(&self.trees[m].value, &self.trees[i].value) != Greater => Some(m),
+            Some(_) => Some(i),
+            None if i == 0 => Some(0),
+            None => None,
+        };
+    }
+
+    /// The lowest value, without removing it.  This finds the minimum if it
+    /// is not already known, which takes O(log n) amortized.
+    pub fn peek(&mut self) -> Option<&T> {
+        let i = self.min_index()?;
+        Some(&self.trees[i].value)
+    }
+
+    /// Remove and return the lowest value.  O(log n) amortized.
+    pub fn take(&mut self) -> Option<T> {
+        let i = self.min_index()?;
+        let tree = self.remove_root(i);
+        self.release(tree.table, tree.slot);
+        Some(tree.value)
+    }
+
+    fn remove_root(&mut self, i: usize) -> Tree<T> {
+        let mut tree = self.trees.swap_remove(i);
+        self.trees.append(&mut tree.children);
+        self.consolidate();
+        self.min = None;
+        tree
+    }
+
+    /// The slot `handle` refers to, if it still refers to a value.
+    fn slot_mut(&mut self, handle: Handle) -> Option<&mut Slot> {
+        let table = self.tables.iter_mut().find(|t| t.id == handle.table)?;
+        let slot = &mut table.slots[handle.slot];
+        if slot.live && slot.generation == handle.generation {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+
+    /// Combine two heaps into one.
+    pub fn meld(mut self, mut other: Self) -> Self {
+        self.append(&mut other);
+        self
+    }
+
+    /// Move all the values of `other` into this heap, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.trees.is_empty() {
+            return;
+        }
+        // A heap and its clone share table ids, so if the two are melded the
+        // other's tables must be renumbered, and their handles lost.
+        for table in other.tables.iter_mut() {
+            if self.tables.iter().any(|t| t.id == table.id) {
+                let id = next_table_id();
+                for tree in other.trees.iter_mut() {
+                    tree.retable(table.id, id);
+                }
+                table.id = id;
+            }
+        }
+        let offset = self.trees.len();
+        let other_min = other.min.take().map(|m| m + offset);
+        self.trees.append(&mut other.trees);
+        self.tables
+            .extend(other.tables.drain(..).filter(|t| t.live > 0));
+        other.tables.push(Table::new());
+        self.len += other.len;
+        other.len = 0;
+        self.stale += other.stale;
+        other.stale = 0;
+        self.min = match (self.min, other_min) {
+            (Some(a), Some(b)) => {
+                if (self.by)(&self.trees[a].value, &self.trees[b].value) == Greater {
+                    Some(b)
+                } else {
+                    Some(a)
+                }
+            }
+            (None, b) if offset == 0 => b,
+            _ => None,
+        };
+    }
+
+    /// Remove all values, in order, keeping the allocation of the heap.
+    /// Any values not yielded are dropped with the iterator.
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, T, F> {
+        DrainSorted { heap: self }
+    }
+
+    /// A consuming iterator over the values in order, lowest first.
+    pub fn into_iter_sorted(self) -> IntoIter<T, F> {
+        IntoIter { heap: self }
+    }
+
+    /// The index of the tree with the lowest live root, dropping any roots
+    /// that have been replaced along the way.
+    fn min_index(&mut self) -> Option<usize> {
+        loop {
+            if self.min.is_none() {
+                self.min = self.find_min();
+            }
+            let i = self.min?;
+            if self.is_live(&self.trees[i]) {
+                return Some(i);
+            }
+            self.remove_root(i);
+            self.stale -= 1;
+        }
+    }
+
+    /// The index of the tree with the lowest root.
+    fn find_min(&self) -> Option<usize> {
+        let mut trees = self.trees.iter().enumerate();
+        let (mut min, mut min_tree) = trees.next()?;
+        for (i, tree) in trees {
+            if (self.by)(&min_tree.value, &tree.value) == Greater {
+                min = i;
+                min_tree = tree;
+            }
+        }
+        Some(min)
+    }
+
+    /// Link trees of the same order until there is at most one of each.
+    fn consolidate(&mut self) {
+        let by = &self.by;
+        // A tree of order `k` holds `2^k` values, so there can be no more
+        // orders than bits in a `usize`.
+        let mut by_order: [Option<Tree<T>>; MAX_ORDERS] = [const { None }; MAX_ORDERS];
+        let mut orders = 0;
+        for mut tree in self.trees.drain(..) {
+            loop {
+                let order = tree.order();
+                orders = orders.max(order + 1);
+                match by_order[order].take() {
+                    Some(other) => tree = link(by, tree, other),
+                    None => {
+                        by_order[order] = Some(tree);
+                        break;
+                    }
+                }
+            }
+        }
+        self.trees
+            .extend(by_order[..orders].iter_mut().filter_map(Option::take));
+    }
+}
+
+impl<T, F> IntoIterator for Heap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+    type IntoIter = IntoIter<T, F>;
+
+    /// The values in order, lowest first.
+    fn into_iter(self) -> IntoIter<T, F> {
+        self.into_iter_sorted()
+    }
+}
+
+impl<T, F> PriorityQueue<T> for Heap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn add(&mut self, value: T) {
+        Heap::add(self, value);
+    }
+
+    fn take(&mut self) -> Option<T> {
+        Heap::take(self)
+    }
+
+    fn peek(&mut self) -> Option<&T> {
+        Heap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+pub type HeapSortIterator<T> = IntoIter<T, fn(&T, &T) -> Ordering>;
+
+pub type HeapSortIteratorBy<T, F> = IntoIter<T, F>;
+
+/// Lazy sorting through a `Heap`: the values are added to the heap, which is
+/// O(1) each, and each call to `next` takes the lowest.  This can beat the
+/// lazy quicksort when only the first few values are wanted.
+pub trait SortedViaHeap {
+    type Item;
+
+    fn sorted_via_heap(self) -> HeapSortIterator<Self::Item>
+    where
+        Self::Item: Ord;
+
+    fn sorted_via_heap_by<F>(self, by: F) -> HeapSortIteratorBy<Self::Item, F>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering;
+
+    /// As `sorted_via_heap`, but through any `PriorityQueue`, such as a
+    /// `PairingHeap` or a `DaryHeap`.  The values are added to `queue`, which
+    /// orders them, and taken from it in turn; any values it already holds
+    /// are yielded among them.
+    fn sorted_via_queue<Q>(self, queue: Q) -> QueueSortIterator<Self::Item, Q>
+    where
+        Q: PriorityQueue<Self::Item>;
+}
+
+impl<T, I> SortedViaHeap for I
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn sorted_via_heap(self) -> HeapSortIterator<T>
+    where
+        T: Ord,
+    {
+        self.sorted_via_heap_by(Ord::cmp as fn(&T, &T) -> Ordering)
+    }
+
+    fn sorted_via_heap_by<F>(self, by: F) -> HeapSortIteratorBy<T, F>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        let mut heap = Heap::new(by);
+        let (lower, _) = self.size_hint();
+        heap.reserve(lower);
+        for value in self {
+            heap.add(value);
+        }
+        heap.into_iter_sorted()
+    }
+
+    fn sorted_via_queue<Q>(self, mut queue: Q) -> QueueSortIterator<T, Q>
+    where
+        Q: PriorityQueue<T>,
+    {
+        for value in self {
+            queue.add(value);
+        }
+        QueueSortIterator {
+            queue,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Takes each value from a `PriorityQueue` in turn.
+pub struct QueueSortIterator<T, Q> {
+    queue: Q,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T, Q> Iterator for QueueSortIterator<T, Q>
+where
+    Q: PriorityQueue<T>,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.queue.take()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.queue.len();
+        (l, Some(l))
+    }
+}
+
+impl<T, Q> SortedIterator for QueueSortIterator<T, Q> where Q: PriorityQueue<T> {}
+
+/// Takes each value from a heap in turn.
+pub struct IntoIter<T, F> {
+    heap: Heap<T, F>,
+}
+
+impl<T, F> Iterator for IntoIter<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.heap.take()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.heap.len();
+        (l, Some(l))
+    }
+}
+
+impl<T, F> SortedIterator for IntoIter<T, F> where F: Fn(&T, &T) -> Ordering {}
+
+pub struct Drain<'a, T: 'a, F: 'a> {
+    heap: &'a mut Heap<T, F>,
+}
+
+impl<'a, T, F> Iterator for Drain<'a, T, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            let mut tree = self.heap.trees.pop()?;
+            self.heap.trees.append(&mut tree.children);
+            if self.heap.is_live(&tree) {
+                self.heap.release(tree.table, tree.slot);
+                return Some(tree.value);
+            }
+            self.heap.stale -= 1;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.heap.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T, F> Drop for Drain<'a, T, F> {
+    fn drop(&mut self) {
+        self.heap.clear();
+    }
+}
+
+pub struct DrainSorted<'a, T: 'a, F: 'a> {
+    heap: &'a mut Heap<T, F>,
+}
+
+impl<'a, T, F> Iterator for DrainSorted<'a, T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.heap.take()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.heap.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T, F> SortedIterator for DrainSorted<'a, T, F> where F: Fn(&T, &T) -> Ordering {}
+
+impl<'a, T, F> Drop for DrainSorted<'a, T, F> {
+    fn drop(&mut self) {
+        self.heap.clear();
+    }
+}
+
+/// Combine two trees of the same order into one of the next order.
+fn link<T, F>(by: &F, mut a: Tree<T>, mut b: Tree<T>) -> Tree<T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if by(&a.value, &b.value) == Greater {
+        b.children.push(a);
+        b
+    } else {
+        a.children.push(b);
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::{DaryHeap, Heap, PairingHeap, PriorityQueue, SortedViaHeap};
+
+    #[test]
+    fn heap_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 100)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        let mut heap = Heap::new_min();
+        for x in before {
+            heap.add(x);
+        }
+        let after: Vec<u32> = heap.into_iter().collect();
+        assert_eq!(expected, after);
+    }
+
+    #[test]
+    fn heap_by_test() {
+        let mut heap = Heap::new(|a: &&str, b: &&str| b.len().cmp(&a.len()));
+        heap.add("on");
+        heap.add("a");
+        heap.add("mat");
+        assert_eq!(Some("mat"), heap.take());
+        heap.add("the");
+        assert_eq!(Some("the"), heap.take());
+        assert_eq!(Some("on"), heap.take());
+        assert_eq!(Some("a"), heap.take());
+        assert_eq!(None, heap.take());
+    }
+
+    #[test]
+    fn meld_test() {
+        let mut a = Heap::new_min();
+        let mut b = Heap::new_min();
+        for x in 0..50 {
+            a.add(x * 2);
+            b.add(x * 2 + 1);
+        }
+        b.take();
+        let heap = a.meld(b);
+        let after: Vec<u32> = heap.into_iter().collect();
+        assert_eq!(100 - 1, after.len());
+        assert_eq!(Some(&0), after.first());
+        assert!(after.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut a = Heap::new_min();
+        let mut b = Heap::new_min();
+        b.add(7);
+        a.append(&mut b);
+        assert_eq!(None, b.take());
+        assert_eq!(Some(&7), a.peek());
+    }
+
+    #[test]
+    fn decrease_key_test() {
+        let mut heap = Heap::new_min();
+        let handles: Vec<_> = (0..20).map(|x| heap.add(x * 10)).collect();
+        assert_eq!(Some(0), heap.take());
+        assert_eq!(Err(5), heap.decrease_key(handles[0], 5));
+
+        assert_eq!(Ok(()), heap.decrease_key(handles[15], 15));
+        assert_eq!(Ok(()), heap.decrease_key(handles[19], 1));
+        assert_eq!(Ok(()), heap.decrease_key(handles[19], 0));
+
+        let mut other = Heap::new_min();
+        let h = other.add(50);
+        heap.append(&mut other);
+        assert_eq!(Ok(()), heap.decrease_key(h, 2));
+
+        let after: Vec<u32> = heap.into_iter().collect();
+        let mut expected: Vec<u32> = vec![0, 2, 10, 15];
+        expected.extend((2..19).filter(|&x| x != 15).map(|x| x * 10));
+        expected.sort();
+        assert_eq!(expected, after);
+    }
+
+    #[test]
+    fn remove_test() {
+        let mut heap = Heap::new_min();
+        let handles: Vec<_> = (0..10).map(|x| heap.add(x)).collect();
+        assert!(heap.remove(handles[0]));
+        assert!(!heap.remove(handles[0]));
+        assert!(heap.remove(handles[5]));
+        assert_eq!(Some(&1), heap.peek());
+        assert!(heap.remove(handles[1]));
+
+        // A freed slot is reused without reviving the removed value.
+        let h = heap.add(100);
+        assert!(!heap.remove(handles[1]));
+        assert_eq!(Ok(()), heap.decrease_key(h, 3));
+
+        let after: Vec<u32> = heap.into_iter().collect();
+        assert_eq!(vec![2, 3, 3, 4, 6, 7, 8, 9], after);
+    }
+
+    #[test]
+    fn decrease_key_compact_test() {
+        let mut heap = Heap::new_min();
+        let handles: Vec<_> = (0..100u32).map(|x| heap.add(x + 1_000_000)).collect();
+        for round in 0..1000u32 {
+            for (i, h) in handles.iter().enumerate() {
+                heap.decrease_key(*h, 1_000_000 - round * 100 - i as u32)
+                    .unwrap();
+            }
+        }
+        // The replaced values are dropped as the updates are made, rather
+        // than all kept until they are reached.
+        let entries = |heap: &Heap<u32, _>| {
+            let mut trees: Vec<&super::Tree<u32>> = heap.trees.iter().collect();
+            let mut count = 0;
+            while let Some(tree) = trees.pop() {
+                count += 1;
+                trees.extend(tree.children.iter());
+            }
+            count
+        };
+        assert!(entries(&heap) <= 2 * heap.len() + 1);
+        let after: Vec<u32> = heap.into_iter().collect();
+        let expected: Vec<u32> = (0..100).map(|i| 900_100 - 99 + i).collect();
+        assert_eq!(expected, after);
+    }
+
+    #[test]
+    fn remove_compact_test() {
+        use std::rc::Rc;
+
+        let value = Rc::new(0);
+        let mut heap = Heap::new(|a: &Rc<u32>, b: &Rc<u32>| a.cmp(b));
+        let handles: Vec<_> = (0..1000).map(|_| heap.add(value.clone())).collect();
+        for h in &handles[..900] {
+            assert!(heap.remove(*h));
+        }
+        // At most as many removed values are still held as live ones.
+        assert_eq!(100, heap.len());
+        assert!(Rc::strong_count(&value) <= 1 + 2 * 100);
+        assert_eq!(100, heap.into_iter().count());
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn len_test() {
+        let mut heap = Heap::new_min();
+        assert!(heap.is_empty());
+        heap.reserve(10);
+        assert!(heap.capacity() >= 10);
+        let handles: Vec<_> = (0..10).map(|x| heap.add(x)).collect();
+        assert_eq!(10, heap.len());
+        heap.decrease_key(handles[9], 0).unwrap();
+        heap.remove(handles[3]);
+        heap.take();
+        assert_eq!(8, heap.len());
+
+        let mut other = Heap::new_min();
+        other.add(1);
+        heap.append(&mut other);
+        assert_eq!(9, heap.len());
+        assert_eq!(0, other.len());
+
+        heap.clear();
+        assert!(heap.is_empty());
+        assert_eq!(None, heap.take());
+        assert_eq!(Err(0), heap.decrease_key(handles[5], 0));
+    }
+
+    #[test]
+    fn clone_test() {
+        let mut heap = Heap::new_min();
+        let handles: Vec<_> = (0..100).map(|x| heap.add(x)).collect();
+        heap.take();
+
+        let top: Vec<u32> = heap.clone().into_iter().take(3).collect();
+        assert_eq!(vec![1, 2, 3], top);
+        assert_eq!(99, heap.len());
+
+        let mut snapshot = heap.clone();
+        assert_eq!(Ok(()), snapshot.decrease_key(handles[50], 0));
+        assert_eq!(Some(&1), heap.peek());
+        assert_eq!(Some(&0), snapshot.peek());
+
+        let melded = heap.meld(snapshot);
+        assert_eq!(198, melded.len());
+        let after: Vec<u32> = melded.into_iter().collect();
+        assert_eq!(Some(&0), after.first());
+        assert!(after.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn drain_test() {
+        let mut heap = Heap::new_min();
+        let handles: Vec<_> = (0..100).map(|x| heap.add(99 - x)).collect();
+        heap.take();
+        heap.decrease_key(handles[0], 0).unwrap();
+        let capacity = heap.capacity();
+
+        let mut all: Vec<u32> = heap.drain().collect();
+        all.sort();
+        assert_eq!((0..99).collect::<Vec<u32>>(), all);
+        assert!(heap.is_empty());
+        assert!(heap.capacity() >= capacity);
+
+        for x in 0..10 {
+            heap.add(9 - x);
+        }
+        let first: Vec<u32> = heap.drain_sorted().take(3).collect();
+        assert_eq!(vec![0, 1, 2], first);
+        assert!(heap.is_empty());
+        assert_eq!(None, heap.take());
+    }
+
+    #[test]
+    fn sorted_via_heap_test() {
+        let before: Vec<u64> = vec![9, 7, 1, 1, 6, 3, 1, 4, 22];
+        let after: Vec<u64> = before.iter().cloned().sorted_via_heap().take(4).collect();
+        assert_eq!(vec![1, 1, 1, 3], after);
+
+        let after: Vec<&u64> = before.iter().sorted_via_heap_by(|a, b| b.cmp(a)).collect();
+        assert_eq!(vec![&22, &9, &7, &6, &4, &3, &1, &1, &1], after);
+
+        let mut iter = before
+            .iter()
+            .cloned()
+            .sorted_via_queue(PairingHeap::new_min());
+        assert_eq!((9, Some(9)), iter.size_hint());
+        assert_eq!(Some(1), iter.next());
+        let mut queue: DaryHeap<u64, _> = DaryHeap::new_min();
+        PriorityQueue::add(&mut queue, 0);
+        let after: Vec<u64> = before.iter().cloned().sorted_via_queue(queue).collect();
+        assert_eq!(vec![0, 1, 1, 1, 3, 4, 6, 7, 9, 22], after);
+    }
+
+    #[test]
+    fn peek_test() {
+        let mut heap = Heap::new_min();
+        assert_eq!(None, heap.peek());
+        for x in &[5, 3, 8, 1, 9] {
+            heap.add(*x);
+        }
+        assert_eq!(Some(&1), heap.peek());
+        assert_eq!(Some(1), heap.take());
+        assert_eq!(Some(&3), heap.peek());
+        heap.add(2);
+        assert_eq!(Some(&2), heap.peek());
+        assert_eq!(Some(2), heap.take());
+        assert_eq!(Some(3), heap.take());
+    }
+}
@@ -0,0 +1,188 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use std::cmp::Ordering;
+use std::cmp::Ordering::{Greater, Less};
+
+use super::PriorityQueue;
+
+/// An implicit heap in which each node has `D` children, held in a single
+/// array.  The shallower tree and contiguous children make better use of the
+/// cache than a binary heap, or a heap of linked nodes.
+///
+/// * `from_vec` is O(n).
+/// * `add` is O(log n).
+/// * `peek` is O(1).
+/// * `take` is O(D log n).
+pub struct DaryHeap<T, F, const D: usize = 4> {
+    data: Vec<T>,
+    by: F,
+}
+
+impl<T, const D: usize> DaryHeap<T, fn(&T, &T) -> Ordering, D>
+where
+    T: Ord,
+{
+    /// An empty heap from which the lowest value is taken first.
+    pub fn new_min() -> Self {
+        DaryHeap::new(Ord::cmp)
+    }
+}
+
+impl<T, F, const D: usize> DaryHeap<T, F, D> {
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The lowest value, without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// The values of the heap, in no particular order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T, F, const D: usize> DaryHeap<T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    /// An empty heap from which the value that compares lowest by `by` is
+    /// taken first.
+    pub fn new(by: F) -> Self {
+        DaryHeap::from_vec(Vec::new(), by)
+    }
+
+    /// Arrange the values of `data` into a heap, in place.
+    pub fn from_vec(data: Vec<T>, by: F) -> Self {
+        assert!(D >= 2, "A d-ary heap needs at least two children per node");
+        let mut heap = DaryHeap { data, by };
+        if heap.data.len() > 1 {
+            for i in (0..=(heap.data.len() - 2) / D).rev() {
+                heap.sift_down(i);
+            }
+        }
+        heap
+    }
+
+    pub fn add(&mut self, value: T) {
+        self.data.push(value);
+        self.sift_up(self.data.len() - 1);
+    }
+
+    /// Remove and return the lowest value.
+    pub fn take(&mut self) -> Option<T> {
+        let value = self.data.pop()?;
+        if self.data.is_empty() {
+            return Some(value);
+        }
+        let min = std::mem::replace(&mut self.data[0], value);
+        self.sift_down(0);
+        Some(min)
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let parent = (i - 1) / D;
+            if (self.by)(&self.data[i], &self.data[parent]) != Less {
+                break;
+            }
+            self.data.swap(i, parent);
+            i = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let len = self.data.len();
+        loop {
+            let first = D * i + 1;
+            if first >= len {
+                break;
+            }
+            let mut min = first;
+            for child in first + 1..(first + D).min(len) {
+                if (self.by)(&self.data[min], &self.data[child]) == Greater {
+                    min = child;
+                }
+            }
+            if (self.by)(&self.data[i], &self.data[min]) != Greater {
+                break;
+            }
+            self.data.swap(i, min);
+            i = min;
+        }
+    }
+}
+
+impl<T, F, const D: usize> PriorityQueue<T> for DaryHeap<T, F, D>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn add(&mut self, value: T) {
+        DaryHeap::add(self, value)
+    }
+
+    fn take(&mut self) -> Option<T> {
+        DaryHeap::take(self)
+    }
+
+    fn peek(&mut self) -> Option<&T> {
+        DaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::super::PriorityQueue;
+    use super::DaryHeap;
+
+    #[test]
+    fn dary_heap_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 100)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        let mut heap: DaryHeap<u32, _> = DaryHeap::new_min();
+        for x in before.iter() {
+            PriorityQueue::add(&mut heap, *x);
+        }
+        let mut after = Vec::new();
+        while let Some(x) = heap.take() {
+            after.push(x);
+        }
+        assert_eq!(expected, after);
+
+        let max = *before.iter().max().unwrap();
+        let mut heap = DaryHeap::<_, _, 3>::from_vec(before, |a: &u32, b: &u32| b.cmp(a));
+        assert_eq!(Some(&max), heap.peek());
+        heap.add(1000);
+        assert_eq!(Some(1000), heap.take());
+        assert_eq!(1000, heap.len());
+    }
+}
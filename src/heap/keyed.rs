@@ -0,0 +1,146 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{Handle, Heap};
+
+struct Entry<K, P> {
+    key: K,
+    priority: P,
+}
+
+fn by_priority<K, P: Ord>(a: &Entry<K, P>, b: &Entry<K, P>) -> Ordering {
+    a.priority.cmp(&b.priority)
+}
+
+type EntryOrder<K, P> = fn(&Entry<K, P>, &Entry<K, P>) -> Ordering;
+
+/// A priority queue of keys, each of which can have its priority changed, or
+/// be removed, while it is queued.  The lowest priority is taken first.
+///
+/// This is a `Heap` with a map from each key to its handle, so changing a
+/// priority, in either direction, and removing a key are both O(1) amortized,
+/// and the old entry is dropped when the heap next reaches it or is rebuilt.
+pub struct KeyedHeap<K, P> {
+    heap: Heap<Entry<K, P>, EntryOrder<K, P>>,
+    handles: HashMap<K, Handle>,
+}
+
+impl<K, P> Default for KeyedHeap<K, P>
+where
+    K: Hash + Eq + Clone,
+    P: Ord,
+{
+    fn default() -> Self {
+        KeyedHeap::new()
+    }
+}
+
+impl<K, P> KeyedHeap<K, P>
+where
+    K: Hash + Eq + Clone,
+    P: Ord,
+{
+    pub fn new() -> Self {
+        KeyedHeap {
+            heap: Heap::new(by_priority as EntryOrder<K, P>),
+            handles: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.handles.contains_key(key)
+    }
+
+    /// Queue `key` with `priority`, or give it the new priority if it is
+    /// already queued.  Returns whether the key is new.
+    pub fn insert(&mut self, key: K, priority: P) -> bool {
+        if let Some(&handle) = self.handles.get(&key) {
+            let _ = self.heap.decrease_key(handle, Entry { key, priority });
+            return false;
+        }
+        let handle = self.heap.add(Entry {
+            key: key.clone(),
+            priority,
+        });
+        self.handles.insert(key, handle);
+        true
+    }
+
+    /// Give a queued key a new priority, higher or lower.  Returns whether
+    /// the key was queued.
+    pub fn update(&mut self, key: &K, priority: P) -> bool {
+        match self.handles.get(key) {
+            Some(&handle) => {
+                let entry = Entry {
+                    key: key.clone(),
+                    priority,
+                };
+                self.heap.decrease_key(handle, entry).is_ok()
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a key from the queue, returning whether it was queued.
+    pub fn remove(&mut self, key: &K) -> bool {
+        match self.handles.remove(key) {
+            Some(handle) => self.heap.remove(handle),
+            None => false,
+        }
+    }
+
+    /// The key with the lowest priority, and its priority, without removing
+    /// them.
+    pub fn peek(&mut self) -> Option<(&K, &P)> {
+        self.heap.peek().map(|e| (&e.key, &e.priority))
+    }
+
+    /// Remove and return the key with the lowest priority, and its priority.
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        let entry = self.heap.take()?;
+        self.handles.remove(&entry.key);
+        Some((entry.key, entry.priority))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyedHeap;
+
+    #[test]
+    fn keyed_heap_test() {
+        let mut queue = KeyedHeap::new();
+        assert!(queue.insert("a", 5));
+        assert!(queue.insert("b", 3));
+        assert!(queue.insert("c", 4));
+        assert!(!queue.insert("a", 1));
+        assert_eq!(Some((&"a", &1)), queue.peek());
+        assert!(queue.update(&"a", 9));
+        assert!(queue.remove(&"c"));
+        assert!(!queue.update(&"c", 0));
+        assert_eq!(2, queue.len());
+        assert_eq!(Some(("b", 3)), queue.pop());
+        assert!(!queue.contains_key(&"b"));
+        assert_eq!(Some(("a", 9)), queue.pop());
+        assert_eq!(None, queue.pop());
+    }
+}
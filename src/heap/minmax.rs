@@ -0,0 +1,340 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use std::cmp::Ordering;
+use std::cmp::Ordering::{Greater, Less};
+
+use super::super::SortedIterator;
+use super::PriorityQueue;
+
+/// A min-max heap: an implicit binary heap whose levels alternate between
+/// holding the lowest and the highest values below them, so that both ends
+/// can be found at the top.
+///
+/// * `from_vec` is O(n).
+/// * `add` is O(log n).
+/// * `peek_min` and `peek_max` are O(1).
+/// * `take_min` and `take_max` are O(log n).
+pub struct MinMaxHeap<T, F> {
+    data: Vec<T>,
+    by: F,
+}
+
+/// Whether the node at `i` is on a level of minimums.
+#[inline]
+fn is_min_level(i: usize) -> bool {
+    (i + 1).ilog2().is_multiple_of(2)
+}
+
+impl<T> MinMaxHeap<T, fn(&T, &T) -> Ordering>
+where
+    T: Ord,
+{
+    /// An empty heap ordered by `Ord`.
+    pub fn new_ord() -> Self {
+        MinMaxHeap::new(Ord::cmp)
+    }
+}
+
+impl<T, F> MinMaxHeap<T, F> {
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The lowest value, without removing it.
+    pub fn peek_min(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+    }
+
+    /// The values of the heap, in no particular order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T, F> MinMaxHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    /// An empty heap ordered by `by`.
+    pub fn new(by: F) -> Self {
+        MinMaxHeap::from_vec(Vec::new(), by)
+    }
+
+    /// Arrange the values of `data` into a heap, in place.
+    pub fn from_vec(data: Vec<T>, by: F) -> Self {
+        let mut heap = MinMaxHeap { data, by };
+        for i in (0..heap.data.len() / 2).rev() {
+            heap.trickle_down(i);
+        }
+        heap
+    }
+
+    pub fn add(&mut self, value: T) {
+        self.data.push(value);
+        self.push_up(self.data.len() - 1);
+    }
+
+    /// The highest value, without removing it.
+    pub fn peek_max(&self) -> Option<&T> {
+        self.max_index().map(|i| &self.data[i])
+    }
+
+    /// Remove and return the lowest value.
+    pub fn take_min(&mut self) -> Option<T> {
+        self.take_at(0)
+    }
+
+    /// Remove and return the highest value.
+    pub fn take_max(&mut self) -> Option<T> {
+        let i = self.max_index()?;
+        self.take_at(i)
+    }
+
+    /// A consuming iterator over the values in order, lowest first, that can
+    /// also be taken from the highest end.
+    pub fn into_iter_sorted(self) -> IntoIterSorted<T, F> {
+        IntoIterSorted { heap: self }
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        match self.data.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => match (self.by)(&self.data[1], &self.data[2]) {
+                Less => Some(2),
+                _ => Some(1),
+            },
+        }
+    }
+
+    fn take_at(&mut self, i: usize) -> Option<T> {
+        if i >= self.data.len() {
+            return None;
+        }
+        let value = self.data.swap_remove(i);
+        if i < self.data.len() {
+            self.trickle_down(i);
+        }
+        Some(value)
+    }
+
+    fn push_up(&mut self, i: usize) {
+        if i == 0 {
+            return;
+        }
+        let parent = (i - 1) / 2;
+        let towards = if is_min_level(i) { Greater } else { Less };
+        if (self.by)(&self.data[i], &self.data[parent]) == towards {
+            self.data.swap(i, parent);
+            self.push_up_by(parent, towards);
+        } else {
+            self.push_up_by(i, towards.reverse());
+        }
+    }
+
+    /// Move the value at `i` up through its grandparents while it compares
+    /// `towards` them.
+    fn push_up_by(&mut self, mut i: usize, towards: Ordering) {
+        while i > 2 {
+            let grandparent = ((i - 1) / 2 - 1) / 2;
+            if (self.by)(&self.data[i], &self.data[grandparent]) != towards {
+                break;
+            }
+            self.data.swap(i, grandparent);
+            i = grandparent;
+        }
+    }
+
+    fn trickle_down(&mut self, i: usize) {
+        let towards = if is_min_level(i) { Less } else { Greater };
+        self.trickle_down_by(i, towards);
+    }
+
+    /// Move the value at `i` down, on levels where each value compares
+    /// `towards` everything below it.
+    fn trickle_down_by(&mut self, mut i: usize, towards: Ordering) {
+        let len = self.data.len();
+        loop {
+            let first_child = 2 * i + 1;
+            if first_child >= len {
+                return;
+            }
+            // The most extreme of the children and grandchildren.
+            let first_grandchild = 2 * first_child + 1;
+            let mut m = first_child;
+            let candidates = (first_child + 1..first_child + 2)
+                .chain(first_grandchild..first_grandchild + 4)
+                .filter(|&j| j < len);
+            for j in candidates {
+                if (self.by)(&self.data[j], &self.data[m]) == towards {
+                    m = j;
+                }
+            }
+            if (self.by)(&self.data[m], &self.data[i]) != towards {
+                return;
+            }
+            self.data.swap(m, i);
+            if m < first_grandchild {
+                return;
+            }
+            let parent = (m - 1) / 2;
+            if (self.by)(&self.data[m], &self.data[parent]) == towards.reverse() {
+                self.data.swap(m, parent);
+            }
+            i = m;
+        }
+    }
+}
+
+impl<T, F> PriorityQueue<T> for MinMaxHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn add(&mut self, value: T) {
+        MinMaxHeap::add(self, value)
+    }
+
+    fn take(&mut self) -> Option<T> {
+        self.take_min()
+    }
+
+    fn peek(&mut self) -> Option<&T> {
+        self.peek_min()
+    }
+
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// Takes each value from a min-max heap in turn, from either end.
+pub struct IntoIterSorted<T, F> {
+    heap: MinMaxHeap<T, F>,
+}
+
+impl<T, F> Iterator for IntoIterSorted<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.heap.take_min()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.heap.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, F> DoubleEndedIterator for IntoIterSorted<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn next_back(&mut self) -> Option<T> {
+        self.heap.take_max()
+    }
+}
+
+impl<T, F> SortedIterator for IntoIterSorted<T, F> where F: Fn(&T, &T) -> Ordering {}
+
+/// The lowest and the highest few values of an iterator, found through a
+/// `MinMaxHeap`: the heap is built in O(n), and each value taken from either
+/// end costs O(log n).
+pub trait Extremes: Iterator + Sized {
+    /// The lowest `k` values, lowest first, and the highest `k` of the rest,
+    /// highest first.  With fewer than `2k` values, the highest are only
+    /// those left over after the lowest.
+    fn extremes(self, k: usize) -> (Vec<Self::Item>, Vec<Self::Item>)
+    where
+        Self::Item: Ord,
+    {
+        self.extremes_by(k, Ord::cmp)
+    }
+
+    fn extremes_by<F>(self, k: usize, by: F) -> (Vec<Self::Item>, Vec<Self::Item>)
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering,
+    {
+        let mut heap = MinMaxHeap::from_vec(self.collect(), by);
+        let lowest = (0..k).map_while(|_| heap.take_min()).collect();
+        let highest = (0..k).map_while(|_| heap.take_max()).collect();
+        (lowest, highest)
+    }
+}
+
+impl<I> Extremes for I where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::{Extremes, MinMaxHeap};
+
+    #[test]
+    fn min_max_heap_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 100)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        let mut heap = MinMaxHeap::new_ord();
+        let mut sorted: Vec<u32> = Vec::new();
+        for &x in before.iter() {
+            heap.add(x);
+            let i = sorted.partition_point(|&y| y < x);
+            sorted.insert(i, x);
+            if rng.gen_range(0, 4) == 0 {
+                assert_eq!(sorted.pop(), heap.take_max());
+            }
+            if rng.gen_range(0, 4) == 0 && !sorted.is_empty() {
+                assert_eq!(Some(sorted.remove(0)), heap.take_min());
+            }
+            assert_eq!(sorted.first(), heap.peek_min());
+            assert_eq!(sorted.last(), heap.peek_max());
+        }
+
+        let heap = MinMaxHeap::from_vec(before, |a: &u32, b: &u32| a.cmp(b));
+        let mut iter = heap.into_iter_sorted();
+        assert_eq!(expected.last(), iter.next_back().as_ref());
+        assert_eq!(expected.first(), iter.next().as_ref());
+        let middle: Vec<u32> = iter.collect();
+        assert_eq!(&expected[1..999], &middle[..]);
+    }
+
+    #[test]
+    fn extremes_test() {
+        let before: Vec<u32> = vec![9, 7, 1, 1, 6, 3, 1, 4, 22];
+        let (lowest, highest) = before.iter().cloned().extremes(2);
+        assert_eq!(vec![1, 1], lowest);
+        assert_eq!(vec![22, 9], highest);
+
+        let (lowest, highest) = before.iter().cloned().extremes_by(3, |a, b| b.cmp(a));
+        assert_eq!(vec![22, 9, 7], lowest);
+        assert_eq!(vec![1, 1, 1], highest);
+
+        let (lowest, highest) = before.into_iter().extremes(6);
+        assert_eq!(vec![1, 1, 1, 3, 4, 6], lowest);
+        assert_eq!(vec![22, 9, 7], highest);
+    }
+}
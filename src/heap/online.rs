@@ -0,0 +1,124 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+
+use super::{Heap, IntoIter};
+
+/// Sorting with values added and the lowest taken in any order, as a
+/// scheduler or event queue does.  This is a `Heap`, without handles, under
+/// the names of a sort: `add` is O(1) and `pop_min` O(log n) amortized.
+#[derive(Clone)]
+pub struct OnlineSorter<T, F = fn(&T, &T) -> Ordering> {
+    heap: Heap<T, F>,
+}
+
+impl<T> OnlineSorter<T>
+where
+    T: Ord,
+{
+    pub fn new() -> Self {
+        OnlineSorter::by(Ord::cmp)
+    }
+}
+
+impl<T> Default for OnlineSorter<T>
+where
+    T: Ord,
+{
+    fn default() -> Self {
+        OnlineSorter::new()
+    }
+}
+
+impl<T, F> OnlineSorter<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    /// An empty sorter, ordering values by `by` as `sorted_by` does.
+    pub fn by(by: F) -> Self {
+        OnlineSorter {
+            heap: Heap::new(by),
+        }
+    }
+
+    pub fn add(&mut self, value: T) {
+        self.heap.add(value);
+    }
+
+    /// Remove and return the lowest value.
+    pub fn pop_min(&mut self) -> Option<T> {
+        self.heap.take()
+    }
+
+    /// The lowest value, without removing it.
+    pub fn peek_min(&mut self) -> Option<&T> {
+        self.heap.peek()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The values left, lowest first.
+    pub fn into_sorted(self) -> IntoIter<T, F> {
+        self.heap.into_iter_sorted()
+    }
+}
+
+impl<T, F> Extend<T> for OnlineSorter<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for OnlineSorter<T>
+where
+    T: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut sorter = OnlineSorter::new();
+        sorter.extend(iter);
+        sorter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OnlineSorter;
+
+    #[test]
+    fn online_sorter_test() {
+        let mut sorter: OnlineSorter<u32> = vec![5, 3, 8].into_iter().collect();
+        assert_eq!(Some(3), sorter.pop_min());
+        sorter.add(1);
+        sorter.add(9);
+        assert_eq!(Some(&1), sorter.peek_min());
+        assert_eq!(Some(1), sorter.pop_min());
+        assert_eq!(Some(5), sorter.pop_min());
+        sorter.extend(vec![4, 6]);
+        assert_eq!(vec![4, 6, 8, 9], sorter.into_sorted().collect::<Vec<u32>>());
+
+        let mut sorter = OnlineSorter::by(|a: &&str, b: &&str| b.len().cmp(&a.len()));
+        sorter.extend(vec!["a", "ccc", "bb"]);
+        assert_eq!(Some("ccc"), sorter.pop_min());
+        assert_eq!(2, sorter.len());
+    }
+}
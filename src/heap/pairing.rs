@@ -0,0 +1,231 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use std::cmp::Ordering;
+use std::cmp::Ordering::Greater;
+use std::mem;
+
+use super::PriorityQueue;
+
+struct Node<T> {
+    value: T,
+    children: Vec<Node<T>>,
+}
+
+/// A pairing heap: a single heap-ordered tree with no structural rules, which
+/// is restructured only when the minimum is taken.
+///
+/// * `add` and `meld` are O(1).
+/// * `peek` is O(1).
+/// * `take` is O(log n) amortized.
+///
+/// This is usually faster than `Heap` when values are added much more often
+/// than they are taken.
+pub struct PairingHeap<T, F> {
+    root: Option<Node<T>>,
+    len: usize,
+    by: F,
+}
+
+impl<T> PairingHeap<T, fn(&T, &T) -> Ordering>
+where
+    T: Ord,
+{
+    /// An empty heap from which the lowest value is taken first.
+    pub fn new_min() -> Self {
+        PairingHeap::new(Ord::cmp)
+    }
+}
+
+impl<T, F> PairingHeap<T, F> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The lowest value, without removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.value)
+    }
+
+    pub fn clear(&mut self) {
+        drop_nodes(self.root.take());
+        self.len = 0;
+    }
+}
+
+impl<T, F> PairingHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    /// An empty heap from which the value that compares lowest by `by` is
+    /// taken first.
+    pub fn new(by: F) -> Self {
+        PairingHeap {
+            root: None,
+            len: 0,
+            by,
+        }
+    }
+
+    pub fn add(&mut self, value: T) {
+        self.len += 1;
+        let node = Node {
+            value,
+            children: Vec::new(),
+        };
+        self.root = Some(match self.root.take() {
+            Some(root) => link(&self.by, root, node),
+            None => node,
+        });
+    }
+
+    /// Remove and return the lowest value.
+    pub fn take(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        self.len -= 1;
+        let mut children = root.children;
+
+        // The standard two passes: link the children in pairs from the
+        // left, then link the pairs into one tree from the right.
+        let mut paired = Vec::with_capacity(children.len().div_ceil(2));
+        let mut drain = children.drain(..);
+        while let Some(a) = drain.next() {
+            paired.push(match drain.next() {
+                Some(b) => link(&self.by, a, b),
+                None => a,
+            });
+        }
+        self.root = paired
+            .into_iter()
+            .rev()
+            .reduce(|acc, node| link(&self.by, node, acc));
+        Some(root.value)
+    }
+
+    /// Combine two heaps into one.
+    pub fn meld(mut self, mut other: Self) -> Self {
+        self.len += other.len;
+        other.len = 0;
+        self.root = match (self.root.take(), other.root.take()) {
+            (Some(a), Some(b)) => Some(link(&self.by, a, b)),
+            (a, b) => a.or(b),
+        };
+        self
+    }
+}
+
+impl<T, F> Drop for PairingHeap<T, F> {
+    fn drop(&mut self) {
+        drop_nodes(self.root.take());
+    }
+}
+
+impl<T, F> PriorityQueue<T> for PairingHeap<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn add(&mut self, value: T) {
+        PairingHeap::add(self, value)
+    }
+
+    fn take(&mut self) -> Option<T> {
+        PairingHeap::take(self)
+    }
+
+    fn peek(&mut self) -> Option<&T> {
+        PairingHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Make the tree with the greater root a child of the other.
+fn link<T, F>(by: &F, mut a: Node<T>, mut b: Node<T>) -> Node<T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if by(&a.value, &b.value) == Greater {
+        b.children.push(a);
+        b
+    } else {
+        a.children.push(b);
+        a
+    }
+}
+
+/// A pairing heap can be as deep as it is large, so it is dropped without
+/// recursion.
+fn drop_nodes<T>(root: Option<Node<T>>) {
+    let mut stack: Vec<Node<T>> = root.into_iter().collect();
+    while let Some(mut node) = stack.pop() {
+        stack.append(&mut mem::take(&mut node.children));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use std::cmp::Ordering;
+
+    use super::super::{Heap, PriorityQueue};
+    use super::PairingHeap;
+
+    fn sorted<Q: PriorityQueue<u32>>(queue: &mut Q, values: &[u32]) -> Vec<u32> {
+        for x in values {
+            queue.add(*x);
+        }
+        let mut after = Vec::new();
+        while let Some(x) = queue.take() {
+            after.push(x);
+        }
+        after
+    }
+
+    fn reverse(a: &u32, b: &u32) -> Ordering {
+        b.cmp(a)
+    }
+
+    #[test]
+    fn pairing_heap_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 100)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+        assert_eq!(expected, sorted(&mut PairingHeap::new_min(), &before));
+        assert_eq!(expected, sorted(&mut Heap::new_min(), &before));
+
+        let a = PairingHeap::new(reverse);
+        let mut b = PairingHeap::new(reverse);
+        b.add(3);
+        b.add(9);
+        let heap = a.meld(b);
+        assert_eq!(2, heap.len());
+        assert_eq!(Some(&9), heap.peek());
+    }
+
+    #[test]
+    fn deep_drop_test() {
+        // Adding descending values makes a single chain.
+        let mut heap = PairingHeap::new_min();
+        for x in (0..1000000).rev() {
+            heap.add(x);
+        }
+        assert_eq!(Some(&0), heap.peek());
+    }
+}
@@ -0,0 +1,85 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Sorting by a key borrowed from each value.
+//!
+//! A key function for `sorted_by_key` must return an owned key, so a field
+//! that is not `Copy` has to be cloned for every comparison.  Here the key
+//! function returns a reference into the value instead, `|p: &Person|
+//! &p.name`, and the keys are compared where they are.
+
+use std::cmp::Ordering;
+
+use super::{Compare, LazySortIteratorBy, SortedBy};
+
+/// Order by the key that `F` borrows from each value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByKeyRef<F>(pub F);
+
+impl<T, K, F> Compare<T> for ByKeyRef<F>
+where
+    K: Ord + ?Sized,
+    F: Fn(&T) -> &K,
+{
+    #[inline(always)]
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        (self.0)(a).cmp((self.0)(b))
+    }
+}
+
+pub trait SortedByKeyRef: Iterator + Sized {
+    fn sorted_by_key_ref<K, F>(self, key: F) -> LazySortIteratorBy<Self::Item, ByKeyRef<F>>
+    where
+        K: Ord + ?Sized,
+        F: Fn(&Self::Item) -> &K,
+    {
+        self.sorted_with(ByKeyRef(key))
+    }
+}
+
+impl<I> SortedByKeyRef for I where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    use super::SortedByKeyRef;
+
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    #[test]
+    fn sorted_by_key_ref_test() {
+        let people = vec![
+            Person {
+                name: "cy".to_string(),
+                age: 35,
+            },
+            Person {
+                name: "ada".to_string(),
+                age: 41,
+            },
+            Person {
+                name: "bob".to_string(),
+                age: 23,
+            },
+        ];
+        let ages: Vec<u32> = people
+            .into_iter()
+            .sorted_by_key_ref(|p: &Person| &p.name)
+            .map(|p| p.age)
+            .collect();
+        assert_eq!(vec![41, 23, 35], ages);
+
+        let words = ["pear".to_string(), "fig".to_string()];
+        let after: Vec<&String> = words.iter().sorted_by_key_ref(|s| s.as_str()).collect();
+        assert_eq!(vec!["fig", "pear"], after);
+    }
+}
@@ -12,8 +12,115 @@
 #![crate_name = "lazysort"]
 #![cfg_attr(feature = "nightly", feature(test))]
 
+#[cfg(feature = "derive")]
+extern crate lazysort_derive;
+#[cfg(all(feature = "mmap", unix))]
+extern crate libc;
+
 use std::cmp::Ordering;
 use std::cmp::Ordering::{Greater, Less};
+use std::collections::{BinaryHeap, VecDeque};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+pub mod algo;
+mod approx;
+mod arena;
+mod assume;
+mod audit;
+mod bounded;
+mod bucket;
+mod checkpoint;
+pub mod compare;
+mod compat;
+mod cooperative;
+mod counting;
+mod drain;
+mod entries;
+pub mod external;
+mod fixed;
+mod fork;
+mod group;
+pub mod heap;
+mod indirect;
+mod keyref;
+mod lookahead;
+#[cfg(all(feature = "mmap", unix))]
+pub mod mmap;
+mod network;
+mod permute;
+mod policy;
+pub mod prelude;
+mod radix;
+mod sample;
+mod select;
+mod sketch;
+mod slice;
+mod sorter;
+mod stable;
+#[cfg(feature = "test-utils")]
+pub mod testing;
+mod together;
+pub mod topo;
+pub mod tune;
+#[cfg(feature = "debug-viz")]
+pub mod viz;
+mod weighted;
+mod work;
+
+use tune::Thresholds;
+use work::WorkStack;
+
+pub use approx::{ApproxSortIterator, ApproxSortIteratorBy, SortedApprox};
+pub use arena::{ArenaSortIterator, ArenaSortIteratorBy, SortArena};
+pub use assume::{AssumeSorted, PreSortedIterator};
+pub use audit::{ComparatorAuditor, Violation};
+pub use bounded::BoundedTopK;
+pub use bucket::{BucketSortIterator, BucketSortIteratorByKey, SortedBucketed};
+pub use checkpoint::Checkpoint;
+pub use compare::{BySortKey, Compare, Natural, SortKey};
+pub use compat::{CachedKeySortIterator, ItertoolsCompat};
+pub use cooperative::{CooperativeSort, SortedCooperatively};
+pub use counting::{
+    CountingKey, CountingSortIterator, CountingSortIteratorByKey, SmallDomain, SortedCounting,
+};
+pub use drain::{LazyDrainSorted, VecDrainSorted, VecDrainSortedBy};
+pub use entries::{ByValue, SortedByValue};
+pub use fixed::{
+    CapacityError, FixedLazySortIterator, FixedLazySortIteratorBy, LazySortArray, LazySortArrayBy,
+    SortedArray, SortedFixed,
+};
+pub use fork::{Forkable, IntoForkable};
+pub use group::{
+    DedupByKey, DedupKeep, EqualGroups, EqualGroupsBy, GroupSorted, GroupedTopK, Keep,
+    TopKPerGroup, TopKPerGroupBy,
+};
+pub use heap::{Extremes, HeapSortIterator, HeapSortIteratorBy, QueueSortIterator, SortedViaHeap};
+pub use indirect::{IndirectSortIterator, IndirectSortIteratorBy, SortedIndirect};
+pub use keyref::{ByKeyRef, SortedByKeyRef};
+#[cfg(feature = "derive")]
+pub use lazysort_derive::SortKey;
+pub use lookahead::{BackgroundLookahead, Lookahead, LookaheadBuffer};
+pub use permute::{apply_permutation, apply_permutation_lazy, Permute};
+pub use policy::{
+    ErrPolicy, NonePolicy, OptionSortIterator, ResultSortIterator, SortedOptions, SortedResults,
+};
+pub use radix::{RadixKey, RadixSortIterator, RadixSortIteratorByKey, SortedRadix};
+pub use sample::SampleSorted;
+pub use select::{
+    nth_element, nth_element_by, nth_element_by_key, nth_element_deterministic_by, partial_sort,
+    partial_sort_by, partial_sort_by_key,
+};
+pub use sketch::QuantileSketch;
+pub use slice::{
+    LazySortSlice, SliceSortIterMut, SliceSortIterMutBy, SliceSortIterator, SliceSortIteratorBy,
+};
+pub use sorter::Sorter;
+pub use stable::{SortedStable, StableSortIterator, StableSortIteratorBy};
+pub use together::{
+    sort_together, sort_together_by, Columns, TogetherSortIterator, TogetherSortIteratorBy,
+};
+pub use weighted::WeightedQuantiles;
 
 fn pivot(lower: usize, upper: usize) -> usize {
     upper + ((lower - upper) / 2)
@@ -29,6 +136,31 @@ where
     by(data.get_unchecked(a), data.get_unchecked(b))
 }
 
+/// How many bytes ahead of the partition loop to prefetch.
+#[cfg(feature = "prefetch")]
+const PREFETCH_BYTES: usize = 512;
+
+/// Hint that `data[i]` will be read soon.  Prefetching is only a hint, so `i`
+/// may be out of bounds.
+#[cfg(feature = "prefetch")]
+#[inline(always)]
+#[allow(unused_variables)]
+fn prefetch<T>(data: &[T], i: usize) {
+    #[cfg(all(
+        feature = "prefetch",
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse"
+    ))]
+    unsafe {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+        _mm_prefetch::<_MM_HINT_T0>(data.as_ptr().wrapping_add(i) as *const i8);
+    }
+}
+
 fn partition<F, T>(by: &F, data: &mut [T], lower: usize, upper: usize, p: usize) -> usize
 where
     F: Fn(&T, &T) -> Ordering,
@@ -44,7 +176,12 @@ where
 
         data.swap(lower, p);
 
+        #[cfg(feature = "prefetch")]
+        let ahead = (PREFETCH_BYTES / std::mem::size_of::<T>().max(1)).max(1);
+
         while i < lower {
+            #[cfg(feature = "prefetch")]
+            prefetch(data, i + ahead);
             if cmp_by(by, data, i, lower) == Greater {
                 if i != nextp {
                     data.swap(i, nextp);
@@ -55,22 +192,58 @@ where
         }
 
         data.swap(nextp, lower);
+        #[cfg(feature = "debug-viz")]
+        viz::note(lower, upper, &[nextp]);
         nextp
     }
 }
 
-fn qsort<F, T>(
+/// The storage `qsort` works over, values are pushed and popped at the end.
+trait Stack<T> {
+    fn push(&mut self, value: T);
+    fn pop(&mut self) -> Option<T>;
+}
+
+impl<T> Stack<T> for Vec<T> {
+    #[inline]
+    fn push(&mut self, value: T) {
+        Vec::push(self, value)
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+}
+
+fn qsort<F, T, D, W>(by: &F, data: &mut D, work: &mut W, lower: usize, upper: usize) -> T
+where
+    F: Fn(&T, &T) -> Ordering,
+    D: Stack<T> + DerefMut<Target = [T]>,
+    W: Stack<(usize, usize)>,
+{
+    let small_sort = Thresholds::default().small_sort;
+    qsort_tuned(by, data, work, lower, upper, small_sort)
+}
+
+/// As `qsort`, sorting ranges of up to `small_sort` values with a sorting
+/// network.
+fn qsort_tuned<F, T, D, W>(
     by: &F,
-    data: &mut Vec<T>,
-    work: &mut Vec<(usize, usize)>,
+    data: &mut D,
+    work: &mut W,
     lower: usize,
     upper: usize,
+    small_sort: usize,
 ) -> T
 where
     F: Fn(&T, &T) -> Ordering,
+    D: Stack<T> + DerefMut<Target = [T]>,
+    W: Stack<(usize, usize)>,
 {
     // If lower and upper are the same, then just pop the next value
     // If lower and upper are adjacent, then manually swap depending on ordering
+    // If there are only a handful, sort them all with a sorting network
     // everything else, do the next stage of a quick sort
     match lower - upper {
         0 => data.pop().expect("Non empty vector"),
@@ -81,44 +254,910 @@ where
             work.push((upper, upper));
             data.pop().expect("Non empty vector")
         },
+        n if n < small_sort => {
+            network::sort(by, data, lower, upper);
+            for i in upper..lower {
+                work.push((i, i));
+            }
+            data.pop().expect("Non empty vector")
+        }
         _ => {
             let p = pivot(lower, upper);
             let p = partition(by, data, lower, upper, p);
-            if p == lower {
+            if p == upper {
+                let eq_lower = gather_equal(by, data, work, lower, upper);
+                if lower > eq_lower {
+                    work.push((eq_lower, eq_lower));
+                    qsort_tuned(by, data, work, lower, eq_lower + 1, small_sort)
+                } else {
+                    data.pop().expect("Non empty vector")
+                }
+            } else if p == lower {
                 work.push((p - 1, upper));
-                qsort(by, data, work, lower, p)
+                qsort_tuned(by, data, work, lower, p, small_sort)
             } else {
                 work.push((p, upper));
-                qsort(by, data, work, lower, p + 1)
+                qsort_tuned(by, data, work, lower, p + 1, small_sort)
+            }
+        }
+    }
+}
+
+/// After a partition leaves its pivot at `upper`, so that nothing in the range
+/// is greater, gather the values equal to it.  Otherwise a long run of one
+/// value would be partitioned again and again, each time taking off only the
+/// pivot.  The equal values, which need no more sorting, are pushed one to a
+/// range, all but the last, whose index is returned for the caller to push or
+/// pop; the values below them are from there to `lower`.
+fn gather_equal<F, T, W>(by: &F, data: &mut [T], work: &mut W, lower: usize, upper: usize) -> usize
+where
+    F: Fn(&T, &T) -> Ordering,
+    W: Stack<(usize, usize)>,
+{
+    let (eq_lower, _) = algo::three_way(by, data, lower, upper, upper);
+    for i in upper..eq_lower {
+        work.push((i, i));
+    }
+    eq_lower
+}
+
+/// Ranges of fewer values than this are left to `qsort`.
+const DUAL_PIVOT_MIN: usize = 32;
+
+/// As `qsort`, but partitioning around two pivots at once: the range is split
+/// into the values above the higher pivot, those between the pivots and those
+/// below the lower pivot, and only the last is followed.
+fn qsort_dual<F, T>(
+    by: &F,
+    data: &mut Vec<T>,
+    work: &mut WorkStack,
+    lower: usize,
+    upper: usize,
+    small_sort: usize,
+) -> T
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let n = lower - upper;
+    if n < DUAL_PIVOT_MIN {
+        return qsort_tuned(by, data, work, lower, upper, small_sort);
+    }
+
+    unsafe {
+        // The higher pivot is kept at `upper`, and the lower at `lower`.
+        data.swap(upper, upper + n / 3);
+        data.swap(lower, lower - n / 3);
+        if cmp_by(by, data, upper, lower) == Less {
+            data.swap(upper, lower);
+        }
+
+        // (upper, g) are above the higher pivot, [g, k) between the pivots,
+        // and (l, lower) below the lower pivot.
+        let mut g = upper + 1;
+        let mut k = upper + 1;
+        let mut l = lower - 1;
+        while k <= l {
+            if cmp_by(by, data, k, upper) == Greater {
+                data.swap(k, g);
+                g += 1;
+                k += 1;
+            } else if cmp_by(by, data, k, lower) == Less {
+                data.swap(k, l);
+                l -= 1;
+            } else {
+                k += 1;
+            }
+        }
+        let high = g - 1;
+        let low = l + 1;
+        data.swap(upper, high);
+        data.swap(lower, low);
+        #[cfg(feature = "debug-viz")]
+        viz::note(lower, upper, &[high, low]);
+
+        if high > upper {
+            work.push((high - 1, upper));
+        }
+        work.push((high, high));
+        if l >= g {
+            if cmp_by(by, data, high, low) == Greater {
+                work.push((l, g));
+            } else {
+                // The pivots are equal, and so is everything between them.
+                for i in g..=l {
+                    work.push((i, i));
+                }
+            }
+        }
+        if lower > low {
+            work.push((low, low));
+            qsort_dual(by, data, work, lower, low + 1, small_sort)
+        } else {
+            data.pop().expect("Non empty vector")
+        }
+    }
+}
+
+/// As `qsort`, but pivoting on the median of medians and partitioning three
+/// ways, which bounds the work to find the next value to linear in the worst
+/// case.
+fn qsort_mom<F, T>(
+    by: &F,
+    data: &mut Vec<T>,
+    work: &mut WorkStack,
+    lower: usize,
+    upper: usize,
+    small_sort: usize,
+) -> T
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if lower - upper < small_sort {
+        return qsort_tuned(by, data, work, lower, upper, small_sort);
+    }
+    let p = algo::median_of_medians_by(by, data, lower, upper);
+    let (eq_lower, eq_upper) = algo::three_way(by, data, lower, upper, p);
+    if eq_upper > upper {
+        work.push((eq_upper - 1, upper));
+    }
+    // The values equal to the pivot need no more sorting.
+    for i in eq_upper..eq_lower {
+        work.push((i, i));
+    }
+    if lower > eq_lower {
+        work.push((eq_lower, eq_lower));
+        qsort_mom(by, data, work, lower, eq_lower + 1, small_sort)
+    } else {
+        data.pop().expect("Non empty vector")
+    }
+}
+
+/// Find the next value, from the range `(lower, upper)` just taken from the
+/// top of the work list, as `partitioning` says.
+fn next_by<F, T>(
+    by: &F,
+    data: &mut Vec<T>,
+    work: &mut WorkStack,
+    lower: usize,
+    upper: usize,
+    partitioning: Partitioning,
+    thresholds: Thresholds,
+) -> T
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let small_sort = thresholds.small_sort;
+    match partitioning {
+        Partitioning::SinglePivot => qsort_tuned(by, data, work, lower, upper, small_sort),
+        Partitioning::DualPivot => qsort_dual(by, data, work, lower, upper, small_sort),
+        Partitioning::MedianOfMedians => qsort_mom(by, data, work, lower, upper, small_sort),
+    }
+}
+
+/// As `settle`, as `partitioning` says.  Only the median of medians differs:
+/// the next value is found as `next` would, and put back.
+fn settle_by<F, T>(
+    by: &F,
+    data: &mut Vec<T>,
+    work: &mut WorkStack,
+    partitioning: Partitioning,
+    thresholds: Thresholds,
+) where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let small_sort = thresholds.small_sort;
+    match partitioning {
+        Partitioning::MedianOfMedians => {
+            if let Some((lower, upper)) = work.pop() {
+                let value = qsort_mom(by, data, work, lower, upper, small_sort);
+                work.push((data.len(), data.len()));
+                data.push(value);
+            }
+        }
+        _ => settle(by, data, work, small_sort),
+    }
+}
+
+/// Drop the lowest `n` of the remaining values, selecting them rather than
+/// finding them one at a time, and leave the rest as one range.
+fn skip_lowest<F, T>(
+    by: &F,
+    data: &mut Vec<T>,
+    work: &mut WorkStack,
+    n: usize,
+    partitioning: Partitioning,
+) where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = data.len();
+    if n == 0 {
+        return;
+    }
+    if n >= len {
+        data.clear();
+        work.clear();
+        return;
+    }
+    if !work.is_empty() {
+        let at = len - n;
+        let deterministic = partitioning == Partitioning::MedianOfMedians;
+        algo::select_with(by, data, len - 1, 0, at, deterministic);
+        work.clear();
+        work.push((at - 1, 0));
+    }
+    data.truncate(len - n);
+}
+
+/// Fully sort the remaining values, leaving them in the order they would
+/// have been popped.  The work list is emptied: with no work remaining, `next`
+/// simply pops from the sorted data.
+fn sort_remaining<F, T>(by: &F, data: &mut [T], work: &mut WorkStack)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if !work.is_empty() {
+        data.sort_unstable_by(|a, b| by(b, a));
+        work.clear();
+    }
+}
+
+/// Fully sort the values in the ranges on `work`, partitioning them as the
+/// lazy sort does, until the list is empty.  Unlike `sort_remaining` this
+/// does not panic on a comparator that is not a total order.
+fn finish<F, T>(by: &F, data: &mut [T], work: &mut WorkStack, small_sort: usize)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    while let Some((lower, upper)) = work.pop() {
+        match lower - upper {
+            0 => (),
+            n if n < small_sort => network::sort(by, data, lower, upper),
+            _ => {
+                let p = partition(by, data, lower, upper, pivot(lower, upper));
+                let p = if p == upper {
+                    algo::three_way(by, data, lower, upper, upper).0
+                } else {
+                    work.push((p - 1, upper));
+                    p
+                };
+                if lower > p {
+                    work.push((lower, p + 1));
+                }
+            }
+        }
+    }
+}
+
+/// Split the remaining values into the lowest `n`, sorted, and the rest, in no
+/// particular order.
+fn split_sorted<F, T>(
+    by: &F,
+    mut data: Vec<T>,
+    mut work: WorkStack,
+    n: usize,
+    partitioning: Partitioning,
+    thresholds: Thresholds,
+) -> (Vec<T>, Vec<T>)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = data.len();
+    let at = len - n.min(len);
+    if at > 0 && !work.is_empty() {
+        let deterministic = partitioning == Partitioning::MedianOfMedians;
+        algo::select_with(by, &mut data, len - 1, 0, at, deterministic);
+        work.clear();
+        work.push((len - 1, at));
+    }
+    finish(by, &mut data, &mut work, thresholds.small_sort);
+    let mut sorted = data.split_off(at);
+    sorted.reverse();
+    (sorted, data)
+}
+
+/// Sort the lowest `n` of the remaining values into place at the end of
+/// `data`, each in its own range, with the rest in one range below them.
+fn select_first<F, T>(
+    by: &F,
+    data: &mut [T],
+    work: &mut WorkStack,
+    n: usize,
+    partitioning: Partitioning,
+    thresholds: Thresholds,
+) where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = data.len();
+    if work.is_empty() || n == 0 || n >= len {
+        return;
+    }
+    let at = len - n;
+    let deterministic = partitioning == Partitioning::MedianOfMedians;
+    algo::select_with(by, data, len - 1, 0, at, deterministic);
+    work.clear();
+    work.push((len - 1, at));
+    finish(by, data, work, thresholds.small_sort);
+    work.push((at - 1, 0));
+    for i in at..len {
+        work.push((i, i));
+    }
+}
+
+/// Remove the lowest `n` of the remaining values, or all of them if there are
+/// fewer, lowest first.  The ranges on the work list that lie wholly among them
+/// are sorted whole, and the range that holds both some of them and some not
+/// is split by selecting within it, leaving the rest of its values as a range.
+fn take_lowest<F, T>(
+    by: &F,
+    data: &mut Vec<T>,
+    work: &mut WorkStack,
+    n: usize,
+    partitioning: Partitioning,
+    thresholds: Thresholds,
+) -> Vec<T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = data.len();
+    if n == 0 || len == 0 {
+        return Vec::new();
+    }
+    let at = len - n.min(len);
+    let mut lowest = WorkStack::new();
+    while let Some((lower, upper)) = work.pop() {
+        if upper >= at {
+            lowest.push((lower, upper));
+            if upper == at {
+                break;
+            }
+        } else {
+            let deterministic = partitioning == Partitioning::MedianOfMedians;
+            algo::select_with(by, data, lower, upper, at, deterministic);
+            lowest.push((lower, at));
+            work.push((at - 1, upper));
+            break;
+        }
+    }
+    finish(by, data, &mut lowest, thresholds.small_sort);
+    let mut batch = data.split_off(at);
+    batch.reverse();
+    batch
+}
+
+/// Partition until the next value is in place at the end of `data`, with its
+/// own range on top of the work list, without removing it.
+fn settle<F, T>(by: &F, data: &mut [T], work: &mut WorkStack, small_sort: usize)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    while let Some((lower, upper)) = work.pop() {
+        match lower - upper {
+            0 => {
+                work.push((lower, upper));
+                return;
+            }
+            n if n < small_sort => {
+                network::sort(by, data, lower, upper);
+                for i in upper..=lower {
+                    work.push((i, i));
+                }
+                return;
+            }
+            _ => {
+                let p = partition(by, data, lower, upper, pivot(lower, upper));
+                let p = if p == upper {
+                    gather_equal(by, data, work, lower, upper)
+                } else {
+                    work.push((p - 1, upper));
+                    p
+                };
+                work.push((p, p));
+                if lower > p {
+                    work.push((lower, p + 1));
+                }
+            }
+        }
+    }
+}
+
+/// Add `value` to the values still to be sorted.  If they are already fully
+/// sorted it is inserted in its place.  Otherwise it is pushed at the end of
+/// `data`, joining the range of the next values, which are merged with the
+/// ranges after them until the new value is no greater than any value in the
+/// range beyond; so a value that belongs near the end may undo the work done
+/// partitioning the values before it.
+fn insert<F, T>(by: &F, data: &mut Vec<T>, work: &mut WorkStack, value: T)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let mut upper = match work.pop() {
+        Some((_, upper)) => upper,
+        None => {
+            let i = data.partition_point(|x| by(x, &value) == Greater);
+            data.insert(i, value);
+            return;
+        }
+    };
+    let at = data.len();
+    data.push(value);
+    while let Some((lower, next_upper)) = work.pop() {
+        debug_assert_eq!(lower + 1, upper);
+        if data[next_upper..=lower]
+            .iter()
+            .all(|x| by(x, &data[at]) != Less)
+        {
+            work.push((lower, next_upper));
+            break;
+        }
+        upper = next_upper;
+    }
+    work.push((at, upper));
+}
+
+/// Add the values of `batch`, which must already be in order, to the values
+/// still to be sorted.  Each value of the batch is placed in the range of the
+/// work list its value falls in, and the ranges widened to match, so no
+/// partitioning done so far is lost; this costs a pass over the remaining
+/// values to find where each range begins, and moves each value once.  If the
+/// remaining values are already fully sorted the two are simply merged.
+fn merge_in<F, T, I>(by: &F, data: &mut Vec<T>, work: &mut WorkStack, batch: I)
+where
+    F: Fn(&T, &T) -> Ordering,
+    I: IntoIterator<Item = T>,
+{
+    let batch: Vec<T> = batch.into_iter().collect();
+    if batch.is_empty() {
+        return;
+    }
+    let remaining = mem::replace(data, Vec::with_capacity(data.len() + batch.len()));
+
+    // The ranges, lowest values first.
+    let mut ranges = Vec::new();
+    while let Some(range) = work.pop() {
+        ranges.push(range);
+    }
+
+    if ranges.is_empty() {
+        let mut remaining = remaining.into_iter().peekable();
+        let mut batch = batch.into_iter().rev().peekable();
+        loop {
+            let from_batch = match (remaining.peek(), batch.peek()) {
+                (Some(a), Some(b)) => by(a, b) == Less,
+                (None, Some(_)) => true,
+                _ => false,
+            };
+            match if from_batch {
+                batch.next()
+            } else {
+                remaining.next()
+            } {
+                Some(value) => data.push(value),
+                None => return,
             }
         }
     }
+
+    // Each range takes the values of the batch that are no greater than the
+    // lowest value of the range after it.
+    let mut counts = vec![0; ranges.len()];
+    let mut b = 0;
+    for j in 1..ranges.len() {
+        let (lower, upper) = ranges[j];
+        let lowest = remaining[upper..=lower]
+            .iter()
+            .min_by(|x, y| by(x, y))
+            .expect("Non empty range");
+        while b < batch.len() && by(&batch[b], lowest) != Greater {
+            counts[j - 1] += 1;
+            b += 1;
+        }
+    }
+    counts[ranges.len() - 1] += batch.len() - b;
+
+    let mut remaining = remaining.into_iter();
+    let mut batch = batch.into_iter().rev();
+    for (j, &(lower, upper)) in ranges.iter().enumerate().rev() {
+        let start = data.len();
+        data.extend(remaining.by_ref().take(lower + 1 - upper));
+        data.extend(batch.by_ref().take(counts[j]));
+        work.push((data.len() - 1, start));
+    }
+}
+
+/// The next value of a lazy sort, borrowed in place.  The value is consumed
+/// when this is dropped, or explicitly with `commit`.
+pub struct NextRef<'a, T: 'a> {
+    data: &'a mut Vec<T>,
+    work: &'a mut WorkStack,
+    taken: &'a mut usize,
+}
+
+impl<'a, T> NextRef<'a, T> {
+    /// Consume the value.
+    pub fn commit(self) {}
+}
+
+impl<'a, T> Deref for NextRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.data.last().expect("Non empty vector")
+    }
+}
+
+impl<'a, T> Drop for NextRef<'a, T> {
+    fn drop(&mut self) {
+        self.work.pop();
+        self.data.pop();
+        *self.taken += 1;
+    }
+}
+
+/// How a lazy sort finds the values it yields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Partition only as far as is needed to find each value as it is
+    /// requested.  This is the default.
+    Lazy,
+    /// Fully sort all values, using the standard library's pattern-defeating
+    /// quicksort, when the first value is requested.  This is faster when
+    /// most or all of the values will be consumed, but like the standard
+    /// library's sorts it may panic on a comparator that is not a total
+    /// order.
+    Eager,
+    /// Start lazily, and fully sort the remaining values once as many have
+    /// been consumed as remain (by default, see `tune`).  A consumer that has
+    /// already taken half of the values is likely to take the rest, and
+    /// sorting them at once then costs less than finding them one at a time.
+    Adaptive,
+}
+
+/// How a lazy sort partitions the values it has not yet sorted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Partitioning {
+    /// Around one pivot at a time.  This is the default.
+    SinglePivot,
+    /// Around two pivots at a time, into three ranges, as Java's
+    /// `Arrays.sort` does.  This takes fewer comparisons and moves on many
+    /// inputs, and so is often faster when most of the values are consumed.
+    DualPivot,
+    /// Around the median of medians, three ways, so that finding the next
+    /// value, or skipping to a later one with `nth`, takes linear time in the
+    /// worst case rather than only on average.  This is slower on typical
+    /// inputs, but has no bad cases.
+    MedianOfMedians,
 }
 
-fn make_work(len: usize) -> Vec<(usize, usize)> {
-    let mut work = Vec::with_capacity(len / 4);
+/// Whether an adaptive sort that has yielded `taken` values, with
+/// `remaining` values still to come, should sort the rest at once.
+#[inline]
+fn adapt(taken: usize, remaining: usize, thresholds: Thresholds) -> bool {
+    taken.saturating_mul(100) >= remaining.saturating_mul(thresholds.adapt_percent)
+}
+
+/// The sorted runs at the ends of the input are only looked into if they hold
+/// at least one in this many of the values.
+const PRESORTED_SHARE: usize = 4;
+
+/// Look for a run of ascending values at the start of `data`, in the order it
+/// was given, and a run of descending values at its end.  The values of a run
+/// that are lower than every value outside it, and those that are higher, are
+/// already in order: they are moved to the end of `data` that they are popped
+/// from, and each given a range of its own, which the work list holds as one
+/// entry.  Only the values between are left as a range to be partitioned.
+/// This takes linear time, with a few comparisons for each value; and for
+/// input in no particular order the runs, and the search with them, end within
+/// a few values.
+fn presort<F, T>(by: &F, data: &mut [T], work: &mut WorkStack)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let len = data.len();
+    if len < 2 {
+        return;
+    }
+    let mut ascending = 1;
+    while ascending < len && by(&data[ascending - 1], &data[ascending]) != Greater {
+        ascending += 1;
+    }
+    if ascending == len {
+        data.reverse();
+        work.clear();
+        return;
+    }
+    let mut descending = 1;
+    while ascending + descending < len
+        && by(&data[len - descending - 1], &data[len - descending]) != Less
+    {
+        descending += 1;
+    }
+    if descending == len - 1 {
+        // Descending throughout, which is already the order values are
+        // popped in.
+        work.clear();
+        return;
+    }
+    if (ascending + descending) * PRESORTED_SHARE < len {
+        return;
+    }
+
+    // Reversed, the ascending run is in the order the descending one already
+    // is, highest value first.
+    data[..ascending].reverse();
+    let top = len - descending;
+    let (mut lowest, mut highest) = (None, None);
+    for i in ascending..top {
+        if lowest.is_none_or(|j| by(&data[i], &data[j]) == Less) {
+            lowest = Some(i);
+        }
+        if highest.is_none_or(|j| by(&data[i], &data[j]) == Greater) {
+            highest = Some(i);
+        }
+    }
+    // The lowest and highest values outside each run, given the lowest and
+    // highest of the run and of the values between the runs.
+    let lower = |i: usize| match lowest {
+        Some(j) if by(&data[j], &data[i]) == Less => j,
+        _ => i,
+    };
+    let higher = |i: usize| match highest {
+        Some(j) if by(&data[j], &data[i]) == Greater => j,
+        _ => i,
+    };
+    let below = |i: &usize, of: usize| by(&data[*i], &data[of]) == Less;
+    let above = |i: &usize, of: usize| by(&data[*i], &data[of]) == Greater;
+    let (floor, ceiling) = (lower(len - 1), higher(top));
+    let low_in_ascending = (0..ascending).rev().take_while(|i| below(i, floor)).count();
+    let high_in_ascending = (0..ascending).take_while(|i| above(i, ceiling)).count();
+    let (floor, ceiling) = (lower(ascending - 1), higher(0));
+    let low_in_descending = (top..len).rev().take_while(|i| below(i, floor)).count();
+    let high_in_descending = (top..len).take_while(|i| above(i, ceiling)).count();
+
+    // At most one run has values below all the others, and at most one above.
+    if low_in_ascending > 0 {
+        data[ascending - low_in_ascending..].rotate_left(low_in_ascending);
+    }
+    if high_in_descending > 0 {
+        let at = top - low_in_ascending;
+        data[..at + high_in_descending].rotate_right(high_in_descending);
+    }
+    let low = low_in_ascending + low_in_descending;
+    let high = high_in_ascending + high_in_descending;
+    work.clear();
+    for i in 0..high {
+        work.push((i, i));
+    }
+    if len - low > high {
+        work.push((len - low - 1, high));
+    }
+    for i in len - low..len {
+        work.push((i, i));
+    }
+}
+
+fn make_work(len: usize) -> WorkStack {
+    let mut work = WorkStack::new();
     if len > 0 {
         work.push((len - 1, 0));
     }
     work
 }
 
+/// When, if ever, to release the memory of values that have been yielded.
+#[derive(Clone, Copy)]
+struct Shrink {
+    occupancy: f64,
+    at: usize,
+}
+
+impl Shrink {
+    fn never() -> Self {
+        Shrink {
+            occupancy: 0.0,
+            at: 0,
+        }
+    }
+
+    fn below<T>(occupancy: f64, data: &Vec<T>) -> Self {
+        Shrink {
+            occupancy,
+            at: (data.capacity() as f64 * occupancy) as usize,
+        }
+    }
+
+    #[inline]
+    fn check<T>(&mut self, data: &mut Vec<T>) {
+        if data.len() < self.at {
+            data.shrink_to_fit();
+            self.at = (data.capacity() as f64 * self.occupancy) as usize;
+        }
+    }
+}
+
 macro_rules! lazy_sort_iter_struct {
     ($name:ident) => {
+        #[derive(Clone)]
         pub struct $name<T> {
             data: Vec<T>,
-            work: Vec<(usize, usize)>,
+            work: WorkStack,
+            shrink: Shrink,
+            strategy: Strategy,
+            partitioning: Partitioning,
+            thresholds: Thresholds,
+            taken: usize,
         }
     };
 }
 
 macro_rules! lazy_sort_iter_struct_new {
     () => {
-        fn new(data: Vec<T>) -> Self {
+        /// Lazily sort the values of `data`, taking ownership of its buffer.
+        /// Sorted runs at either end are found, and their values that lie
+        /// beyond all the others are not partitioned.
+        pub fn new(data: Vec<T>) -> Self {
             let work = make_work(data.len());
-            Self {
-                data: data,
-                work: work,
+            let mut iter = Self {
+                data,
+                work,
+                shrink: Shrink::never(),
+                strategy: Strategy::Lazy,
+                partitioning: Partitioning::SinglePivot,
+                thresholds: Thresholds::default(),
+                taken: 0,
+            };
+            iter.presort();
+            iter
+        }
+    };
+}
+
+macro_rules! add_shrink_below {
+    () => {
+        /// Release the memory of yielded values whenever the remaining values
+        /// occupy less than `occupancy` (a fraction between 0 and 1) of the
+        /// buffer, so that a slowly consumed sort stops holding on to its peak
+        /// memory.  By default the buffer is never shrunk.
+        pub fn shrink_below(mut self, occupancy: f64) -> Self {
+            self.shrink = Shrink::below(occupancy, &self.data);
+            self
+        }
+
+        pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+            self.strategy = strategy;
+            self
+        }
+
+        pub fn with_partitioning(mut self, partitioning: Partitioning) -> Self {
+            self.partitioning = partitioning;
+            self
+        }
+
+        /// Change algorithm at `thresholds`, clamped to the values they can
+        /// take, rather than at the defaults; see `tune::calibrate`.
+        pub fn with_thresholds(mut self, thresholds: Thresholds) -> Self {
+            self.thresholds = thresholds.clamped();
+            self
+        }
+
+        /// Declare that roughly `n` more values will be consumed.  If that is
+        /// most of what remains everything is sorted now; otherwise the lowest
+        /// `n` are selected and sorted in one go, and anything taken beyond
+        /// them is found lazily as usual.  Either way the values are
+        /// partitioned as the lazy sort would, so that a comparator that is
+        /// not a total order cannot make this panic.
+        pub fn expect_take(mut self, n: usize) -> Self {
+            self.prepare(n);
+            self
+        }
+
+        /// Find the next `n` values now, as `expect_take` does, so that the
+        /// next `n` calls to `next` each only take a value already in place.
+        /// This moves the cost of the sort to a time of the caller's choosing,
+        /// ahead of a section that must not be held up.
+        pub fn prepare(&mut self, n: usize) {
+            if n == 0 || self.work.is_empty() || self.work.in_place(n) {
+                return;
+            }
+            let len = self.data.len();
+            // The last of the `n` calls is the likeliest to turn the sort
+            // eager, which would then be anything but quick.
+            let last = n.min(len) - 1;
+            let eager = match self.strategy {
+                Strategy::Lazy => false,
+                Strategy::Eager => true,
+                Strategy::Adaptive => adapt(self.taken + last, len - last, self.thresholds),
+            };
+            if eager || n.saturating_mul(2) >= len {
+                self.finish();
+            } else {
+                self.select_first(n);
+            }
+        }
+
+        /// The next `n` values, or all that remain if there are fewer, lowest
+        /// first.  Ranges that lie wholly within the batch are sorted whole,
+        /// rather than partitioned a value at a time, by the lazy sort's own
+        /// code: only a strategy that sorts everything with the standard
+        /// library can make this panic on a comparator that is not a total
+        /// order.
+        pub fn next_batch(&mut self, n: usize) -> Vec<T> {
+            self.apply_strategy();
+            let batch = self.take_lowest(n);
+            self.taken += batch.len();
+            self.shrink.check(&mut self.data);
+            batch
+        }
+
+        /// The next value, without consuming it.
+        pub fn peek(&mut self) -> Option<&T> {
+            self.apply_strategy();
+            self.settle();
+            self.data.last()
+        }
+
+        /// The next value, borrowed where it lies rather than moved out of
+        /// the buffer, and consumed when the borrow is dropped.  This saves
+        /// moving large values that are only inspected.
+        pub fn next_ref(&mut self) -> Option<NextRef<'_, T>> {
+            self.peek()?;
+            Some(NextRef {
+                data: &mut self.data,
+                work: &mut self.work,
+                taken: &mut self.taken,
+            })
+        }
+
+        /// Add a value that arrived late.  It is yielded in its place among
+        /// the values not yet consumed.
+        pub fn push(&mut self, value: T) {
+            self.insert(value)
+        }
+
+        /// Add the values of `batch`, which must already be in the order this
+        /// sort yields, among the values not yet consumed.  The partitioning
+        /// done so far is kept, so this costs much less than sorting the
+        /// union again.
+        pub fn merge_in_sorted<I>(&mut self, batch: I)
+        where
+            I: IntoIterator<Item = T>,
+        {
+            self.merge_in(batch)
+        }
+
+        /// The lowest `n` of the remaining values, sorted, and the rest
+        /// unsorted.  This selects the `n` values and then sorts just those,
+        /// which is cheaper than yielding them one at a time.  Both are done
+        /// with the lazy sort's own partitioning, which does not panic on a
+        /// comparator that is not a total order.
+        pub fn split_sorted_at(self, n: usize) -> (Vec<T>, Vec<T>) {
+            self.split_sorted(n)
+        }
+
+        /// All of the remaining values, sorted at once, as `split_sorted_at`
+        /// sorts.  Once most of the values are wanted this is much faster than
+        /// collecting them.
+        pub fn into_sorted_vec(self) -> Vec<T> {
+            let len = self.data.len();
+            self.split_sorted(len).0
+        }
+
+        /// Append all of the remaining values, sorted at once, to `out`, so
+        /// that a caller sorting repeatedly can reuse the same buffer.
+        pub fn collect_sorted_into(&mut self, out: &mut Vec<T>) {
+            self.finish();
+            self.taken += self.data.len();
+            out.extend(self.data.drain(..).rev());
+        }
+
+        fn apply_strategy(&mut self) {
+            match self.strategy {
+                Strategy::Lazy => (),
+                Strategy::Eager => self.sort_remaining(),
+                Strategy::Adaptive => {
+                    if adapt(self.taken, self.data.len(), self.thresholds) {
+                        self.sort_remaining();
+                    }
+                }
             }
         }
     };
@@ -127,12 +1166,161 @@ macro_rules! lazy_sort_iter_struct_new {
 macro_rules! lazy_sort_iter_struct_qsort {
     ($cmp_f:path) => {
         fn qsort(&mut self, lower: usize, upper: usize) -> T {
-            qsort(&$cmp_f, &mut self.data, &mut self.work, lower, upper)
+            next_by(
+                &$cmp_f,
+                &mut self.data,
+                &mut self.work,
+                lower,
+                upper,
+                self.partitioning,
+                self.thresholds,
+            )
         }
-    }
+
+        fn skip_lowest(&mut self, n: usize) {
+            skip_lowest(
+                &$cmp_f,
+                &mut self.data,
+                &mut self.work,
+                n,
+                self.partitioning,
+            )
+        }
+
+        fn sort_remaining(&mut self) {
+            sort_remaining(&$cmp_f, &mut self.data, &mut self.work)
+        }
+
+        fn settle(&mut self) {
+            settle_by(
+                &$cmp_f,
+                &mut self.data,
+                &mut self.work,
+                self.partitioning,
+                self.thresholds,
+            )
+        }
+
+        fn insert(&mut self, value: T) {
+            insert(&$cmp_f, &mut self.data, &mut self.work, value)
+        }
+
+        fn merge_in<I: IntoIterator<Item = T>>(&mut self, batch: I) {
+            merge_in(&$cmp_f, &mut self.data, &mut self.work, batch)
+        }
+
+        fn finish(&mut self) {
+            finish(
+                &$cmp_f,
+                &mut self.data,
+                &mut self.work,
+                self.thresholds.small_sort,
+            )
+        }
+
+        fn split_sorted(self, n: usize) -> (Vec<T>, Vec<T>) {
+            split_sorted(
+                &$cmp_f,
+                self.data,
+                self.work,
+                n,
+                self.partitioning,
+                self.thresholds,
+            )
+        }
+
+        fn select_first(&mut self, n: usize) {
+            select_first(
+                &$cmp_f,
+                &mut self.data,
+                &mut self.work,
+                n,
+                self.partitioning,
+                self.thresholds,
+            )
+        }
+
+        fn presort(&mut self) {
+            presort(&$cmp_f, &mut self.data, &mut self.work)
+        }
+
+        fn take_lowest(&mut self, n: usize) -> Vec<T> {
+            take_lowest(
+                &$cmp_f,
+                &mut self.data,
+                &mut self.work,
+                n,
+                self.partitioning,
+                self.thresholds,
+            )
+        }
+    };
+}
+
+// Both take ownership of the existing buffer.  A deque is turned into a `Vec`
+// in place, rotating the values rather than copying them into a new
+// allocation.
+macro_rules! add_from {
+    ($name:ident, $bound:path) => {
+        impl<T> From<Vec<T>> for $name<T>
+        where
+            T: $bound,
+        {
+            fn from(data: Vec<T>) -> Self {
+                $name::new(data)
+            }
+        }
+
+        impl<T> From<VecDeque<T>> for $name<T>
+        where
+            T: $bound,
+        {
+            fn from(data: VecDeque<T>) -> Self {
+                $name::new(data.into())
+            }
+        }
+    };
+}
+
+macro_rules! add_extend {
+    ($name:ident, $bound:path) => {
+        impl<T> Extend<T> for $name<T>
+        where
+            T: $bound,
+        {
+            fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+                for value in iter {
+                    self.push(value);
+                }
+            }
+        }
+    };
 }
 
 lazy_sort_iter_struct!(LazySortIterator);
+add_from!(LazySortIterator, Ord);
+add_extend!(LazySortIterator, Ord);
+
+// Nothing is gained from partitioning a heap lazily, but it can be sorted in
+// its own buffer without any further allocation, leaving no work to do.
+impl<T> From<BinaryHeap<T>> for LazySortIterator<T>
+where
+    T: Ord,
+{
+    fn from(heap: BinaryHeap<T>) -> Self {
+        let mut data = heap.into_sorted_vec();
+        data.reverse();
+        LazySortIterator {
+            data,
+            work: WorkStack::new(),
+            shrink: Shrink::never(),
+            strategy: Strategy::Lazy,
+            partitioning: Partitioning::SinglePivot,
+            thresholds: Thresholds::default(),
+            taken: 0,
+        }
+    }
+}
 
 impl<T> LazySortIterator<T>
 where
@@ -140,6 +1328,7 @@ where
 {
     lazy_sort_iter_struct_new!();
     lazy_sort_iter_struct_qsort!(Ord::cmp);
+    add_shrink_below!();
 }
 
 fn partial_cmp_first<T: PartialOrd>(a: &T, b: &T) -> Ordering {
@@ -158,6 +1347,10 @@ fn partial_cmp_last<T: PartialOrd>(a: &T, b: &T) -> Ordering {
 
 lazy_sort_iter_struct!(LazySortIteratorPartialFirst);
 lazy_sort_iter_struct!(LazySortIteratorPartialLast);
+add_from!(LazySortIteratorPartialFirst, PartialOrd);
+add_from!(LazySortIteratorPartialLast, PartialOrd);
+add_extend!(LazySortIteratorPartialFirst, PartialOrd);
+add_extend!(LazySortIteratorPartialLast, PartialOrd);
 
 impl<T> LazySortIteratorPartialFirst<T>
 where
@@ -165,6 +1358,7 @@ where
 {
     lazy_sort_iter_struct_new!();
     lazy_sort_iter_struct_qsort!(partial_cmp_first);
+    add_shrink_below!();
 }
 
 impl<T> LazySortIteratorPartialLast<T>
@@ -173,29 +1367,224 @@ where
 {
     lazy_sort_iter_struct_new!();
     lazy_sort_iter_struct_qsort!(partial_cmp_last);
+    add_shrink_below!();
 }
 
+#[derive(Clone)]
 pub struct LazySortIteratorBy<T, F> {
     data: Vec<T>,
-    work: Vec<(usize, usize)>,
+    work: WorkStack,
+    shrink: Shrink,
+    strategy: Strategy,
+    partitioning: Partitioning,
+    thresholds: Thresholds,
+    taken: usize,
     by: F,
 }
 
 impl<T, F> LazySortIteratorBy<T, F>
 where
-    F: Fn(&T, &T) -> Ordering,
+    F: Compare<T>,
 {
-    fn new(data: Vec<T>, by: F) -> Self {
+    /// Sort the values of a deque, reusing its buffer.
+    pub fn from_vec_deque(data: VecDeque<T>, by: F) -> Self {
+        LazySortIteratorBy::new(data.into(), by)
+    }
+
+    /// Lazily sort the values of `data` by `by`, taking ownership of its
+    /// buffer.  Sorted runs at either end are found, and their values that
+    /// lie beyond all the others are not partitioned.
+    pub fn new(data: Vec<T>, by: F) -> Self {
         let work = make_work(data.len());
-        LazySortIteratorBy {
-            data: data,
-            work: work,
-            by: by,
-        }
+        let mut iter = LazySortIteratorBy {
+            data,
+            work,
+            shrink: Shrink::never(),
+            strategy: Strategy::Lazy,
+            partitioning: Partitioning::SinglePivot,
+            thresholds: Thresholds::default(),
+            taken: 0,
+            by,
+        };
+        iter.presort();
+        iter
     }
 
     fn qsort(&mut self, lower: usize, upper: usize) -> T {
-        qsort(&self.by, &mut self.data, &mut self.work, lower, upper)
+        let by = &self.by;
+        next_by(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            lower,
+            upper,
+            self.partitioning,
+            self.thresholds,
+        )
+    }
+
+    fn skip_lowest(&mut self, n: usize) {
+        let by = &self.by;
+        skip_lowest(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            n,
+            self.partitioning,
+        )
+    }
+
+    fn sort_remaining(&mut self) {
+        let by = &self.by;
+        sort_remaining(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+        )
+    }
+
+    fn settle(&mut self) {
+        let by = &self.by;
+        settle_by(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            self.partitioning,
+            self.thresholds,
+        )
+    }
+
+    fn insert(&mut self, value: T) {
+        let by = &self.by;
+        insert(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            value,
+        )
+    }
+
+    fn merge_in<I: IntoIterator<Item = T>>(&mut self, batch: I) {
+        let by = &self.by;
+        merge_in(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            batch,
+        )
+    }
+
+    fn finish(&mut self) {
+        let by = &self.by;
+        finish(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            self.thresholds.small_sort,
+        )
+    }
+
+    fn split_sorted(self, n: usize) -> (Vec<T>, Vec<T>) {
+        let by = &self.by;
+        split_sorted(
+            &|a: &T, b: &T| by.compare(a, b),
+            self.data,
+            self.work,
+            n,
+            self.partitioning,
+            self.thresholds,
+        )
+    }
+
+    fn select_first(&mut self, n: usize) {
+        let by = &self.by;
+        select_first(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            n,
+            self.partitioning,
+            self.thresholds,
+        )
+    }
+
+    fn presort(&mut self) {
+        let by = &self.by;
+        presort(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+        )
+    }
+
+    fn take_lowest(&mut self, n: usize) -> Vec<T> {
+        let by = &self.by;
+        take_lowest(
+            &|a: &T, b: &T| by.compare(a, b),
+            &mut self.data,
+            &mut self.work,
+            n,
+            self.partitioning,
+            self.thresholds,
+        )
+    }
+
+    add_shrink_below!();
+}
+
+impl<T, F> Extend<T> for LazySortIteratorBy<T, F>
+where
+    F: Compare<T>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+/// A marker for iterators that yield their values in sorted order: by `Ord`,
+/// or by whatever comparison or key they were built with.  Adaptors that need
+/// sorted input, here or in other crates, can require it, and any other
+/// iterator can be marked with `assume_sorted`.
+pub trait SortedIterator: Iterator {
+    /// Erase the type of this iterator, so that functions can return any of
+    /// several differently built sorts.
+    fn into_boxed<'a>(self) -> BoxedSorted<'a, Self::Item>
+    where
+        Self: Sized + 'a,
+    {
+        BoxedSorted {
+            iter: Box::new(self),
+        }
+    }
+}
+
+/// Some sorted iterator, of a type known only at runtime.
+pub struct BoxedSorted<'a, T> {
+    iter: Box<dyn SortedIterator<Item = T> + 'a>,
+}
+
+impl<'a, T> Iterator for BoxedSorted<'a, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T> SortedIterator for BoxedSorted<'a, T> {
+    fn into_boxed<'b>(self) -> BoxedSorted<'b, T>
+    where
+        Self: 'b,
+    {
+        self
     }
 }
 
@@ -215,42 +1604,65 @@ pub trait SortedPartial {
 pub trait SortedBy {
     type Item;
 
-    fn sorted_by<F>(self, F) -> LazySortIteratorBy<Self::Item, F>
+    fn sorted_by<F>(self, by: F) -> LazySortIteratorBy<Self::Item, F>
     where
         F: Fn(&Self::Item, &Self::Item) -> Ordering;
+
+    /// As `sorted_by`, with a `Compare` rather than a closure, so that the
+    /// iterator's type can be named.
+    fn sorted_with<C>(self, compare: C) -> LazySortIteratorBy<Self::Item, C>
+    where
+        C: Compare<Self::Item>;
+
+    /// As `sorted_by`, with the comparator boxed so that the iterator's type
+    /// can be named, and chosen at runtime.
+    fn sorted_by_boxed<'a, F>(self, by: F) -> LazySortIteratorDyn<'a, Self::Item>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering + Send + 'a;
 }
 
+/// A comparator chosen at runtime.
+pub type BoxedComparator<'a, T> = Box<dyn Fn(&T, &T) -> Ordering + Send + 'a>;
+
+/// A `LazySortIteratorBy` with a boxed comparator, which can be named in a
+/// struct and sent between threads.
+pub type LazySortIteratorDyn<'a, T> = LazySortIteratorBy<T, BoxedComparator<'a, T>>;
+
+// `collect` already reserves the lower bound of the source's `size_hint`, and
+// exactly the right capacity for sources of a known length, before growing
+// by doubling.  The work list needs no reserving: it holds roughly `log2(n)`
+// ranges, which fit in its inline entries for any realistic `n`.
 impl<T, I> Sorted for I
 where
     T: Eq + Ord,
-    I: Iterator<Item = T>,
+    I: IntoIterator<Item = T>,
 {
     type Item = T;
 
     fn sorted(self) -> LazySortIterator<T> {
-        LazySortIterator::new(self.collect())
+        LazySortIterator::new(self.into_iter().collect())
     }
 }
 
 impl<T, I> SortedPartial for I
 where
     T: PartialOrd,
-    I: Iterator<Item = T>,
+    I: IntoIterator<Item = T>,
 {
     type Item = T;
 
     fn sorted_partial_first(self) -> LazySortIteratorPartialFirst<T> {
-        LazySortIteratorPartialFirst::new(self.collect())
+        LazySortIteratorPartialFirst::new(self.into_iter().collect())
     }
 
     fn sorted_partial_last(self) -> LazySortIteratorPartialLast<T> {
-        LazySortIteratorPartialLast::new(self.collect())
+        LazySortIteratorPartialLast::new(self.into_iter().collect())
     }
 }
 
 impl<T, I> SortedBy for I
 where
-    I: Iterator<Item = T>,
+    I: IntoIterator<Item = T>,
 {
     type Item = T;
 
@@ -258,7 +1670,21 @@ where
     where
         F: Fn(&T, &T) -> Ordering,
     {
-        LazySortIteratorBy::new(self.collect(), by)
+        LazySortIteratorBy::new(self.into_iter().collect(), by)
+    }
+
+    fn sorted_with<C>(self, compare: C) -> LazySortIteratorBy<T, C>
+    where
+        C: Compare<T>,
+    {
+        LazySortIteratorBy::new(self.into_iter().collect(), compare)
+    }
+
+    fn sorted_by_boxed<'a, F>(self, by: F) -> LazySortIteratorDyn<'a, T>
+    where
+        F: Fn(&T, &T) -> Ordering + Send + 'a,
+    {
+        LazySortIteratorBy::new(self.into_iter().collect(), Box::new(by))
     }
 }
 
@@ -266,12 +1692,45 @@ macro_rules! add_next {
     () => {
         #[inline]
         fn next(&mut self) -> Option<T> {
-            match self.work.pop() {
-                Some((lower, upper)) => Some(self.qsort(lower, upper)),
-                None => None
+            self.apply_strategy();
+            self.taken += 1;
+            let value = match self.work.pop() {
+                Some((lower, upper)) => self.qsort(lower, upper),
+                None => self.data.pop()?,
+            };
+            self.shrink.check(&mut self.data);
+            Some(value)
+        }
+
+        // The values skipped over are selected rather than sorted.
+        fn nth(&mut self, n: usize) -> Option<T> {
+            self.taken += n.min(self.data.len());
+            self.skip_lowest(n);
+            self.next()
+        }
+
+        // Consuming every value is done fastest by sorting them all at once,
+        // but only where the strategy allows it: the standard library's sort
+        // panics on a comparator that is not a total order, where a lazy sort
+        // only yields the values in some unsorted order.
+        fn fold<B, G>(mut self, init: B, mut f: G) -> B
+        where
+            G: FnMut(B, T) -> B,
+        {
+            if self.strategy != Strategy::Lazy {
+                self.sort_remaining();
             }
+            let mut acc = init;
+            while let Some(value) = self.next() {
+                acc = f(acc, value);
+            }
+            acc
         }
-    }
+
+        fn count(self) -> usize {
+            self.data.len()
+        }
+    };
 }
 
 macro_rules! add_size_hint {
@@ -281,7 +1740,7 @@ macro_rules! add_size_hint {
             let l = self.data.len();
             (l, Some(l))
         }
-    }
+    };
 }
 
 impl<T> Iterator for LazySortIterator<T>
@@ -294,6 +1753,8 @@ where
     add_size_hint!();
 }
 
+impl<T> SortedIterator for LazySortIterator<T> where T: Ord {}
+
 impl<T> Iterator for LazySortIteratorPartialFirst<T>
 where
     T: PartialOrd,
@@ -304,6 +1765,8 @@ where
     add_size_hint!();
 }
 
+impl<T> SortedIterator for LazySortIteratorPartialFirst<T> where T: PartialOrd {}
+
 impl<T> Iterator for LazySortIteratorPartialLast<T>
 where
     T: PartialOrd,
@@ -314,9 +1777,11 @@ where
     add_size_hint!();
 }
 
+impl<T> SortedIterator for LazySortIteratorPartialLast<T> where T: PartialOrd {}
+
 impl<T, F> Iterator for LazySortIteratorBy<T, F>
 where
-    F: Fn(&T, &T) -> Ordering,
+    F: Compare<T>,
 {
     type Item = T;
 
@@ -324,15 +1789,21 @@ where
     add_size_hint!();
 }
 
+impl<T, F> SortedIterator for LazySortIteratorBy<T, F> where F: Compare<T> {}
+
 #[cfg(test)]
 mod tests {
     extern crate rand;
 
+    use super::Partitioning;
     use super::Sorted;
     use super::SortedBy;
     use super::SortedPartial;
+    use super::Strategy;
+    use super::{LazySortIterator, LazySortIteratorBy, LazySortIteratorPartialLast};
 
     use std::cmp::Ordering::Equal;
+    use std::collections::{BinaryHeap, VecDeque};
 
     #[test]
     fn single_test() {
@@ -405,6 +1876,589 @@ mod tests {
         assert_eq!(expected, after);
     }
 
+    #[test]
+    fn shrink_below_test() {
+        let before: Vec<u64> = (0..1000).rev().collect();
+        let mut iter = before.into_iter().sorted().shrink_below(0.25);
+        let capacity = iter.data.capacity();
+
+        let first: Vec<u64> = iter.by_ref().take(800).collect();
+        assert_eq!((0..800).collect::<Vec<u64>>(), first);
+        assert!(iter.data.capacity() < capacity / 4);
+
+        let rest: Vec<u64> = iter.collect();
+        assert_eq!((800..1000).collect::<Vec<u64>>(), rest);
+    }
+
+    #[test]
+    fn strategy_test() {
+        let before: Vec<u64> = vec![9u64, 7, 1, 1, 6, 3, 1, 4, 22];
+        let mut iter = before.iter().sorted().with_strategy(Strategy::Eager);
+        assert_eq!(Some(&1), iter.next());
+        assert!(iter.work.is_empty());
+        let rest: Vec<u64> = iter.cloned().collect();
+        assert_eq!(vec![1u64, 1, 3, 4, 6, 7, 9, 22], rest);
+
+        let mut iter = before.iter().sorted();
+        iter.next();
+        let mut rest = Vec::new();
+        iter.for_each(|&x| rest.push(x));
+        assert_eq!(vec![1u64, 1, 3, 4, 6, 7, 9, 22], rest);
+
+        assert_eq!(9, before.iter().sorted_partial_last().count());
+    }
+
+    #[test]
+    fn adaptive_strategy_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let mut iter = before.iter().sorted().with_strategy(Strategy::Adaptive);
+        let first: Vec<u64> = iter.by_ref().take(500).cloned().collect();
+        assert_eq!((0..500).collect::<Vec<u64>>(), first);
+        assert!(!iter.work.is_empty());
+        assert_eq!(Some(&500), iter.next());
+        assert!(iter.work.is_empty());
+        let rest: Vec<u64> = iter.cloned().collect();
+        assert_eq!((501..1000).collect::<Vec<u64>>(), rest);
+    }
+
+    #[test]
+    fn inconsistent_comparator_test() {
+        use std::cmp::Ordering;
+
+        // Neither a total order nor even consistent with itself.
+        let by = |a: &u64, b: &u64| {
+            if (a ^ b) % 3 == 1 {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            }
+        };
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let mut after = Vec::new();
+        before
+            .iter()
+            .cloned()
+            .sorted_by(by)
+            .for_each(|x| after.push(x));
+        after.sort();
+        assert_eq!((0..1000).collect::<Vec<u64>>(), after);
+        assert!(before.iter().cloned().sorted_by(by).max().is_some());
+
+        // Nor do the methods that sort many values at once.
+        let sorted = |mut values: Vec<u64>| {
+            values.sort();
+            assert_eq!((0..1000).collect::<Vec<u64>>(), values);
+        };
+        let (mut low, high) = before.iter().cloned().sorted_by(by).split_sorted_at(300);
+        low.extend(high);
+        sorted(low);
+        sorted(before.iter().cloned().sorted_by(by).into_sorted_vec());
+        let mut out = Vec::new();
+        before
+            .iter()
+            .cloned()
+            .sorted_by(by)
+            .collect_sorted_into(&mut out);
+        sorted(out);
+        sorted(
+            before
+                .iter()
+                .cloned()
+                .sorted_by(by)
+                .expect_take(900)
+                .collect(),
+        );
+        sorted(
+            before
+                .iter()
+                .cloned()
+                .sorted_by(by)
+                .expect_take(100)
+                .collect(),
+        );
+        let mut iter = before.iter().cloned().sorted_by(by);
+        let mut prepared: Vec<u64> = iter.by_ref().take(10).collect();
+        iter.prepare(200);
+        prepared.extend(iter);
+        sorted(prepared);
+        let mut iter = before.iter().cloned().sorted_by(by);
+        let mut batches = iter.next_batch(10);
+        batches.extend(iter.next_batch(400));
+        batches.extend(iter.next_batch(1000));
+        sorted(batches);
+    }
+
+    #[test]
+    fn into_iterator_test() {
+        let before: Vec<u64> = vec![9, 7, 1, 1, 6, 3, 1, 4, 22];
+        let refs: Vec<&u64> = (&before).sorted().take(2).collect();
+        assert_eq!(vec![&1, &1], refs);
+        let partial: Vec<u64> = before.clone().sorted_partial_first().collect();
+        let by: Vec<u64> = before.sorted_by(|a, b| b.cmp(a)).collect();
+        assert_eq!(vec![1, 1, 1, 3, 4, 6, 7, 9, 22], partial);
+        assert_eq!(vec![22, 9, 7, 6, 4, 3, 1, 1, 1], by);
+    }
+
+    #[test]
+    fn sorted_iterator_test() {
+        use super::{AssumeSorted, SortedIterator};
+
+        fn first<I: SortedIterator>(mut iter: I) -> Option<I::Item> {
+            iter.next()
+        }
+        assert_eq!(Some(1), first(vec![3, 1, 2].sorted()));
+        assert_eq!(Some(1.0), first(vec![3.0, 1.0].sorted_partial_first()));
+        assert_eq!(Some(0), first((0..3).assume_sorted()));
+    }
+
+    #[test]
+    fn sorted_by_boxed_test() {
+        use super::LazySortIteratorDyn;
+
+        fn by_direction(values: Vec<u64>, descending: bool) -> LazySortIteratorDyn<'static, u64> {
+            if descending {
+                values.sorted_by_boxed(|a, b| b.cmp(a))
+            } else {
+                values.sorted_by_boxed(Ord::cmp)
+            }
+        }
+
+        let iter = by_direction(vec![2, 3, 1], true);
+        let after = ::std::thread::spawn(move || iter.collect::<Vec<u64>>())
+            .join()
+            .unwrap();
+        assert_eq!(vec![3, 2, 1], after);
+        let after: Vec<u64> = by_direction(vec![2, 3, 1], false).collect();
+        assert_eq!(vec![1, 2, 3], after);
+    }
+
+    #[test]
+    fn into_boxed_test() {
+        use super::{BoxedSorted, SortedIterator, SortedRadix};
+
+        fn sorted(values: Vec<u32>, radix: bool) -> BoxedSorted<'static, u32> {
+            if radix {
+                values.into_iter().sorted_radix().into_boxed()
+            } else {
+                values.sorted().into_boxed().into_boxed()
+            }
+        }
+        assert_eq!(
+            vec![1, 2, 3],
+            sorted(vec![3, 1, 2], true).collect::<Vec<u32>>()
+        );
+        assert_eq!(
+            vec![1, 2, 3],
+            sorted(vec![3, 1, 2], false).collect::<Vec<u32>>()
+        );
+    }
+
+    #[test]
+    fn next_ref_test() {
+        let before: Vec<u64> = (0..100).map(|i| (i * 37) % 100).collect();
+        let mut iter = before.iter().cloned().sorted();
+        assert_eq!(Some(&0), iter.peek());
+        assert_eq!(0, *iter.next_ref().unwrap());
+        let one = iter.next_ref().unwrap();
+        assert_eq!(1, *one);
+        one.commit();
+        assert_eq!(Some(2), iter.next());
+        assert_eq!(3, *iter.next_ref().unwrap());
+        let rest: Vec<u64> = iter.collect();
+        assert_eq!((4..100).collect::<Vec<u64>>(), rest);
+
+        let mut iter = vec![2u64, 1].into_iter().sorted_by(|a, b| b.cmp(a));
+        assert_eq!(2, *iter.next_ref().unwrap());
+        assert_eq!(1, *iter.next_ref().unwrap());
+        assert!(iter.next_ref().is_none());
+    }
+
+    #[test]
+    fn push_test() {
+        use self::rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        for &strategy in [Strategy::Lazy, Strategy::Eager].iter() {
+            let before: Vec<u64> = (0..500).map(|_| rng.gen_range(1, 1000)).collect();
+            let late: Vec<u64> = (0..100).map(|_| rng.gen_range(1, 1000)).collect();
+            let mut iter = before.clone().sorted().with_strategy(strategy);
+            let mut after: Vec<u64> = iter.by_ref().take(100).collect();
+            iter.extend(late.iter().cloned());
+            after.extend(iter.by_ref().take(50));
+            iter.push(0);
+            after.extend(iter);
+
+            let mut early = before.clone();
+            early.sort();
+            let mut expected: Vec<u64> = early[100..].iter().chain(late.iter()).cloned().collect();
+            expected.sort();
+            expected.insert(50, 0);
+            expected.splice(0..0, early[..100].iter().cloned());
+            assert_eq!(expected, after);
+        }
+    }
+
+    #[test]
+    fn merge_in_sorted_test() {
+        use self::rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        for &strategy in [Strategy::Lazy, Strategy::Eager].iter() {
+            let before: Vec<u64> = (0..1000).map(|_| rng.gen_range(0, 1000)).collect();
+            let mut batch: Vec<u64> = (0..300).map(|_| rng.gen_range(0, 1000)).collect();
+            batch.sort();
+            let mut iter = before.clone().sorted().with_strategy(strategy);
+            let mut after: Vec<u64> = iter.by_ref().take(100).collect();
+            iter.merge_in_sorted(batch.iter().cloned());
+            assert_eq!((1200, Some(1200)), iter.size_hint());
+            after.extend(iter);
+
+            let mut early = before.clone();
+            early.sort();
+            let mut expected: Vec<u64> = early[100..].iter().chain(batch.iter()).cloned().collect();
+            expected.sort();
+            expected.splice(0..0, early[..100].iter().cloned());
+            assert_eq!(expected, after);
+        }
+    }
+
+    #[test]
+    fn split_sorted_at_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let mut iter = before.iter().cloned().sorted();
+        assert_eq!(Some(0), iter.next());
+        let (sorted, mut rest) = iter.split_sorted_at(10);
+        assert_eq!((1..11).collect::<Vec<u64>>(), sorted);
+        rest.sort();
+        assert_eq!((11..1000).collect::<Vec<u64>>(), rest);
+
+        let iter = vec![3, 1, 2].sorted_by(|a: &u64, b: &u64| b.cmp(a));
+        assert_eq!((vec![3, 2, 1], vec![]), iter.split_sorted_at(5));
+        let iter = LazySortIterator::from(BinaryHeap::from(vec![3u64, 1, 2]));
+        assert_eq!((vec![1, 2], vec![3]), iter.split_sorted_at(2));
+    }
+
+    #[test]
+    fn dual_pivot_test() {
+        use self::rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        let inputs: Vec<Vec<u64>> = vec![
+            (0..1000).map(|_| rng.gen_range(0, 1000)).collect(),
+            (0..1000).map(|_| rng.gen_range(0, 3)).collect(),
+            vec![7; 500],
+            (0..1000).collect(),
+            (0..1000).rev().collect(),
+        ];
+        for before in inputs {
+            let mut expected = before.clone();
+            expected.sort();
+
+            let iter = before
+                .iter()
+                .cloned()
+                .sorted()
+                .with_partitioning(Partitioning::DualPivot);
+            // `next` rather than `collect`, which would sort eagerly.
+            let mut after = Vec::new();
+            for x in iter {
+                after.push(x);
+            }
+            assert_eq!(expected, after);
+
+            let mut iter = before
+                .sorted_by(|a: &u64, b: &u64| b.cmp(a))
+                .with_partitioning(Partitioning::DualPivot);
+            expected.reverse();
+            assert_eq!(Some(&expected[0]), iter.peek());
+            let after: Vec<u64> = iter.by_ref().take(100).collect();
+            assert_eq!(&expected[..100], &after[..]);
+        }
+    }
+
+    #[test]
+    fn median_of_medians_test() {
+        use self::rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        let before: Vec<u64> = (0..1000).map(|_| rng.gen_range(0, 50)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        let mut iter = before
+            .iter()
+            .cloned()
+            .sorted()
+            .with_partitioning(Partitioning::MedianOfMedians);
+        assert_eq!(Some(&expected[0]), iter.peek());
+        let mut after = Vec::new();
+        for x in iter {
+            after.push(x);
+        }
+        assert_eq!(expected, after);
+
+        for &partitioning in [Partitioning::SinglePivot, Partitioning::MedianOfMedians].iter() {
+            let mut iter = before
+                .iter()
+                .cloned()
+                .sorted()
+                .with_partitioning(partitioning);
+            assert_eq!(Some(expected[0]), iter.next());
+            assert_eq!(Some(expected[501]), iter.nth(500));
+            assert_eq!(Some(expected[502]), iter.next());
+            assert_eq!(None, iter.nth(1000));
+        }
+    }
+
+    #[test]
+    fn equal_runs_test() {
+        use std::cell::Cell;
+
+        let comparisons = Cell::new(0);
+        let by = |a: &u64, b: &u64| {
+            comparisons.set(comparisons.get() + 1);
+            a.cmp(b)
+        };
+
+        let before = vec![7u64; 1000];
+        let mut iter = before.iter().cloned().sorted_by(by);
+        assert_eq!(Some(&7), iter.peek());
+        let mut after = Vec::new();
+        for x in iter {
+            after.push(x);
+        }
+        assert_eq!(before, after);
+        assert!(comparisons.get() < 3000);
+
+        comparisons.set(0);
+        let mut before = vec![7u64; 1000];
+        before.extend(0..100);
+        let mut expected = before.clone();
+        expected.sort();
+        let mut after = Vec::new();
+        for x in before.iter().cloned().sorted_by(by) {
+            after.push(x);
+        }
+        assert_eq!(expected, after);
+        assert!(comparisons.get() < 10_000);
+    }
+
+    #[test]
+    fn presorted_test() {
+        use self::rand::Rng;
+        use std::cell::Cell;
+
+        let comparisons = Cell::new(0);
+        let by = |a: &u64, b: &u64| {
+            comparisons.set(comparisons.get() + 1);
+            a.cmp(b)
+        };
+        let mut rng = rand::thread_rng();
+        let ascending: Vec<u64> = (0..1000).collect();
+        // A log with a few entries that arrived late.
+        let mut late = ascending.clone();
+        late.extend((0..20).map(|_| rng.gen_range(950, 1000)));
+        let mut both = ascending.clone();
+        both.extend((0..50).map(|_| rng.gen_range(1000, 1100)));
+        both.extend((1100..2000).rev());
+        let mut mixed: Vec<u64> = (0..1000).map(|_| rng.gen_range(0, 1000)).collect();
+        mixed.sort();
+        mixed.extend((0..50).map(|_| rng.gen_range(0, 1000)));
+        mixed.extend((0..1000).rev());
+        let inputs = vec![
+            (ascending.clone(), true),
+            (ascending.iter().cloned().rev().collect(), true),
+            (late, true),
+            (both, true),
+            (mixed, false),
+        ];
+        for (before, presorted) in inputs {
+            let mut expected = before.clone();
+            expected.sort();
+
+            comparisons.set(0);
+            let mut iter = before.iter().cloned().sorted_by(by);
+            assert_eq!(Some(&expected[0]), iter.peek());
+            iter.push(0);
+            expected.insert(0, 0);
+            let mut after = Vec::new();
+            for x in iter {
+                after.push(x);
+            }
+            assert_eq!(expected, after);
+            if presorted {
+                // A lazy sort of all of them would take around `n log n`.
+                assert!(comparisons.get() < 4 * before.len());
+            }
+        }
+    }
+
+    #[test]
+    fn presorted_lazy_test() {
+        use std::cell::Cell;
+
+        let comparisons = Cell::new(0);
+        let by = |a: &u64, b: &u64| {
+            comparisons.set(comparisons.get() + 1);
+            a.cmp(b)
+        };
+        let mut before: Vec<u64> = (0..10_000).collect();
+        before.push(5_000);
+        let iter = before.iter().cloned().sorted_by(by);
+        // Finding the runs, and nothing sorted.
+        assert!(comparisons.get() < 3 * before.len());
+        comparisons.set(0);
+        let first: Vec<u64> = iter.take(100).collect();
+        assert_eq!((0..100).collect::<Vec<u64>>(), first);
+        assert_eq!(0, comparisons.get());
+    }
+
+    #[test]
+    fn prepare_test() {
+        use std::cell::Cell;
+
+        let comparisons = Cell::new(0);
+        let by = |a: &u64, b: &u64| {
+            comparisons.set(comparisons.get() + 1);
+            a.cmp(b)
+        };
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        for &strategy in [Strategy::Lazy, Strategy::Adaptive].iter() {
+            let mut iter = before.iter().cloned().sorted_by(by).with_strategy(strategy);
+            assert_eq!(Some(0), iter.next());
+            iter.prepare(100);
+            comparisons.set(0);
+            let first: Vec<u64> = iter.by_ref().take(100).collect();
+            assert_eq!((1..101).collect::<Vec<u64>>(), first);
+            assert_eq!(0, comparisons.get());
+
+            iter.prepare(450);
+            comparisons.set(0);
+            let next: Vec<u64> = iter.by_ref().take(450).collect();
+            assert_eq!((101..551).collect::<Vec<u64>>(), next);
+            assert_eq!(0, comparisons.get());
+            let rest: Vec<u64> = iter.collect();
+            assert_eq!((551..1000).collect::<Vec<u64>>(), rest);
+        }
+    }
+
+    #[test]
+    fn next_batch_test() {
+        use self::rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        let before: Vec<u64> = (0..5000).map(|_| rng.gen_range(0, 2000)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        let mut iter = before.iter().cloned().sorted();
+        let mut after = iter.next_batch(1024);
+        assert_eq!(1024, after.len());
+        after.extend(iter.by_ref().take(10));
+        assert_eq!(Some(&expected[after.len()]), iter.peek());
+        after.extend(iter.next_batch(1000));
+        after.push(iter.next().unwrap());
+        while iter.size_hint().0 > 0 {
+            after.extend(iter.next_batch(1024));
+        }
+        assert_eq!(expected, after);
+        assert!(iter.next_batch(10).is_empty());
+
+        let mut iter = before.sorted_by(|a: &u64, b: &u64| b.cmp(a));
+        iter.push(2000);
+        let batch = iter.next_batch(3);
+        assert_eq!(2000, batch[0]);
+        assert!(batch[1] >= batch[2]);
+        assert_eq!((4998, Some(4998)), iter.size_hint());
+    }
+
+    #[test]
+    fn expect_take_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let after: Vec<u64> = before
+            .iter()
+            .cloned()
+            .sorted()
+            .expect_take(10)
+            .take(30)
+            .collect();
+        assert_eq!((0..30).collect::<Vec<u64>>(), after);
+
+        let mut iter = before
+            .sorted_by(|a: &u64, b: &u64| b.cmp(a))
+            .expect_take(600);
+        assert_eq!(Some(999), iter.next());
+        assert_eq!(999, iter.by_ref().count());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn collect_sorted_into_test() {
+        let mut out = Vec::with_capacity(100);
+        for _ in 0..3 {
+            out.clear();
+            let mut iter = (0..100u64).rev().sorted();
+            out.push(iter.next().unwrap());
+            iter.collect_sorted_into(&mut out);
+            assert_eq!(None, iter.next());
+            assert_eq!((0..100).collect::<Vec<u64>>(), out);
+            assert_eq!(100, out.capacity());
+        }
+    }
+
+    #[test]
+    fn into_sorted_vec_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let mut iter = before.iter().cloned().sorted();
+        assert_eq!(Some(0), iter.next());
+        assert_eq!((1..1000).collect::<Vec<u64>>(), iter.into_sorted_vec());
+
+        let mut iter = vec![3, 1, 2]
+            .sorted_partial_first()
+            .with_strategy(Strategy::Eager);
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(vec![2, 3], iter.into_sorted_vec());
+    }
+
+    #[test]
+    fn binary_heap_test() {
+        let heap: BinaryHeap<u64> = vec![9, 7, 1, 1, 6, 3].into_iter().collect();
+        let iter = LazySortIterator::from(heap);
+        assert!(iter.work.is_empty());
+        assert_eq!(vec![1, 1, 3, 6, 7, 9], iter.collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn preallocation_test() {
+        let iter = (0..1000u64).map(|x| x * 2).sorted();
+        assert_eq!(1000, iter.data.capacity());
+    }
+
+    #[test]
+    fn from_buffer_test() {
+        let mut before: VecDeque<u64> = VecDeque::with_capacity(8);
+        before.extend(vec![5, 6, 7]);
+        before.push_front(1);
+        before.push_front(9);
+        let ptr = before.as_slices().1.as_ptr();
+
+        let iter = LazySortIterator::from(before.clone());
+        let after: Vec<u64> = iter.collect();
+        assert_eq!(vec![1, 5, 6, 7, 9], after);
+
+        let iter = LazySortIteratorBy::from_vec_deque(before, |a: &u64, b: &u64| b.cmp(a));
+        assert_eq!(ptr, iter.data.as_ptr());
+        let after: Vec<u64> = iter.collect();
+        assert_eq!(vec![9, 7, 6, 5, 1], after);
+
+        let before: Vec<u64> = vec![3, 2, 1];
+        let ptr = before.as_ptr();
+        let iter = LazySortIteratorPartialLast::from(before);
+        assert_eq!(ptr, iter.data.as_ptr());
+        let iter = LazySortIteratorBy::new(iter.collect(), |a: &u64, b: &u64| b.cmp(a));
+        assert_eq!(vec![3, 2, 1], iter.collect::<Vec<u64>>());
+    }
+
     #[test]
     fn sorted_by_test() {
         let expected: Vec<u64> = vec![4, 1, 3, 2];
@@ -435,6 +2489,7 @@ mod benches {
 
     use self::rand::distributions::{IndependentSample, Range};
 
+    use super::heap::{DaryHeap, Heap, PairingHeap};
     use super::Sorted;
 
     use std::cmp::Ordering;
@@ -446,13 +2501,16 @@ mod benches {
     static PICK_SIZE_A: usize = 1000;
     static PICK_SIZE_B: usize = 10000;
     static PICK_SIZE_C: usize = 50000;
+    static LARGE_VEC_SIZE: u64 = 4000000;
 
     fn data() -> Vec<u64> {
+        data_of_size(VEC_SIZE)
+    }
+
+    fn data_of_size(size: u64) -> Vec<u64> {
         let mut rng = rand::thread_rng();
         let between = Range::new(0u64, RANGE);
-        (0u64..VEC_SIZE)
-            .map(|_| between.ind_sample(&mut rng))
-            .collect()
+        (0u64..size).map(|_| between.ind_sample(&mut rng)).collect()
     }
 
     #[bench]
@@ -494,6 +2552,52 @@ mod benches {
         });
     }
 
+    #[bench]
+    fn a_binomial_heap_bench(b: &mut Bencher) {
+        let input = data();
+
+        b.iter(|| {
+            let mut heap = Heap::new_min();
+            for x in black_box(&input).iter().cloned() {
+                heap.add(x);
+            }
+            let pick: Vec<u64> = heap.into_iter().take(PICK_SIZE_A).collect();
+            black_box(pick)
+        });
+    }
+
+    #[bench]
+    fn a_pairing_heap_bench(b: &mut Bencher) {
+        let input = data();
+
+        b.iter(|| {
+            let mut heap = PairingHeap::new_min();
+            for x in black_box(&input).iter().cloned() {
+                heap.add(x);
+            }
+            let mut pick: Vec<u64> = Vec::with_capacity(PICK_SIZE_A);
+            for _ in 0..PICK_SIZE_A {
+                pick.push(heap.take().unwrap());
+            }
+            black_box(pick)
+        });
+    }
+
+    #[bench]
+    fn a_dary_heap_bench(b: &mut Bencher) {
+        let input = data();
+
+        b.iter(|| {
+            let mut heap: DaryHeap<u64, _> =
+                DaryHeap::from_vec(black_box(&input).clone(), Ord::cmp);
+            let mut pick: Vec<u64> = Vec::with_capacity(PICK_SIZE_A);
+            for _ in 0..PICK_SIZE_A {
+                pick.push(heap.take().unwrap());
+            }
+            black_box(pick)
+        });
+    }
+
     #[bench]
     fn b_standard_bench(b: &mut Bencher) {
         let input = data();
@@ -572,6 +2676,18 @@ mod benches {
         });
     }
 
+    // The first value of an input far larger than the cache, the case the
+    // `prefetch` feature helps.
+    #[bench]
+    fn d_lazy_first_bench(b: &mut Bencher) {
+        let input = data_of_size(LARGE_VEC_SIZE);
+
+        b.iter(|| {
+            let numbers = black_box(&input).clone();
+            black_box(numbers.into_iter().sorted().next())
+        });
+    }
+
     // BinaryHeap is a max heap. We want to extract the minimum values so
     // reverse the ordering.
     struct RevOrd<V>(V);
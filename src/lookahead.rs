@@ -0,0 +1,211 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Taking values from a sort ahead of when they are needed.
+//!
+//! Most calls to `next` on a lazy sort are quick, but now and then one has
+//! to partition a large range first.  A lookahead keeps a few values already
+//! taken, so that the cost falls elsewhere: on the caller's own thread when it
+//! calls `fill`, or on a background thread that keeps the buffer topped up
+//! while the consumer works.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::panic;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+
+use super::SortedIterator;
+
+/// A buffer of up to `depth` values taken from `iter`, refilled by `fill`.
+pub struct LookaheadBuffer<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    buffer: VecDeque<I::Item>,
+    depth: usize,
+}
+
+impl<I> LookaheadBuffer<I>
+where
+    I: Iterator,
+{
+    /// Take values until `depth` are buffered, or the iterator is exhausted.
+    pub fn fill(&mut self) {
+        while self.buffer.len() < self.depth {
+            match self.iter.next() {
+                Some(value) => self.buffer.push_back(value),
+                None => return,
+            }
+        }
+    }
+
+    /// The number of values buffered.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl<I> Iterator for LookaheadBuffer<I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        self.buffer.pop_front().or_else(|| self.iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let n = self.buffer.len();
+        (
+            lower.saturating_add(n),
+            upper.and_then(|upper| upper.checked_add(n)),
+        )
+    }
+}
+
+impl<I> SortedIterator for LookaheadBuffer<I> where I: SortedIterator {}
+
+/// Values taken from an iterator on a background thread, which keeps up to
+/// `depth` of them waiting.  The thread stops once the iterator is exhausted,
+/// or soon after this is dropped.  A panic on the thread, such as from a
+/// comparator, is raised again on the consumer's thread when it reaches the
+/// values that were never sent.
+pub struct BackgroundLookahead<I>
+where
+    I: Iterator,
+{
+    receiver: Receiver<I::Item>,
+    worker: Option<JoinHandle<()>>,
+    size_hint: (usize, Option<usize>),
+    marker: PhantomData<fn() -> I>,
+}
+
+impl<I> Iterator for BackgroundLookahead<I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        match self.receiver.recv() {
+            Ok(value) => {
+                let (lower, upper) = self.size_hint;
+                self.size_hint = (
+                    lower.saturating_sub(1),
+                    upper.map(|upper| upper.saturating_sub(1)),
+                );
+                Some(value)
+            }
+            Err(_) => {
+                self.size_hint = (0, Some(0));
+                if let Some(worker) = self.worker.take() {
+                    if let Err(payload) = worker.join() {
+                        panic::resume_unwind(payload);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.size_hint
+    }
+}
+
+impl<I> SortedIterator for BackgroundLookahead<I> where I: SortedIterator {}
+
+pub trait Lookahead: Iterator + Sized {
+    /// Buffer up to `depth` values, taken when `fill` is called, for example
+    /// while the consumer is idle.
+    fn lookahead(self, depth: usize) -> LookaheadBuffer<Self> {
+        LookaheadBuffer {
+            iter: self,
+            buffer: VecDeque::with_capacity(depth),
+            depth,
+        }
+    }
+
+    /// Take the values on a new thread, which keeps up to `depth` of them
+    /// waiting, so that `next` is slow only when the consumer overtakes it.
+    fn lookahead_background(self, depth: usize) -> BackgroundLookahead<Self>
+    where
+        Self: Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        let size_hint = self.size_hint();
+        let (sender, receiver) = mpsc::sync_channel(depth);
+        let worker = thread::spawn(move || {
+            for value in self {
+                if sender.send(value).is_err() {
+                    return;
+                }
+            }
+        });
+        BackgroundLookahead {
+            receiver,
+            worker: Some(worker),
+            size_hint,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<I> Lookahead for I where I: Iterator {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Sorted, SortedBy};
+    use super::Lookahead;
+
+    #[test]
+    fn lookahead_test() {
+        let before: Vec<u64> = (0..1000).map(|i| (i * 7919) % 1000).collect();
+        let mut iter = before.clone().sorted().lookahead(16);
+        assert_eq!(0, iter.buffered());
+        iter.fill();
+        assert_eq!(16, iter.buffered());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!((999, Some(999)), iter.size_hint());
+        let after: Vec<u64> = iter.collect();
+        assert_eq!((1..1000).collect::<Vec<u64>>(), after);
+
+        let mut iter = before.clone().sorted().lookahead_background(16);
+        assert_eq!(Some(0), iter.next());
+        assert_eq!((999, Some(999)), iter.size_hint());
+        let after: Vec<u64> = iter.collect();
+        assert_eq!((1..1000).collect::<Vec<u64>>(), after);
+
+        // Dropped before the thread has finished.
+        let first: Vec<u64> = before.sorted().lookahead_background(4).take(2).collect();
+        assert_eq!(vec![0, 1], first);
+    }
+
+    #[test]
+    #[should_panic(expected = "comparator failed")]
+    fn lookahead_background_panic_test() {
+        let before: Vec<u64> = (0..100).collect();
+        let iter = before
+            .sorted_by(|a: &u64, b: &u64| {
+                if *a == 50 || *b == 50 {
+                    panic!("comparator failed");
+                }
+                a.cmp(b)
+            })
+            .lookahead_background(4);
+        for _ in iter {}
+    }
+}
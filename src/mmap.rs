@@ -0,0 +1,225 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy sorting of flat files of fixed-size records.
+//!
+//! The file is memory-mapped read-only, and a permutation of record indexes
+//! is lazily sorted instead of the records themselves, so the file is neither
+//! modified nor read into memory; only the pages holding records that are
+//! compared are ever touched.
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use libc;
+
+use super::{make_work, qsort, SortedIterator, Stack, WorkStack};
+
+/// A read-only memory map of a whole file.
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl Mapping {
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped.
+    unsafe fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Ok(Mapping {
+                ptr: ptr::null_mut(),
+                len,
+            });
+        }
+        // SAFETY: a fresh read-only mapping of the whole file, which is
+        // checked for failure below.  The mapping outlives the descriptor,
+        // which may be closed once it is made.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping { ptr, len })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            // SAFETY: `ptr` maps `len` readable bytes until `self` is dropped,
+            // and the caller of `open` promised that they do not change.
+            unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: the mapping made in `open`, which nothing borrows any
+            // longer.
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+/// A memory-mapped file of fixed-size records, ordered by a comparator over
+/// the records' bytes.
+pub struct MmapSorter<F> {
+    map: Mapping,
+    record_size: usize,
+    by: F,
+}
+
+impl<F> MmapSorter<F>
+where
+    F: Fn(&[u8], &[u8]) -> Ordering,
+{
+    /// Map the file at `path`, which must consist of whole records of
+    /// `record_size` bytes.
+    ///
+    /// # Safety
+    ///
+    /// The records are borrowed straight from the mapping, so the file must
+    /// not be modified, by this process or any other, while the sorter
+    /// exists: the records would change under the borrows, which is undefined
+    /// behaviour, and reading a record beyond the end of a truncated file
+    /// raises `SIGBUS`.
+    pub unsafe fn open<P: AsRef<Path>>(path: P, record_size: usize, by: F) -> io::Result<Self> {
+        if record_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "record size must not be zero",
+            ));
+        }
+        // SAFETY: passed on to our caller.
+        let map = Mapping::open(path.as_ref())?;
+        if map.len % record_size != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file length is not a multiple of the record size",
+            ));
+        }
+        Ok(MmapSorter {
+            map,
+            record_size,
+            by,
+        })
+    }
+
+    /// The number of records in the file.
+    pub fn len(&self) -> usize {
+        self.map.len / self.record_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.len == 0
+    }
+
+    /// A lazily sorted iterator over the records of the file.
+    pub fn sorted(&self) -> MmapSortIterator<'_, F> {
+        let len = self.len();
+        MmapSortIterator {
+            records: self.map.as_slice(),
+            record_size: self.record_size,
+            indexes: (0..len).collect(),
+            work: make_work(len),
+            by: &self.by,
+        }
+    }
+}
+
+pub struct MmapSortIterator<'a, F: 'a> {
+    records: &'a [u8],
+    record_size: usize,
+    indexes: Vec<usize>,
+    work: WorkStack,
+    by: &'a F,
+}
+
+impl<'a, F> Iterator for MmapSortIterator<'a, F>
+where
+    F: Fn(&[u8], &[u8]) -> Ordering,
+{
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let (lower, upper) = self.work.pop()?;
+        let records = self.records;
+        let size = self.record_size;
+        let record = move |i: usize| &records[i * size..(i + 1) * size];
+        let by = self.by;
+        let i = qsort(
+            &|a: &usize, b: &usize| by(record(*a), record(*b)),
+            &mut self.indexes,
+            &mut self.work,
+            lower,
+            upper,
+        );
+        Some(record(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.indexes.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, F> SortedIterator for MmapSortIterator<'a, F> where F: Fn(&[u8], &[u8]) -> Ordering {}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::process;
+
+    use super::MmapSorter;
+
+    #[test]
+    fn sorted_records_test() {
+        let path = env::temp_dir().join(format!("lazysort-mmap-{}.bin", process::id()));
+        {
+            let mut file = File::create(&path).unwrap();
+            for x in &[9u32, 7, 1, 1, 6, 3, 1, 4, 22] {
+                file.write_all(&x.to_be_bytes()).unwrap();
+            }
+        }
+
+        // The file is not changed while it is mapped.
+        let sorter = unsafe { MmapSorter::open(&path, 4, |a: &[u8], b: &[u8]| a.cmp(b)) }.unwrap();
+        let after: Vec<u32> = sorter
+            .sorted()
+            .map(|r| u32::from_be_bytes([r[0], r[1], r[2], r[3]]))
+            .collect();
+        assert_eq!(vec![1, 1, 1, 3, 4, 6, 7, 9, 22], after);
+
+        assert!(unsafe { MmapSorter::open(&path, 5, |a: &[u8], b: &[u8]| a.cmp(b)) }.is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
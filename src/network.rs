@@ -0,0 +1,131 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Sorting networks for the smallest partitions.
+//!
+//! Below `MAX` values, partitioning again costs more than simply sorting the
+//! whole range with a fixed sequence of compare-exchanges, which has no data
+//! dependent control flow beyond each conditional swap.
+
+use std::cmp::Ordering;
+use std::cmp::Ordering::Greater;
+
+/// The largest range sorted with a network.
+pub(crate) const MAX: usize = 8;
+
+// The compare-exchanges of the size-optimal network for each size, indexed by
+// the number of values.  After the exchange `(a, b)` the value at `a` is no
+// greater than the value at `b`.
+const NETWORKS: [&[(usize, usize)]; MAX + 1] = [
+    &[],
+    &[],
+    &[(0, 1)],
+    &[(0, 2), (0, 1), (1, 2)],
+    &[(0, 2), (1, 3), (0, 1), (2, 3), (1, 2)],
+    &[
+        (0, 3),
+        (1, 4),
+        (0, 2),
+        (1, 3),
+        (0, 1),
+        (2, 4),
+        (1, 2),
+        (3, 4),
+        (2, 3),
+    ],
+    &[
+        (0, 5),
+        (1, 3),
+        (2, 4),
+        (1, 2),
+        (3, 4),
+        (0, 3),
+        (2, 5),
+        (0, 1),
+        (2, 3),
+        (4, 5),
+        (1, 2),
+        (3, 4),
+    ],
+    &[
+        (0, 6),
+        (2, 3),
+        (4, 5),
+        (0, 2),
+        (1, 4),
+        (3, 6),
+        (0, 1),
+        (2, 5),
+        (3, 4),
+        (1, 2),
+        (4, 6),
+        (2, 3),
+        (4, 5),
+        (1, 2),
+        (3, 4),
+        (5, 6),
+    ],
+    &[
+        (0, 2),
+        (1, 3),
+        (4, 6),
+        (5, 7),
+        (0, 4),
+        (1, 5),
+        (2, 6),
+        (3, 7),
+        (0, 1),
+        (2, 3),
+        (4, 5),
+        (6, 7),
+        (2, 4),
+        (3, 5),
+        (1, 4),
+        (3, 6),
+        (1, 2),
+        (3, 4),
+        (5, 6),
+    ],
+];
+
+/// Sort `data[upper..=lower]` so that the lowest value is at `lower`, the
+/// orientation in which the values are popped.  The range must hold at most
+/// `MAX` values.
+pub(crate) fn sort<F, T>(by: &F, data: &mut [T], lower: usize, upper: usize)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let range = &mut data[upper..=lower];
+    let top = range.len() - 1;
+    for &(a, b) in NETWORKS[range.len()] {
+        let (a, b) = (top - a, top - b);
+        if by(&range[a], &range[b]) == Greater {
+            range.swap(a, b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sort, MAX};
+
+    #[test]
+    fn network_test() {
+        // By the 0-1 principle a network sorts everything if it sorts every
+        // sequence of zeros and ones.
+        for n in 1..=MAX {
+            for bits in 0..1u32 << n {
+                let mut data: Vec<u32> = (0..n).map(|i| (bits >> i) & 1).collect();
+                sort(&Ord::cmp, &mut data, n - 1, 0);
+                assert!(data.windows(2).all(|w| w[0] >= w[1]), "{:?}", data);
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Reordering a buffer in place by a permutation of its indexes.
+//!
+//! A permutation `perm`, such as the indexes of some values in sorted order,
+//! is applied so that the value at `perm[i]` ends at `i`.  Each cycle of the
+//! permutation is followed once, swapping each value into place, so the whole
+//! takes `n` swaps and a linear pass to validate `perm` first.  No memory is
+//! used beyond the slices themselves: entries are marked as visited by setting
+//! their high bit, which no index of a slice uses, and the marks are cleared
+//! again before returning.
+
+use std::mem;
+
+const MARK: usize = !(!0 >> 1);
+
+/// Panic unless `perm` is a permutation of the indexes of `data`, leaving
+/// every entry of `perm` marked if it is.
+fn check<T>(data: &[T], perm: &mut [usize]) {
+    assert_eq!(
+        data.len(),
+        perm.len(),
+        "permutation of {} indexes for {} values",
+        perm.len(),
+        data.len()
+    );
+    // An index out of range may have the high bit set, and so is rejected
+    // before anything is marked.
+    if let Some(j) = perm.iter().find(|&&j| j >= perm.len()) {
+        panic!("not a permutation: index {} is out of range", j);
+    }
+    // The mark on entry `j` records that some entry has named index `j`.
+    for i in 0..perm.len() {
+        let j = perm[i] & !MARK;
+        if perm[j] & MARK != 0 {
+            unmark(perm);
+            panic!("not a permutation: index {} repeats", j);
+        }
+        perm[j] |= MARK;
+    }
+}
+
+fn unmark(perm: &mut [usize]) {
+    for p in perm {
+        *p &= !MARK;
+    }
+}
+
+/// Follow the cycle of `perm` through `start`, which must not have been
+/// visited, moving each value in it into place and clearing its marks.  The
+/// cycle must not reach below `base`, where `data` begins.
+fn cycle<T>(data: &mut [T], perm: &mut [usize], base: usize, start: usize) {
+    let mut i = start;
+    loop {
+        let j = perm[i] & !MARK;
+        perm[i] = j;
+        if j == start {
+            return;
+        }
+        data.swap(i - base, j - base);
+        i = j;
+    }
+}
+
+/// Reorder `data` so that the value at `perm[i]` is at `i`.  `perm` is used
+/// to keep track of the work, and is left as it was; it is taken as
+/// `&mut [usize]` rather than `&[usize]` for that reason, so that no memory
+/// need be allocated.
+///
+/// # Panics
+///
+/// If `perm` is not a permutation of the indexes of `data`, before anything
+/// is moved.
+pub fn apply_permutation<T>(data: &mut [T], perm: &mut [usize]) {
+    check(data, perm);
+    for i in 0..data.len() {
+        if perm[i] & MARK != 0 {
+            cycle(data, perm, 0, i);
+        }
+    }
+}
+
+/// As `apply_permutation`, but yielding each position in turn once it is
+/// filled.  A position not yet filled is filled by following its cycle, which
+/// fills the other positions in the cycle too, all of them further on; so the
+/// whole is still `n` swaps, and positions already yielded are never moved
+/// again, each handed out as an exclusive reference.  Cycles not reached are
+/// left alone if the iterator is dropped, and `perm` is restored then.
+///
+/// # Panics
+///
+/// If `perm` is not a permutation of the indexes of `data`, before anything
+/// is moved.
+pub fn apply_permutation_lazy<'a, T>(data: &'a mut [T], perm: &'a mut [usize]) -> Permute<'a, T> {
+    check(data, perm);
+    Permute { rest: data, perm }
+}
+
+pub struct Permute<'a, T: 'a> {
+    rest: &'a mut [T],
+    perm: &'a mut [usize],
+}
+
+impl<'a, T> Iterator for Permute<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let rest = mem::take(&mut self.rest);
+        if rest.is_empty() {
+            return None;
+        }
+        let offset = self.perm.len() - rest.len();
+        if self.perm[offset] & MARK != 0 {
+            // The cycle visits only positions from here on, as any earlier
+            // one would have been followed when that was yielded.
+            cycle(rest, self.perm, offset, offset);
+        }
+        let (first, rest) = rest.split_first_mut()?;
+        self.rest = rest;
+        Some(first)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.rest.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T> Drop for Permute<'a, T> {
+    fn drop(&mut self) {
+        let offset = self.perm.len() - self.rest.len();
+        unmark(&mut self.perm[offset..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::super::SortedBy;
+    use super::{apply_permutation, apply_permutation_lazy};
+
+    #[test]
+    fn apply_permutation_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..500).map(|_| rng.gen_range(0, 1000)).collect();
+        let mut perm: Vec<usize> = (0..before.len())
+            .sorted_by(|a: &usize, b: &usize| before[*a].cmp(&before[*b]))
+            .collect();
+        let expected: Vec<u32> = perm.iter().map(|&i| before[i]).collect();
+        let original = perm.clone();
+
+        let mut data = before.clone();
+        apply_permutation(&mut data, &mut perm);
+        assert_eq!(expected, data);
+        assert_eq!(original, perm);
+
+        let mut data = before;
+        let first: Vec<u32> = apply_permutation_lazy(&mut data, &mut perm)
+            .take(10)
+            .map(|x| *x)
+            .collect();
+        assert_eq!(&expected[..10], &first[..]);
+        assert_eq!(&expected[..10], &data[..10]);
+        assert_eq!(original, perm);
+    }
+
+    #[test]
+    fn apply_permutation_rotation_test() {
+        // A single cycle through every index, which following each position
+        // back through the earlier ones made quadratic.
+        let n = 100_000;
+        let mut perm: Vec<usize> = (0..n).map(|i| (i + 1) % n).collect();
+        let mut data: Vec<usize> = (0..n).collect();
+        apply_permutation(&mut data, &mut perm);
+        assert_eq!(perm, data);
+
+        let mut data: Vec<usize> = (0..n).collect();
+        let after: Vec<usize> = apply_permutation_lazy(&mut data, &mut perm)
+            .map(|x| *x)
+            .collect();
+        assert_eq!(perm, after);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a permutation")]
+    fn apply_permutation_repeat_test() {
+        apply_permutation(&mut [10, 20], &mut [1, 1]);
+    }
+
+    #[test]
+    fn apply_permutation_invalid_test() {
+        let mut data = [1, 2, 3];
+        let mut perm = [2, 0, 3];
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            apply_permutation(&mut data, &mut perm)
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], data);
+        assert_eq!([2, 0, 3], perm);
+
+        // An entry with the high bit set is not confused with a marked one.
+        let mut perm = [usize::MAX, 0, 1];
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            apply_permutation(&mut data, &mut perm)
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], data);
+        assert_eq!([usize::MAX, 0, 1], perm);
+    }
+}
@@ -0,0 +1,197 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Sorting values that may be missing or failed.
+//!
+//! The values that can be ordered are lazily sorted, and the rest are kept
+//! aside, in the order they came, to be yielded together at whichever end
+//! the policy says.  Nothing is lost or short-circuited.
+
+use std::vec;
+
+use super::{LazySortIterator, Sorted};
+
+/// Where the errors go among the sorted `Ok` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrPolicy {
+    First,
+    Last,
+}
+
+/// Yields the `Ok` values sorted, and the `Err` values, in the order they
+/// came, before or after them.
+pub struct ResultSortIterator<T, E> {
+    oks: LazySortIterator<T>,
+    errs: vec::IntoIter<E>,
+    policy: ErrPolicy,
+}
+
+pub trait SortedResults<T, E>: Iterator<Item = Result<T, E>> + Sized {
+    /// Sort the `Ok` values, keeping every `Err` intact at the end
+    /// `policy` gives.
+    fn sorted_results(self, policy: ErrPolicy) -> ResultSortIterator<T, E>
+    where
+        T: Ord,
+    {
+        let mut oks = Vec::new();
+        let mut errs = Vec::new();
+        for item in self {
+            match item {
+                Ok(value) => oks.push(value),
+                Err(error) => errs.push(error),
+            }
+        }
+        ResultSortIterator {
+            oks: oks.sorted(),
+            errs: errs.into_iter(),
+            policy,
+        }
+    }
+}
+
+impl<I, T, E> SortedResults<T, E> for I where I: Iterator<Item = Result<T, E>> {}
+
+impl<T, E> Iterator for ResultSortIterator<T, E>
+where
+    T: Ord,
+{
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Result<T, E>> {
+        match self.policy {
+            ErrPolicy::First => self
+                .errs
+                .next()
+                .map(Err)
+                .or_else(|| self.oks.next().map(Ok)),
+            ErrPolicy::Last => self
+                .oks
+                .next()
+                .map(Ok)
+                .or_else(|| self.errs.next().map(Err)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.oks.size_hint().0 + self.errs.len();
+        (len, Some(len))
+    }
+}
+
+/// Where the `None`s go among the sorted values, as SQL's `NULLS FIRST` and
+/// `NULLS LAST`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonePolicy {
+    First,
+    Last,
+}
+
+/// Yields the `Some` values sorted, and the `None`s before or after them.
+pub struct OptionSortIterator<T> {
+    somes: LazySortIterator<T>,
+    nones: usize,
+    policy: NonePolicy,
+}
+
+pub trait SortedOptions<T>: Iterator<Item = Option<T>> + Sized {
+    /// Sort the `Some` values, with every `None` at the end `policy` gives.
+    fn sorted_options(self, policy: NonePolicy) -> OptionSortIterator<T>
+    where
+        T: Ord,
+    {
+        let mut somes = Vec::new();
+        let mut nones = 0;
+        for item in self {
+            match item {
+                Some(value) => somes.push(value),
+                None => nones += 1,
+            }
+        }
+        OptionSortIterator {
+            somes: somes.sorted(),
+            nones,
+            policy,
+        }
+    }
+}
+
+impl<I, T> SortedOptions<T> for I where I: Iterator<Item = Option<T>> {}
+
+impl<T> OptionSortIterator<T>
+where
+    T: Ord,
+{
+    fn next_none(&mut self) -> Option<Option<T>> {
+        if self.nones > 0 {
+            self.nones -= 1;
+            Some(None)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Iterator for OptionSortIterator<T>
+where
+    T: Ord,
+{
+    type Item = Option<T>;
+
+    fn next(&mut self) -> Option<Option<T>> {
+        match self.policy {
+            NonePolicy::First => self.next_none().or_else(|| self.somes.next().map(Some)),
+            NonePolicy::Last => self.somes.next().map(Some).or_else(|| self.next_none()),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.somes.size_hint().0 + self.nones;
+        (len, Some(len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrPolicy, NonePolicy, SortedOptions, SortedResults};
+
+    #[test]
+    fn sorted_results_test() {
+        let before: Vec<Result<u32, &str>> = vec![Ok(3), Err("b"), Ok(1), Err("a"), Ok(2)];
+        let after: Vec<Result<u32, &str>> = before
+            .clone()
+            .into_iter()
+            .sorted_results(ErrPolicy::Last)
+            .collect();
+        assert_eq!(vec![Ok(1), Ok(2), Ok(3), Err("b"), Err("a")], after);
+
+        let mut iter = before.into_iter().sorted_results(ErrPolicy::First);
+        assert_eq!((5, Some(5)), iter.size_hint());
+        assert_eq!(Some(Err("b")), iter.next());
+        assert_eq!(Some(Err("a")), iter.next());
+        assert_eq!(Some(Ok(1)), iter.next());
+    }
+
+    #[test]
+    fn sorted_options_test() {
+        let before = vec![Some(3), None, Some(1), None, Some(2)];
+        let after: Vec<Option<u32>> = before
+            .clone()
+            .into_iter()
+            .sorted_options(NonePolicy::First)
+            .collect();
+        assert_eq!(vec![None, None, Some(1), Some(2), Some(3)], after);
+
+        let after: Vec<Option<u32>> = before
+            .into_iter()
+            .sorted_options(NonePolicy::Last)
+            .collect();
+        assert_eq!(vec![Some(1), Some(2), Some(3), None, None], after);
+    }
+}
@@ -0,0 +1,20 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! All of the crate's extension traits, for a single glob import:
+//! `use lazysort::prelude::*;`.
+
+pub use super::{
+    AssumeSorted, Extremes, GroupSorted, GroupedTopK, IntoForkable, ItertoolsCompat,
+    LazyDrainSorted, LazySortSlice, Lookahead, SampleSorted, Sorted, SortedApprox, SortedArray,
+    SortedBucketed, SortedBy, SortedByKeyRef, SortedByValue, SortedCooperatively, SortedCounting,
+    SortedFixed, SortedIndirect, SortedIterator, SortedOptions, SortedPartial, SortedRadix,
+    SortedResults, SortedStable, SortedViaHeap, WeightedQuantiles,
+};
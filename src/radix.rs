@@ -0,0 +1,299 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy most-significant-digit radix sort for integer keys.
+//!
+//! Each pass distributes a bucket into 256 sub-buckets by the next byte of
+//! the key, in place, and only the bucket holding the next values is ever
+//! refined.  Small buckets are finished with a comparison sort.
+
+use std::cmp::Ordering;
+
+use super::SortedIterator;
+
+/// Buckets of at most this many values are sorted by comparison instead of
+/// being distributed further.
+const SMALL: usize = 32;
+
+/// The level of a bucket that is fully sorted.
+const SORTED: usize = usize::MAX;
+
+/// An integer type whose values can be mapped, preserving order, to unsigned
+/// keys of `BYTES` bytes.
+pub trait RadixKey: Copy {
+    const BYTES: usize;
+
+    /// The key, in the low `BYTES` bytes.
+    fn radix_key(self) -> u64;
+}
+
+macro_rules! radix_key {
+    ($t:ty, $u:ty, $flip:expr) => {
+        impl RadixKey for $t {
+            const BYTES: usize = std::mem::size_of::<$t>();
+
+            #[inline]
+            fn radix_key(self) -> u64 {
+                (self as $u ^ $flip) as u64
+            }
+        }
+    };
+}
+
+radix_key!(u8, u8, 0);
+radix_key!(u16, u16, 0);
+radix_key!(u32, u32, 0);
+radix_key!(u64, u64, 0);
+radix_key!(usize, usize, 0);
+radix_key!(i8, u8, 1 << 7);
+radix_key!(i16, u16, 1 << 15);
+radix_key!(i32, u32, 1 << 31);
+radix_key!(i64, u64, 1 << 63);
+radix_key!(isize, usize, 1 << (8 * std::mem::size_of::<isize>() - 1));
+
+/// The state shared by both radix iterators.  As elsewhere the values are
+/// held in reverse, so the bucket holding the lowest keys is at the end of
+/// `data` and on top of `buckets`.
+struct Radix<T> {
+    data: Vec<T>,
+    // `(start, level)` for each bucket, each ending where the one above it
+    // starts, and the top one at the end of `data`.
+    buckets: Vec<(usize, usize)>,
+}
+
+impl<T> Radix<T> {
+    fn new(data: Vec<T>) -> Self {
+        let buckets = if data.is_empty() {
+            Vec::new()
+        } else {
+            vec![(0, 0)]
+        };
+        Radix { data, buckets }
+    }
+
+    fn next<K, F>(&mut self, key: &F) -> Option<T>
+    where
+        K: RadixKey,
+        F: Fn(&T) -> K,
+    {
+        loop {
+            let (start, level) = *self.buckets.last()?;
+            if level == SORTED {
+                if start + 1 == self.data.len() {
+                    self.buckets.pop();
+                }
+                return self.data.pop();
+            }
+            self.buckets.pop();
+            let end = self.data.len();
+            if end - start <= SMALL {
+                self.data[start..end].sort_unstable_by(|a, b| descending(key, a, b));
+                self.buckets.push((start, SORTED));
+            } else {
+                self.distribute(key, start, end, level);
+            }
+        }
+    }
+
+    /// Distribute `data[start..end]` by the byte of the key at `level`,
+    /// counting from the most significant, and push the sub-buckets.
+    fn distribute<K, F>(&mut self, key: &F, start: usize, end: usize, level: usize)
+    where
+        K: RadixKey,
+        F: Fn(&T) -> K,
+    {
+        let shift = 8 * (K::BYTES - 1 - level);
+        // Bucket `b` holds the byte `255 - b`, so the lowest bytes end up
+        // last.
+        let bucket = |value: &T| 255 - ((key(value).radix_key() >> shift) & 0xff) as usize;
+
+        let slice = &mut self.data[start..end];
+        let mut counts = [0usize; 256];
+        for value in slice.iter() {
+            counts[bucket(value)] += 1;
+        }
+        let mut heads = [0usize; 256];
+        let mut tails = [0usize; 256];
+        let mut offset = 0;
+        for b in 0..256 {
+            heads[b] = offset;
+            offset += counts[b];
+            tails[b] = offset;
+        }
+
+        // American flag sort: swap each value into its bucket.
+        for b in 0..256 {
+            while heads[b] < tails[b] {
+                let target = bucket(&slice[heads[b]]);
+                if target == b {
+                    heads[b] += 1;
+                } else {
+                    slice.swap(heads[b], heads[target]);
+                    heads[target] += 1;
+                }
+            }
+        }
+
+        let level = if level + 1 == K::BYTES {
+            SORTED
+        } else {
+            level + 1
+        };
+        let mut offset = start;
+        for count in counts.iter() {
+            if *count > 0 {
+                self.buckets.push((offset, level));
+                offset += count;
+            }
+        }
+    }
+}
+
+#[inline]
+fn descending<T, K, F>(key: &F, a: &T, b: &T) -> Ordering
+where
+    K: RadixKey,
+    F: Fn(&T) -> K,
+{
+    key(b).radix_key().cmp(&key(a).radix_key())
+}
+
+pub struct RadixSortIterator<T> {
+    radix: Radix<T>,
+}
+
+pub struct RadixSortIteratorByKey<T, F> {
+    radix: Radix<T>,
+    key: F,
+}
+
+pub trait SortedRadix {
+    type Item;
+
+    fn sorted_radix(self) -> RadixSortIterator<Self::Item>
+    where
+        Self::Item: RadixKey;
+
+    fn sorted_radix_by_key<K, F>(self, key: F) -> RadixSortIteratorByKey<Self::Item, F>
+    where
+        K: RadixKey,
+        F: Fn(&Self::Item) -> K;
+}
+
+impl<T, I> SortedRadix for I
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn sorted_radix(self) -> RadixSortIterator<T>
+    where
+        T: RadixKey,
+    {
+        RadixSortIterator {
+            radix: Radix::new(self.collect()),
+        }
+    }
+
+    fn sorted_radix_by_key<K, F>(self, key: F) -> RadixSortIteratorByKey<T, F>
+    where
+        K: RadixKey,
+        F: Fn(&T) -> K,
+    {
+        RadixSortIteratorByKey {
+            radix: Radix::new(self.collect()),
+            key,
+        }
+    }
+}
+
+impl<T> Iterator for RadixSortIterator<T>
+where
+    T: RadixKey,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.radix.next(&|x: &T| *x)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.radix.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<T> SortedIterator for RadixSortIterator<T> where T: RadixKey {}
+
+impl<T, K, F> Iterator for RadixSortIteratorByKey<T, F>
+where
+    K: RadixKey,
+    F: Fn(&T) -> K,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.radix.next(&self.key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.radix.data.len();
+        (l, Some(l))
+    }
+}
+
+impl<T, K, F> SortedIterator for RadixSortIteratorByKey<T, F>
+where
+    K: RadixKey,
+    F: Fn(&T) -> K,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::SortedRadix;
+
+    #[test]
+    fn sorted_radix_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u64> = (0..10000).map(|_| rng.gen()).collect();
+        let mut expected = before.clone();
+        expected.sort();
+        let after: Vec<u64> = before.into_iter().sorted_radix().collect();
+        assert_eq!(expected, after);
+
+        let before: Vec<i32> = (0..10000).map(|_| rng.gen_range(-500, 500)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+        let after: Vec<i32> = before.into_iter().sorted_radix().take(100).collect();
+        assert_eq!(&expected[..100], &after[..]);
+    }
+
+    #[test]
+    fn sorted_radix_by_key_test() {
+        let before: Vec<(i16, String)> = (0..1000i32)
+            .map(|i| (((i * 7919) % 1000 - 500) as i16, i.to_string()))
+            .collect();
+        let after: Vec<i16> = before
+            .into_iter()
+            .sorted_radix_by_key(|x| x.0)
+            .map(|x| x.0)
+            .collect();
+        assert_eq!((-500..500).collect::<Vec<i16>>(), after);
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazily sorted random samples.
+//!
+//! A reservoir of `k` values is kept while the source is consumed, so only
+//! the sample is ever held in memory, and only the sample is sorted.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use super::{LazySortIterator, LazySortIteratorBy};
+
+/// A xorshift generator, seeded from the standard library's per-process
+/// random hashing keys.  It only needs to be good enough to choose a sample.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(0x9e37_79b9_7f4a_7c15);
+        XorShift(hasher.finish() | 1)
+    }
+
+    /// A value in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        ((self.0 as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// Choose `k` values uniformly at random, or every value if there are fewer.
+fn reservoir<T, I>(iter: I, k: usize) -> Vec<T>
+where
+    I: Iterator<Item = T>,
+{
+    let mut rng = XorShift::new();
+    let mut sample = Vec::with_capacity(k.min(iter.size_hint().0));
+    for (i, value) in iter.enumerate() {
+        if i < k {
+            sample.push(value);
+        } else {
+            let j = rng.below(i + 1);
+            if j < k {
+                sample[j] = value;
+            }
+        }
+    }
+    sample
+}
+
+pub trait SampleSorted {
+    type Item;
+
+    /// A random sample of `k` of the values, sorted lazily.
+    fn sample_sorted(self, k: usize) -> LazySortIterator<Self::Item>
+    where
+        Self::Item: Ord;
+
+    fn sample_sorted_by<F>(self, k: usize, by: F) -> LazySortIteratorBy<Self::Item, F>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering;
+}
+
+impl<T, I> SampleSorted for I
+where
+    I: Iterator<Item = T>,
+{
+    type Item = T;
+
+    fn sample_sorted(self, k: usize) -> LazySortIterator<T>
+    where
+        T: Ord,
+    {
+        LazySortIterator::new(reservoir(self, k))
+    }
+
+    fn sample_sorted_by<F>(self, k: usize, by: F) -> LazySortIteratorBy<T, F>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        LazySortIteratorBy::new(reservoir(self, k), by)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SampleSorted;
+
+    #[test]
+    fn sample_sorted_test() {
+        let after: Vec<u32> = (0..100000u32).sample_sorted(100).collect();
+        assert_eq!(100, after.len());
+        assert!(after.windows(2).all(|w| w[0] < w[1]));
+        // A uniform sample is very unlikely to miss either quarter.
+        assert!(after[0] < 25000);
+        assert!(after[99] >= 75000);
+
+        let after: Vec<u32> = (0..5u32).sample_sorted_by(10, |a, b| b.cmp(a)).collect();
+        assert_eq!(vec![4, 3, 2, 1, 0], after);
+    }
+}
@@ -0,0 +1,184 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! In-place selection and partial sorting of slices.
+//!
+//! These partition the slice itself, with the same partitioning as the lazy
+//! sorts, following only the side that holds the values asked for.  The lazy
+//! sorts keep the lowest values at the highest indexes, so the comparator is
+//! reversed here to leave them at the front of the slice instead.
+
+use std::cmp::Ordering;
+
+use super::algo;
+
+/// Partition `data` until the value at `n` is in its sorted place, with no
+/// greater values before it and no lower values after it.
+fn select<T, F>(by: &F, data: &mut [T], n: usize, deterministic: bool)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let last = data.len() - 1;
+    algo::select_with(&|a: &T, b: &T| by(b, a), data, last, 0, n, deterministic)
+}
+
+/// Partition `data` so that the value at `n` is the one that would be there
+/// were it sorted, with no greater values before it and no lower values after
+/// it, and return that value.
+///
+/// # Panics
+///
+/// If `n` is not an index of `data`.
+pub fn nth_element<T>(data: &mut [T], n: usize) -> &mut T
+where
+    T: Ord,
+{
+    nth_element_by(data, n, Ord::cmp)
+}
+
+/// As `nth_element`, ordered by `by`.
+pub fn nth_element_by<T, F>(data: &mut [T], n: usize, by: F) -> &mut T
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    assert!(
+        n < data.len(),
+        "index {} out of range for {} values",
+        n,
+        data.len()
+    );
+    select(&by, data, n, false);
+    &mut data[n]
+}
+
+/// As `nth_element`, ordered by the key `key` gives each value.
+pub fn nth_element_by_key<T, K, F>(data: &mut [T], n: usize, key: F) -> &mut T
+where
+    K: Ord,
+    F: Fn(&T) -> K,
+{
+    nth_element_by(data, n, |a, b| key(a).cmp(&key(b)))
+}
+
+/// As `nth_element_by`, but pivoting on the median of medians, so that it
+/// takes linear time in the worst case rather than only on average.
+///
+/// # Panics
+///
+/// If `n` is not an index of `data`.
+pub fn nth_element_deterministic_by<T, F>(data: &mut [T], n: usize, by: F) -> &mut T
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    assert!(
+        n < data.len(),
+        "index {} out of range for {} values",
+        n,
+        data.len()
+    );
+    select(&by, data, n, true);
+    &mut data[n]
+}
+
+/// Sort the lowest `n` values of `data` into place at its front, leaving the
+/// rest after them in no particular order.
+pub fn partial_sort<T>(data: &mut [T], n: usize)
+where
+    T: Ord,
+{
+    partial_sort_by(data, n, Ord::cmp)
+}
+
+/// As `partial_sort`, ordered by `by`.
+pub fn partial_sort_by<T, F>(data: &mut [T], n: usize, by: F)
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    if n == 0 || data.is_empty() {
+        return;
+    }
+    if n < data.len() {
+        select(&by, data, n - 1, false);
+    }
+    let n = n.min(data.len());
+    data[..n].sort_unstable_by(by);
+}
+
+/// As `partial_sort`, ordered by the key `key` gives each value.
+pub fn partial_sort_by_key<T, K, F>(data: &mut [T], n: usize, key: F)
+where
+    K: Ord,
+    F: Fn(&T) -> K,
+{
+    partial_sort_by(data, n, |a, b| key(a).cmp(&key(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::{
+        nth_element, nth_element_by_key, nth_element_deterministic_by, partial_sort,
+        partial_sort_by_key,
+    };
+
+    #[test]
+    fn partial_sort_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 100)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        for &n in [0, 1, 10, 999, 1000, 2000].iter() {
+            let mut after = before.clone();
+            partial_sort(&mut after, n);
+            let n = n.min(1000);
+            assert_eq!(&expected[..n], &after[..n]);
+            after[n..].sort();
+            assert_eq!(expected, after);
+        }
+
+        let mut words = vec!["ccc", "a", "dddd", "bb"];
+        partial_sort_by_key(&mut words, 2, |w| w.len());
+        assert_eq!(&["a", "bb"], &words[..2]);
+    }
+
+    #[test]
+    fn nth_element_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..1000).map(|_| rng.gen_range(0, 100)).collect();
+        let mut expected = before.clone();
+        expected.sort();
+
+        for &n in [0, 1, 500, 999].iter() {
+            let mut after = before.clone();
+            assert_eq!(expected[n], *nth_element(&mut after, n));
+            let mut deterministic = before.clone();
+            assert_eq!(
+                expected[n],
+                *nth_element_deterministic_by(&mut deterministic, n, |a, b| a.cmp(b))
+            );
+            assert!(after[..n].iter().all(|&x| x <= after[n]));
+            assert!(after[n + 1..].iter().all(|&x| x >= after[n]));
+        }
+
+        let mut words = vec!["ccc", "a", "dddd", "bb"];
+        *nth_element_by_key(&mut words, 3, |w| w.len()) = "e";
+        assert_eq!("e", words[3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn nth_element_out_of_range_test() {
+        nth_element(&mut [1, 2, 3], 3);
+    }
+}
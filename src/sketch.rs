@@ -0,0 +1,171 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Approximate quantiles of a stream that is too large to collect.
+//!
+//! This is the Greenwald-Khanna summary: a sorted list of sampled values, each
+//! with bounds on its rank among all the values seen.  Neighbouring samples are
+//! merged whenever the bounds allow, so the summary holds
+//! `O(log(epsilon * n) / epsilon)` values however long the stream is.
+
+struct Sample<T> {
+    value: T,
+    // The lowest possible rank of this sample, less that of the one before.
+    g: usize,
+    // The highest possible rank, less the lowest.
+    delta: usize,
+}
+
+/// A summary of a stream of values that can answer any quantile to within
+/// `epsilon * n` ranks.
+pub struct QuantileSketch<T> {
+    epsilon: f64,
+    len: usize,
+    samples: Vec<Sample<T>>,
+}
+
+impl<T> QuantileSketch<T>
+where
+    T: PartialOrd,
+{
+    /// An empty sketch, answering to within `epsilon` (between 0 and 1) of
+    /// the number of values added.
+    pub fn new(epsilon: f64) -> Self {
+        assert!(
+            epsilon > 0.0 && epsilon < 1.0,
+            "epsilon must be between 0 and 1"
+        );
+        QuantileSketch {
+            epsilon,
+            len: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// The number of values added.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add a value.  A value that is not comparable with itself, such as a
+    /// NaN, has no rank and is ignored.
+    pub fn add(&mut self, value: T) {
+        if value.partial_cmp(&value).is_none() {
+            return;
+        }
+        let i = self.samples.partition_point(|s| s.value <= value);
+        let delta = if i == 0 || i == self.samples.len() {
+            0
+        } else {
+            self.threshold()
+        };
+        self.samples.insert(i, Sample { value, g: 1, delta });
+        self.len += 1;
+
+        let period = ((1.0 / (2.0 * self.epsilon)) as usize).max(1);
+        if self.len.is_multiple_of(period) {
+            self.compress();
+        }
+    }
+
+    /// A value whose rank is within `epsilon * len` of `phi * len`, where
+    /// `phi` is between 0 and 1; so `quantile(0.5)` is an approximate median.
+    pub fn quantile(&self, phi: f64) -> Option<&T> {
+        let rank = phi.clamp(0.0, 1.0) * self.len as f64;
+        let bound = self.epsilon * self.len as f64;
+        let mut rmin = 0;
+        let mut previous = None;
+        for sample in self.samples.iter() {
+            rmin += sample.g;
+            if (rmin + sample.delta) as f64 > rank + bound {
+                return previous.or(Some(&sample.value));
+            }
+            previous = Some(&sample.value);
+        }
+        previous
+    }
+
+    /// The most that the ranks of a new sample, or a merged pair of samples,
+    /// may be uncertain by.
+    fn threshold(&self) -> usize {
+        (2.0 * self.epsilon * self.len as f64) as usize
+    }
+
+    /// Merge each sample into the one after it where the combined bounds stay
+    /// within the threshold.  The first and last samples, the minimum and
+    /// maximum, are always kept.
+    fn compress(&mut self) {
+        let threshold = self.threshold();
+        let mut i = self.samples.len().saturating_sub(2);
+        while i >= 1 {
+            let (g, next) = (self.samples[i].g, &self.samples[i + 1]);
+            if g + next.g + next.delta <= threshold {
+                self.samples[i + 1].g += g;
+                self.samples.remove(i);
+            }
+            i -= 1;
+        }
+    }
+}
+
+impl<T> Extend<T> for QuantileSketch<T>
+where
+    T: PartialOrd,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::QuantileSketch;
+
+    #[test]
+    fn quantile_sketch_test() {
+        let mut rng = rand::thread_rng();
+        let before: Vec<u32> = (0..100000).map(|_| rng.gen_range(0, 1000000)).collect();
+        let mut sorted = before.clone();
+        sorted.sort();
+
+        let mut sketch = QuantileSketch::new(0.01);
+        sketch.extend(before);
+        assert_eq!(100000, sketch.len());
+        assert!(sketch.samples.len() < 2000);
+
+        for &phi in [0.0, 0.1, 0.25, 0.5, 0.9, 0.99, 1.0].iter() {
+            let value = *sketch.quantile(phi).unwrap();
+            let lowest = sorted.partition_point(|x| *x < value) as f64;
+            let highest = sorted.partition_point(|x| *x <= value) as f64;
+            let rank = phi * 100000.0;
+            assert!(lowest - 1000.0 <= rank && rank <= highest + 1000.0);
+        }
+    }
+
+    #[test]
+    fn quantile_sketch_nan_test() {
+        let mut sketch = QuantileSketch::new(0.1);
+        assert_eq!(None, sketch.quantile(0.5));
+        sketch.extend(vec![3.0, f64::NAN, 1.0, 2.0]);
+        assert_eq!(3, sketch.len());
+        assert_eq!(Some(&1.0), sketch.quantile(0.0));
+        assert_eq!(Some(&3.0), sketch.quantile(1.0));
+    }
+}
@@ -0,0 +1,296 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy sorting of a borrowed slice.
+//!
+//! As in `indirect`, a permutation of indexes is sorted rather than the
+//! values, so the slice itself is never reordered and its values are never
+//! moved or cloned.  The mutable iterators hand out each value's exclusive
+//! reference once, when it is yielded; as yielded indexes are gone from the
+//! permutation, values that have been handed out are never compared again.
+
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+use super::{make_work, qsort, SortedIterator, Stack, WorkStack};
+
+/// The index of the next value, by `by`, of those of `data` whose indexes
+/// remain in `indexes`.
+pub(crate) fn next<T, F>(
+    by: &F,
+    data: &[T],
+    indexes: &mut Vec<usize>,
+    work: &mut WorkStack,
+) -> Option<usize>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let (lower, upper) = work.pop()?;
+    Some(qsort(
+        &|a: &usize, b: &usize| by(&data[*a], &data[*b]),
+        indexes,
+        work,
+        lower,
+        upper,
+    ))
+}
+
+/// As `next`, for the values behind `data`, which are only ever read one at a
+/// time so that no reference overlaps a value already handed out.
+unsafe fn next_mut<T, F>(
+    by: &F,
+    data: *mut T,
+    indexes: &mut Vec<usize>,
+    work: &mut WorkStack,
+) -> Option<usize>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let (lower, upper) = work.pop()?;
+    Some(qsort(
+        &|a: &usize, b: &usize| by(&*data.add(*a), &*data.add(*b)),
+        indexes,
+        work,
+        lower,
+        upper,
+    ))
+}
+
+pub struct SliceSortIterator<'a, T: 'a> {
+    data: &'a [T],
+    indexes: Vec<usize>,
+    work: WorkStack,
+}
+
+pub struct SliceSortIteratorBy<'a, T: 'a, F> {
+    data: &'a [T],
+    indexes: Vec<usize>,
+    work: WorkStack,
+    by: F,
+}
+
+pub struct SliceSortIterMut<'a, T: 'a> {
+    data: *mut T,
+    indexes: Vec<usize>,
+    work: WorkStack,
+    marker: PhantomData<&'a mut [T]>,
+}
+
+pub struct SliceSortIterMutBy<'a, T: 'a, F> {
+    data: *mut T,
+    indexes: Vec<usize>,
+    work: WorkStack,
+    by: F,
+    marker: PhantomData<&'a mut [T]>,
+}
+
+// As with `std::slice::IterMut`, the raw pointer stands in for `&'a mut [T]`.
+unsafe impl<'a, T: Send> Send for SliceSortIterMut<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for SliceSortIterMut<'a, T> {}
+unsafe impl<'a, T: Send, F: Send> Send for SliceSortIterMutBy<'a, T, F> {}
+unsafe impl<'a, T: Sync, F: Sync> Sync for SliceSortIterMutBy<'a, T, F> {}
+
+pub trait LazySortSlice<T> {
+    /// Iterate over references to the values in sorted order, leaving the
+    /// slice as it is.
+    fn lazy_sorted(&self) -> SliceSortIterator<'_, T>
+    where
+        T: Ord;
+
+    fn lazy_sorted_by<F>(&self, by: F) -> SliceSortIteratorBy<'_, T, F>
+    where
+        F: Fn(&T, &T) -> Ordering;
+
+    /// Iterate over exclusive references to the values in sorted order,
+    /// leaving the slice in its order.
+    fn lazy_sorted_mut(&mut self) -> SliceSortIterMut<'_, T>
+    where
+        T: Ord;
+
+    fn lazy_sorted_mut_by<F>(&mut self, by: F) -> SliceSortIterMutBy<'_, T, F>
+    where
+        F: Fn(&T, &T) -> Ordering;
+}
+
+impl<T> LazySortSlice<T> for [T] {
+    fn lazy_sorted(&self) -> SliceSortIterator<'_, T>
+    where
+        T: Ord,
+    {
+        SliceSortIterator {
+            data: self,
+            indexes: (0..self.len()).collect(),
+            work: make_work(self.len()),
+        }
+    }
+
+    fn lazy_sorted_by<F>(&self, by: F) -> SliceSortIteratorBy<'_, T, F>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        SliceSortIteratorBy {
+            data: self,
+            indexes: (0..self.len()).collect(),
+            work: make_work(self.len()),
+            by,
+        }
+    }
+
+    fn lazy_sorted_mut(&mut self) -> SliceSortIterMut<'_, T>
+    where
+        T: Ord,
+    {
+        SliceSortIterMut {
+            data: self.as_mut_ptr(),
+            indexes: (0..self.len()).collect(),
+            work: make_work(self.len()),
+            marker: PhantomData,
+        }
+    }
+
+    fn lazy_sorted_mut_by<F>(&mut self, by: F) -> SliceSortIterMutBy<'_, T, F>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        SliceSortIterMutBy {
+            data: self.as_mut_ptr(),
+            indexes: (0..self.len()).collect(),
+            work: make_work(self.len()),
+            by,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for SliceSortIterator<'a, T>
+where
+    T: Ord,
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        let i = next(&Ord::cmp, self.data, &mut self.indexes, &mut self.work)?;
+        Some(&self.data[i])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.indexes.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T> SortedIterator for SliceSortIterator<'a, T> where T: Ord {}
+
+impl<'a, T, F> Iterator for SliceSortIteratorBy<'a, T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        let i = next(&self.by, self.data, &mut self.indexes, &mut self.work)?;
+        Some(&self.data[i])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.indexes.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T, F> SortedIterator for SliceSortIteratorBy<'a, T, F> where F: Fn(&T, &T) -> Ordering {}
+
+impl<'a, T> Iterator for SliceSortIterMut<'a, T>
+where
+    T: Ord,
+{
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        // Each index is yielded once, so the references never alias.
+        unsafe {
+            let i = next_mut(&Ord::cmp, self.data, &mut self.indexes, &mut self.work)?;
+            Some(&mut *self.data.add(i))
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.indexes.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T> SortedIterator for SliceSortIterMut<'a, T> where T: Ord {}
+
+impl<'a, T, F> Iterator for SliceSortIterMutBy<'a, T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        unsafe {
+            let i = next_mut(&self.by, self.data, &mut self.indexes, &mut self.work)?;
+            Some(&mut *self.data.add(i))
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.indexes.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, T, F> SortedIterator for SliceSortIterMutBy<'a, T, F> where F: Fn(&T, &T) -> Ordering {}
+
+#[cfg(test)]
+mod tests {
+    use super::LazySortSlice;
+
+    #[test]
+    fn lazy_sorted_test() {
+        let before: Vec<String> = vec!["pear", "apple", "fig", "kiwi"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let after: Vec<&String> = before.lazy_sorted().collect();
+        assert_eq!(vec!["apple", "fig", "kiwi", "pear"], after);
+        assert_eq!("pear", before[0]);
+
+        let first: Vec<&String> = before
+            .lazy_sorted_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)))
+            .take(2)
+            .collect();
+        assert_eq!(vec!["fig", "kiwi"], first);
+    }
+
+    #[test]
+    fn lazy_sorted_mut_test() {
+        let mut values = vec![40u32, 10, 30, 20];
+        for (rank, x) in values.lazy_sorted_mut().enumerate() {
+            *x += rank as u32;
+        }
+        assert_eq!(vec![43, 10, 32, 21], values);
+
+        let held: Vec<&mut u32> = values.lazy_sorted_mut_by(|a, b| b.cmp(a)).take(2).collect();
+        for x in held {
+            *x = 0;
+        }
+        assert_eq!(vec![0, 10, 0, 21], values);
+    }
+}
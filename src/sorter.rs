@@ -0,0 +1,129 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Gathering values before sorting them.
+//!
+//! A `Sorter` is a buffer with a comparator attached: values are pushed or
+//! extended into it from wherever they come from, for as long as needed, and
+//! `into_sorted` then hands the buffer to a lazy sort without copying it.
+
+use std::iter::FromIterator;
+
+use super::compare::Natural;
+use super::{Compare, LazySortIteratorBy};
+
+/// Collects values to be sorted from any number of sources, over time, and
+/// then sorts them lazily by the comparator it was created with.
+pub struct Sorter<T, F = Natural> {
+    data: Vec<T>,
+    by: F,
+}
+
+impl<T: Ord> Sorter<T> {
+    pub fn new() -> Self {
+        Sorter::with_comparator(Natural)
+    }
+}
+
+impl<T: Ord> Default for Sorter<T> {
+    fn default() -> Self {
+        Sorter::new()
+    }
+}
+
+impl<T, F> Sorter<T, F>
+where
+    F: Compare<T>,
+{
+    pub fn with_comparator(by: F) -> Self {
+        Sorter {
+            data: Vec::new(),
+            by,
+        }
+    }
+
+    /// Reserve room for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        self.data.reserve(additional)
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.data.push(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Start sorting the values collected so far.
+    pub fn into_sorted(self) -> LazySortIteratorBy<T, F> {
+        LazySortIteratorBy::new(self.data, self.by)
+    }
+}
+
+impl<T, F> Extend<T> for Sorter<T, F>
+where
+    F: Compare<T>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.data.extend(iter)
+    }
+}
+
+impl<T: Ord> FromIterator<T> for Sorter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut sorter = Sorter::new();
+        sorter.extend(iter);
+        sorter
+    }
+}
+
+impl<T, F> IntoIterator for Sorter<T, F>
+where
+    F: Compare<T>,
+{
+    type Item = T;
+    type IntoIter = LazySortIteratorBy<T, F>;
+
+    fn into_iter(self) -> LazySortIteratorBy<T, F> {
+        self.into_sorted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::compare::{Natural, Reverse};
+    use super::Sorter;
+
+    #[test]
+    fn sorter_test() {
+        let mut sorter = Sorter::new();
+        sorter.extend(vec![5, 3]);
+        sorter.push(4);
+        sorter.extend((0..3).rev());
+        assert_eq!(6, sorter.len());
+        assert_eq!(
+            vec![0, 1, 2, 3, 4, 5],
+            sorter.into_iter().collect::<Vec<u32>>()
+        );
+
+        let mut sorter = Sorter::with_comparator(Reverse(Natural));
+        sorter.extend(vec!["b", "c"]);
+        sorter.extend(vec!["a"]);
+        assert_eq!(
+            vec!["c", "b", "a"],
+            sorter.into_sorted().collect::<Vec<&str>>()
+        );
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Stable lazy sorting.
+//!
+//! Each value is tagged with its position in the source, and values that
+//! compare equal are ordered by their positions, so the usual (unstable)
+//! quicksort yields them in their original order.  This costs a `usize` per
+//! value, and no other change to the sort.
+
+use std::cmp::Ordering;
+
+use super::compare::Natural;
+use super::{Compare, LazySortIteratorBy, SortedBy, SortedIterator};
+
+/// Orders tagged values by `C`, then by their tags.
+struct Stable<C>(C);
+
+impl<T, C> Compare<(usize, T)> for Stable<C>
+where
+    C: Compare<T>,
+{
+    #[inline(always)]
+    fn compare(&self, a: &(usize, T), b: &(usize, T)) -> Ordering {
+        match self.0.compare(&a.1, &b.1) {
+            Ordering::Equal => a.0.cmp(&b.0),
+            order => order,
+        }
+    }
+}
+
+pub struct StableSortIterator<T> {
+    iter: LazySortIteratorBy<(usize, T), Stable<Natural>>,
+}
+
+pub struct StableSortIteratorBy<T, F> {
+    iter: LazySortIteratorBy<(usize, T), Stable<F>>,
+}
+
+pub trait SortedStable {
+    type Item;
+
+    /// As `sorted`, keeping values that are equal in their original order.
+    fn sorted_stable(self) -> StableSortIterator<Self::Item>
+    where
+        Self::Item: Ord;
+
+    fn sorted_stable_by<F>(self, by: F) -> StableSortIteratorBy<Self::Item, F>
+    where
+        F: Fn(&Self::Item, &Self::Item) -> Ordering;
+}
+
+impl<T, I> SortedStable for I
+where
+    I: IntoIterator<Item = T>,
+{
+    type Item = T;
+
+    fn sorted_stable(self) -> StableSortIterator<T>
+    where
+        T: Ord,
+    {
+        StableSortIterator {
+            iter: self.into_iter().enumerate().sorted_with(Stable(Natural)),
+        }
+    }
+
+    fn sorted_stable_by<F>(self, by: F) -> StableSortIteratorBy<T, F>
+    where
+        F: Fn(&T, &T) -> Ordering,
+    {
+        StableSortIteratorBy {
+            iter: self.into_iter().enumerate().sorted_with(Stable(by)),
+        }
+    }
+}
+
+impl<T> Iterator for StableSortIterator<T>
+where
+    T: Ord,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> SortedIterator for StableSortIterator<T> where T: Ord {}
+
+impl<T, F> Iterator for StableSortIteratorBy<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|(_, value)| value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, F> SortedIterator for StableSortIteratorBy<T, F> where F: Fn(&T, &T) -> Ordering {}
+
+#[cfg(test)]
+mod tests {
+    use super::SortedStable;
+
+    #[test]
+    fn sorted_stable_test() {
+        let before: Vec<(u32, u32)> = (0..1000).map(|i| ((i * 7919) % 10, i)).collect();
+        let mut expected = before.clone();
+        expected.sort_by_key(|x| x.0);
+
+        let after: Vec<(u32, u32)> = before
+            .iter()
+            .cloned()
+            .sorted_stable_by(|a, b| a.0.cmp(&b.0))
+            .collect();
+        assert_eq!(expected, after);
+
+        let after: Vec<u32> = vec![3, 1, 2].sorted_stable().collect();
+        assert_eq!(vec![1, 2, 3], after);
+    }
+}
@@ -0,0 +1,299 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Checking lazy sorts, and adaptors built on them, against an eager sort.
+//!
+//! The oracle sorts a copy of the values by insertion, and each step of the
+//! sort under test is compared with it.  Values are compared with the comparator
+//! rather than for equality, so an unstable sort may yield equal values in
+//! any order.  `check_prefixes` needs only an iterator; `check_ops` also
+//! interleaves `peek`, `nth` and `push`, through the `Probe` trait.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::{
+    Compare, LazySortIterator, LazySortIteratorBy, LazySortIteratorPartialFirst,
+    LazySortIteratorPartialLast,
+};
+
+/// The parts of a lazy sort that `check_ops` exercises beyond `next`.
+pub trait Probe: Iterator {
+    fn probe_peek(&mut self) -> Option<&Self::Item>;
+
+    fn probe_push(&mut self, value: Self::Item);
+}
+
+macro_rules! add_probe {
+    ($name:ident, $bound:path) => {
+        impl<T> Probe for $name<T>
+        where
+            T: $bound,
+        {
+            fn probe_peek(&mut self) -> Option<&T> {
+                self.peek()
+            }
+
+            fn probe_push(&mut self, value: T) {
+                self.push(value)
+            }
+        }
+    };
+}
+
+add_probe!(LazySortIterator, Ord);
+add_probe!(LazySortIteratorPartialFirst, PartialOrd);
+add_probe!(LazySortIteratorPartialLast, PartialOrd);
+
+impl<T, F> Probe for LazySortIteratorBy<T, F>
+where
+    F: Compare<T>,
+{
+    fn probe_peek(&mut self) -> Option<&T> {
+        self.peek()
+    }
+
+    fn probe_push(&mut self, value: T) {
+        self.push(value)
+    }
+}
+
+/// One step of a check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op<T> {
+    Next,
+    Peek,
+    Nth(usize),
+    Push(T),
+}
+
+/// Where the sort under test and the oracle disagreed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch<T> {
+    /// The number of the step, counting from 0.  Steps after the given
+    /// operations are the `next` calls that drain what remains.
+    pub step: usize,
+    pub expected: Option<T>,
+    pub found: Option<T>,
+}
+
+impl<T: fmt::Debug> fmt::Display for Mismatch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "step {}: expected {:?}, found {:?}",
+            self.step, self.expected, self.found
+        )
+    }
+}
+
+/// The values still to come, sorted highest first so the next is popped.
+///
+/// Each value is inserted where a binary search puts it, rather than the lot
+/// sorted with the standard library, whose sorts may panic on a comparator
+/// that is not a total order.  The oracle's order then means nothing, but a
+/// check of such a comparator reports a mismatch instead of panicking.
+struct Oracle<T, F> {
+    values: Vec<T>,
+    by: F,
+}
+
+impl<T, F> Oracle<T, F>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    fn new(values: Vec<T>, by: F) -> Self {
+        let mut oracle = Oracle {
+            values: Vec::with_capacity(values.len()),
+            by,
+        };
+        for value in values {
+            oracle.push(value);
+        }
+        oracle
+    }
+
+    fn push(&mut self, value: T) {
+        let by = &self.by;
+        let i = self
+            .values
+            .partition_point(|x| by(x, &value) == Ordering::Greater);
+        self.values.insert(i, value);
+    }
+
+    fn check(&self, step: usize, expected: Option<T>, found: Option<T>) -> Result<(), Mismatch<T>> {
+        let same = match (&expected, &found) {
+            (Some(a), Some(b)) => (self.by)(a, b) == Ordering::Equal,
+            (None, None) => true,
+            _ => false,
+        };
+        if same {
+            Ok(())
+        } else {
+            Err(Mismatch {
+                step,
+                expected,
+                found,
+            })
+        }
+    }
+}
+
+/// Run `ops` against `iter`, a lazy sort of `values` by `by`, and then drain
+/// it, checking every value it yields against an eager sort.
+pub fn check_ops<I, F>(
+    mut iter: I,
+    values: Vec<I::Item>,
+    by: F,
+    ops: Vec<Op<I::Item>>,
+) -> Result<(), Mismatch<I::Item>>
+where
+    I: Probe,
+    I::Item: Clone,
+    F: Fn(&I::Item, &I::Item) -> Ordering,
+{
+    let mut oracle = Oracle::new(values, by);
+    let steps = ops.len();
+    for (step, op) in ops.into_iter().enumerate() {
+        match op {
+            Op::Next => {
+                let expected = oracle.values.pop();
+                oracle.check(step, expected, iter.next())?;
+            }
+            Op::Peek => {
+                let expected = oracle.values.last().cloned();
+                oracle.check(step, expected, iter.probe_peek().cloned())?;
+            }
+            Op::Nth(n) => {
+                let keep = oracle.values.len().saturating_sub(n);
+                oracle.values.truncate(keep);
+                let expected = oracle.values.pop();
+                oracle.check(step, expected, iter.nth(n))?;
+            }
+            Op::Push(value) => {
+                oracle.push(value.clone());
+                iter.probe_push(value);
+            }
+        }
+    }
+    let mut step = steps;
+    loop {
+        let expected = oracle.values.pop();
+        let done = expected.is_none();
+        oracle.check(step, expected, iter.next())?;
+        if done {
+            return Ok(());
+        }
+        step += 1;
+    }
+}
+
+/// Check the first `k` values of the iterators made by `make` from `values`,
+/// for every `k` up to the number of values, against an eager sort by `by`.
+pub fn check_prefixes<T, F, M, I>(values: &[T], by: F, mut make: M) -> Result<(), Mismatch<T>>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+    M: FnMut(Vec<T>) -> I,
+    I: Iterator<Item = T>,
+{
+    let oracle = Oracle::new(values.to_vec(), by);
+    for k in 0..=values.len() {
+        let found = make(values.to_vec()).take(k);
+        for (step, value) in found.enumerate() {
+            let expected = oracle.values[values.len() - 1 - step].clone();
+            oracle.check(step, Some(expected), Some(value))?;
+        }
+    }
+    let mut all = make(values.to_vec()).skip(values.len());
+    oracle.check(values.len(), None, all.next())
+}
+
+/// A pseudo-random sequence of `steps` operations, the same for each `seed`,
+/// pushing values from `pushes` in turn.
+pub fn interleaving<T: Clone>(seed: u64, steps: usize, pushes: &[T]) -> Vec<Op<T>> {
+    // xorshift64*, which is plenty for choosing operations.
+    let mut state = seed | 1;
+    let mut next = move || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    };
+    let mut pushed = 0;
+    (0..steps)
+        .map(|_| match next() % 8 {
+            0..=3 => Op::Next,
+            4 | 5 => Op::Peek,
+            6 => Op::Nth((next() % 8) as usize),
+            _ if pushed < pushes.len() => {
+                pushed += 1;
+                Op::Push(pushes[pushed - 1].clone())
+            }
+            _ => Op::Next,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::super::{Sorted, SortedBy};
+    use super::{check_ops, check_prefixes, interleaving, Mismatch, Op};
+
+    #[test]
+    fn check_ops_test() {
+        let values: Vec<u32> = (0..200).map(|i| (i * 37) % 50).collect();
+        for seed in 0..20 {
+            let ops = interleaving(seed, 100, &[5, 70, 0, 13]);
+            let iter = values.clone().sorted();
+            assert_eq!(Ok(()), check_ops(iter, values.clone(), Ord::cmp, ops));
+        }
+
+        let by = |a: &u32, b: &u32| (a % 10).cmp(&(b % 10));
+        let iter = values.clone().sorted_by(by);
+        let ops = vec![Op::Nth(30), Op::Peek, Op::Push(3), Op::Next];
+        assert_eq!(Ok(()), check_ops(iter, values.clone(), by, ops));
+
+        // A sort that yields the wrong order is caught.
+        let iter = values.clone().sorted();
+        let found = check_ops(iter, values.clone(), |a: &u32, b: &u32| b.cmp(a), vec![]);
+        assert_eq!(
+            Err(Mismatch {
+                step: 0,
+                expected: Some(49),
+                found: Some(0),
+            }),
+            found
+        );
+    }
+
+    #[test]
+    fn check_prefixes_test() {
+        let values: Vec<u32> = (0..50).map(|i| (i * 7) % 20).collect();
+        assert_eq!(
+            Ok(()),
+            check_prefixes(&values, Ord::cmp, |v: Vec<u32>| v.sorted())
+        );
+        assert!(check_prefixes(&values, Ord::cmp, |v: Vec<u32>| v.into_iter()).is_err());
+
+        // A comparator that is not a total order is reported, not a panic.
+        let by = |a: &u32, b: &u32| {
+            if (a ^ b) % 3 == 1 {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            }
+        };
+        let values: Vec<u32> = (0..500).collect();
+        assert!(check_prefixes(&values, by, |v: Vec<u32>| v.sorted_by(by)).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy sorting of parallel slices, by the values of one of them.
+//!
+//! Data laid out as a struct of arrays is sorted by its keys, and each row is
+//! yielded as the key with the values at the same index of the other columns.
+//! As in `slice`, a permutation of indexes is sorted, so neither the keys nor
+//! the columns are moved or copied into tuples.
+
+use std::cmp::Ordering;
+
+use super::slice::next;
+use super::{make_work, SortedIterator, WorkStack};
+
+/// Columns of values to be yielded alongside the keys: a slice, or a tuple of
+/// up to four slices.
+pub trait Columns<'a> {
+    type Row;
+
+    /// The number of rows, the same in every column.
+    fn rows(&self) -> usize;
+
+    fn row(&self, i: usize) -> Self::Row;
+}
+
+impl<'a, A> Columns<'a> for &'a [A] {
+    type Row = &'a A;
+
+    fn rows(&self) -> usize {
+        self.len()
+    }
+
+    fn row(&self, i: usize) -> &'a A {
+        &self[i]
+    }
+}
+
+macro_rules! columns_tuple {
+    ($($name:ident . $idx:tt),+) => {
+        impl<'a, $($name),+> Columns<'a> for ($(&'a [$name],)+) {
+            type Row = ($(&'a $name,)+);
+
+            fn rows(&self) -> usize {
+                let lens = [$(self.$idx.len()),+];
+                assert!(
+                    lens.iter().all(|&l| l == lens[0]),
+                    "columns of different lengths: {:?}",
+                    lens
+                );
+                lens[0]
+            }
+
+            fn row(&self, i: usize) -> Self::Row {
+                ($(&self.$idx[i],)+)
+            }
+        }
+    };
+}
+
+columns_tuple!(A.0);
+columns_tuple!(A.0, B.1);
+columns_tuple!(A.0, B.1, C.2);
+columns_tuple!(A.0, B.1, C.2, D.3);
+
+pub struct TogetherSortIterator<'a, K: 'a, C> {
+    keys: &'a [K],
+    columns: C,
+    indexes: Vec<usize>,
+    work: WorkStack,
+}
+
+pub struct TogetherSortIteratorBy<'a, K: 'a, C, F> {
+    keys: &'a [K],
+    columns: C,
+    indexes: Vec<usize>,
+    work: WorkStack,
+    by: F,
+}
+
+fn check<'a, K, C>(keys: &[K], columns: &C)
+where
+    C: Columns<'a>,
+{
+    let len = columns.rows();
+    assert_eq!(
+        keys.len(),
+        len,
+        "{} keys for columns of {} values",
+        keys.len(),
+        len
+    );
+}
+
+/// Iterate over `keys` in sorted order, each with the values at its index in
+/// `columns`.
+///
+/// # Panics
+///
+/// If the columns are not all as long as `keys`.
+pub fn sort_together<'a, K, C>(keys: &'a [K], columns: C) -> TogetherSortIterator<'a, K, C>
+where
+    K: Ord,
+    C: Columns<'a>,
+{
+    check(keys, &columns);
+    TogetherSortIterator {
+        keys,
+        columns,
+        indexes: (0..keys.len()).collect(),
+        work: make_work(keys.len()),
+    }
+}
+
+/// As `sort_together`, ordering the keys by `by`.
+///
+/// # Panics
+///
+/// If the columns are not all as long as `keys`.
+pub fn sort_together_by<'a, K, C, F>(
+    keys: &'a [K],
+    columns: C,
+    by: F,
+) -> TogetherSortIteratorBy<'a, K, C, F>
+where
+    C: Columns<'a>,
+    F: Fn(&K, &K) -> Ordering,
+{
+    check(keys, &columns);
+    TogetherSortIteratorBy {
+        keys,
+        columns,
+        indexes: (0..keys.len()).collect(),
+        work: make_work(keys.len()),
+        by,
+    }
+}
+
+impl<'a, K, C> Iterator for TogetherSortIterator<'a, K, C>
+where
+    K: Ord,
+    C: Columns<'a>,
+{
+    type Item = (&'a K, C::Row);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, C::Row)> {
+        let i = next(&Ord::cmp, self.keys, &mut self.indexes, &mut self.work)?;
+        Some((&self.keys[i], self.columns.row(i)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.indexes.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, K, C> SortedIterator for TogetherSortIterator<'a, K, C>
+where
+    K: Ord,
+    C: Columns<'a>,
+{
+}
+
+impl<'a, K, C, F> Iterator for TogetherSortIteratorBy<'a, K, C, F>
+where
+    C: Columns<'a>,
+    F: Fn(&K, &K) -> Ordering,
+{
+    type Item = (&'a K, C::Row);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, C::Row)> {
+        let i = next(&self.by, self.keys, &mut self.indexes, &mut self.work)?;
+        Some((&self.keys[i], self.columns.row(i)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.indexes.len();
+        (l, Some(l))
+    }
+}
+
+impl<'a, K, C, F> SortedIterator for TogetherSortIteratorBy<'a, K, C, F>
+where
+    C: Columns<'a>,
+    F: Fn(&K, &K) -> Ordering,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sort_together, sort_together_by};
+
+    #[test]
+    fn sort_together_test() {
+        let ages = vec![41u32, 23, 35];
+        let names = ["ada", "bob", "cy"];
+        let heights = [1.6f64, 1.8, 1.7];
+
+        let after: Vec<(&u32, &&str)> = sort_together(&ages, &names[..]).collect();
+        assert_eq!(vec![(&23, &"bob"), (&35, &"cy"), (&41, &"ada")], after);
+
+        let mut iter = sort_together_by(&ages, (&names[..], &heights[..]), |a, b| b.cmp(a));
+        assert_eq!(Some((&41, (&"ada", &1.6))), iter.next());
+        assert_eq!((2, Some(2)), iter.size_hint());
+        assert_eq!(vec![41, 23, 35], ages);
+    }
+
+    #[test]
+    #[should_panic(expected = "columns of different lengths")]
+    fn sort_together_length_test() {
+        let keys = [3, 1, 2];
+        sort_together(&keys, (&[1, 2, 3][..], &[1, 2][..]));
+    }
+}
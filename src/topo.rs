@@ -0,0 +1,190 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Lazy topological sorting.
+//!
+//! The number of unmet dependencies of each item is counted up front, and
+//! from then on the items are resolved only as they are consumed: yielding an
+//! item releases the items that were waiting on it.  Items become ready in
+//! the order they are released, and items ready from the start in the order
+//! they were given.
+//!
+//! If the dependencies contain a cycle, the items in it, and any that depend
+//! on them, can never be released.  Once every other item has been yielded,
+//! they are returned together in a `CycleError`.
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+
+/// The items that could not be ordered because they are in, or depend on, a
+/// cycle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleError<T> {
+    remaining: Vec<T>,
+}
+
+impl<T> CycleError<T> {
+    pub fn remaining(&self) -> &[T] {
+        &self.remaining
+    }
+
+    pub fn into_remaining(self) -> Vec<T> {
+        self.remaining
+    }
+}
+
+impl<T> fmt::Display for CycleError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} values are in, or depend on, a cycle",
+            self.remaining.len()
+        )
+    }
+}
+
+impl<T: fmt::Debug> Error for CycleError<T> {}
+
+/// Yields items so that each comes after everything it depends on.
+pub struct TopoSortIterator<T> {
+    items: Vec<Option<T>>,
+    // The items that depend on each item.
+    dependents: Vec<Vec<usize>>,
+    // The number of each item's dependencies not yet yielded.
+    waiting: Vec<usize>,
+    ready: VecDeque<usize>,
+    remaining: usize,
+}
+
+impl<T> TopoSortIterator<T> {
+    /// Order `items`, where each edge `(a, b)` says that the item at index `a`
+    /// must come before the item at index `b`.
+    ///
+    /// # Panics
+    ///
+    /// If an edge refers to an index with no item.
+    pub fn new<I, E>(items: I, edges: E) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        E: IntoIterator<Item = (usize, usize)>,
+    {
+        let items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+        let mut dependents = vec![Vec::new(); items.len()];
+        let mut waiting = vec![0; items.len()];
+        for (before, after) in edges {
+            assert!(
+                before < items.len() && after < items.len(),
+                "edge ({}, {}) out of range for {} items",
+                before,
+                after,
+                items.len()
+            );
+            dependents[before].push(after);
+            waiting[after] += 1;
+        }
+        let ready = (0..items.len()).filter(|&i| waiting[i] == 0).collect();
+        TopoSortIterator {
+            remaining: items.len(),
+            items,
+            dependents,
+            waiting,
+            ready,
+        }
+    }
+
+    /// Order `items`, each identified by `key`, and depending on the items
+    /// whose keys are returned by `depends_on`.  Dependencies on keys that no
+    /// item has are ignored.
+    pub fn by_key<I, K, F, D, DI>(items: I, key: F, depends_on: D) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        K: Hash + Eq,
+        F: Fn(&T) -> K,
+        D: Fn(&T) -> DI,
+        DI: IntoIterator<Item = K>,
+    {
+        let items: Vec<T> = items.into_iter().collect();
+        let index: HashMap<K, usize> = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (key(item), i))
+            .collect();
+        let mut edges = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            for dependency in depends_on(item) {
+                if let Some(&before) = index.get(&dependency) {
+                    edges.push((before, i));
+                }
+            }
+        }
+        TopoSortIterator::new(items, edges)
+    }
+}
+
+impl<T> Iterator for TopoSortIterator<T> {
+    type Item = Result<T, CycleError<T>>;
+
+    fn next(&mut self) -> Option<Result<T, CycleError<T>>> {
+        match self.ready.pop_front() {
+            Some(i) => {
+                for j in mem::take(&mut self.dependents[i]) {
+                    self.waiting[j] -= 1;
+                    if self.waiting[j] == 0 {
+                        self.ready.push_back(j);
+                    }
+                }
+                self.remaining -= 1;
+                self.items[i].take().map(Ok)
+            }
+            None if self.remaining > 0 => {
+                self.remaining = 0;
+                let remaining = self.items.iter_mut().filter_map(Option::take).collect();
+                Some(Err(CycleError { remaining }))
+            }
+            None => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining.min(1), Some(self.remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TopoSortIterator;
+
+    #[test]
+    fn topo_sort_test() {
+        let tasks = vec![
+            ("link", vec!["compile"]),
+            ("compile", vec!["fetch"]),
+            ("fetch", vec![]),
+            ("test", vec!["link", "fetch"]),
+        ];
+        let order: Vec<&str> = TopoSortIterator::by_key(tasks, |t| t.0, |t| t.1.clone())
+            .map(|t| t.unwrap().0)
+            .collect();
+        assert_eq!(vec!["fetch", "compile", "link", "test"], order);
+    }
+
+    #[test]
+    fn topo_cycle_test() {
+        let mut iter =
+            TopoSortIterator::new(vec!['a', 'b', 'c', 'd'], vec![(1, 2), (2, 1), (2, 3)]);
+        assert_eq!(Some(Ok('a')), iter.next());
+        let error = iter.next().unwrap().unwrap_err();
+        assert_eq!(&['b', 'c', 'd'], error.remaining());
+        assert_eq!(None, iter.next());
+    }
+}
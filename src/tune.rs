@@ -0,0 +1,150 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! The thresholds at which the lazy sorts change algorithm.
+//!
+//! The defaults suit small values with cheap comparisons.  Where moving
+//! values or comparing them costs much more, `calibrate` times the sorts over
+//! a sample of the real values, with the real comparator, once, and returns
+//! the thresholds that suit them, to be given to each sort with
+//! `with_thresholds`.  They change how much work is done, never the order of
+//! the values.
+
+use std::cmp::Ordering;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use super::network;
+use super::SortedBy;
+
+/// Timings are the minimum of this many runs.
+const ROUNDS: usize = 3;
+
+/// When the lazy sorts change algorithm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Ranges of at most this many values are sorted with a sorting network
+    /// rather than partitioned; between 2 and 8.
+    pub small_sort: usize,
+    /// An adaptive sort sorts the rest of its values at once when it has
+    /// yielded this percentage of the number remaining; at 100 it switches
+    /// half way through.
+    pub adapt_percent: usize,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            small_sort: network::MAX,
+            adapt_percent: 100,
+        }
+    }
+}
+
+impl Thresholds {
+    /// These thresholds, clamped to the values they can take.
+    pub(crate) fn clamped(self) -> Self {
+        Thresholds {
+            small_sort: self.small_sort.clamp(2, network::MAX),
+            adapt_percent: self.adapt_percent.max(1),
+        }
+    }
+}
+
+fn time<F>(mut f: F) -> Duration
+where
+    F: FnMut(),
+{
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .expect("Some rounds")
+}
+
+/// Time the sorts over `sample`, compared by `by`, and return the thresholds
+/// that suit it: the small sort size that consumes the whole sample fastest,
+/// and the adaptive switch at the point where the time spent yielding values
+/// lazily would have paid for sorting the rest at once.
+///
+/// The sample should be a few thousand values like those that will be
+/// sorted, in the order they usually arrive.
+pub fn calibrate<T, F>(sample: &[T], by: F) -> Thresholds
+where
+    T: Clone,
+    F: Fn(&T, &T) -> Ordering,
+{
+    if sample.len() < 2 {
+        return Thresholds::default();
+    }
+    let lazy = |small_sort: usize| {
+        let thresholds = Thresholds {
+            small_sort,
+            ..Thresholds::default()
+        };
+        time(|| {
+            let sorted = sample.to_vec().sorted_by(|a: &T, b: &T| by(a, b));
+            for value in sorted.with_thresholds(thresholds) {
+                black_box(value);
+            }
+        })
+    };
+
+    let (small_sort, lazy_time) = (2..=network::MAX)
+        .map(|n| (n, lazy(n)))
+        .min_by_key(|x| x.1)
+        .expect("Some sizes");
+    let eager_time = time(|| {
+        let mut values = sample.to_vec();
+        values.sort_unstable_by(|a, b| by(a, b));
+        black_box(values);
+    });
+
+    let lazy_nanos = lazy_time.as_nanos().max(1);
+    Thresholds {
+        small_sort,
+        adapt_percent: (eager_time.as_nanos() * 100 / lazy_nanos) as usize,
+    }
+    .clamped()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Sorted, Strategy};
+    use super::{calibrate, Thresholds};
+
+    #[test]
+    fn calibrate_test() {
+        let before: Vec<[u64; 16]> = (0..1000).map(|i| [(i * 7919) % 1000; 16]).collect();
+        let thresholds = calibrate(&before, |a, b| a.cmp(b));
+        assert!((2..=8).contains(&thresholds.small_sort));
+        assert!(thresholds.adapt_percent >= 1);
+
+        let extreme = Thresholds {
+            small_sort: 100,
+            adapt_percent: 0,
+        };
+        assert_eq!(8, extreme.clamped().small_sort);
+        assert_eq!(1, extreme.clamped().adapt_percent);
+        for thresholds in [thresholds, extreme] {
+            let after: Vec<u64> = before
+                .iter()
+                .map(|x| x[0])
+                .sorted()
+                .with_strategy(Strategy::Adaptive)
+                .with_thresholds(thresholds)
+                .collect();
+            assert_eq!((0..1000).collect::<Vec<u64>>(), after);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Recording the partitions a sort makes, to see why it is slow.
+//!
+//! Once `start_recording` is called, every partition made on the thread is
+//! noted: the range partitioned and where its pivots ended up.
+//! `export_partition_tree` stops recording and returns the partitions as a
+//! tree, each range beneath the range it was split from, in Graphviz DOT or
+//! in JSON.  A pivot that lands near one end of its range, again and again,
+//! shows up as a long thin chain.
+//!
+//! Ranges are indexes into the sort's buffer, where the lowest values are at
+//! the highest indexes.  Record one sort at a time: the tree is built from
+//! the ranges alone, so those of different sorts would be mixed together.
+
+use std::cell::RefCell;
+use std::fmt::Write;
+
+/// A partition of `data[upper..=lower]`, with the indexes its pivots ended
+/// at; for a three-way partition, the first and last of the values equal to
+/// the pivot.
+struct Partition {
+    lower: usize,
+    upper: usize,
+    pivots: Vec<usize>,
+}
+
+thread_local! {
+    static RECORDING: RefCell<Option<Vec<Partition>>> = const { RefCell::new(None) };
+}
+
+/// The format to export the partitions in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Dot,
+    Json,
+}
+
+/// Start recording the partitions made on this thread, discarding any
+/// recorded so far.
+pub fn start_recording() {
+    RECORDING.with(|r| *r.borrow_mut() = Some(Vec::new()));
+}
+
+#[inline]
+pub(crate) fn note(lower: usize, upper: usize, pivots: &[usize]) {
+    RECORDING.with(|r| {
+        if let Some(ref mut partitions) = *r.borrow_mut() {
+            partitions.push(Partition {
+                lower,
+                upper,
+                pivots: pivots.to_vec(),
+            });
+        }
+    });
+}
+
+/// Stop recording, and return the partitions made since `start_recording`
+/// in `format`.  Empty if recording was never started.
+pub fn export_partition_tree(format: Format) -> String {
+    let partitions = RECORDING
+        .with(|r| r.borrow_mut().take())
+        .unwrap_or_default();
+    let parents = parents(&partitions);
+    match format {
+        Format::Dot => dot(&partitions, &parents),
+        Format::Json => json(&partitions, &parents),
+    }
+}
+
+/// The partition each was split from.  Ranges are split one after the other,
+/// so the ranges that contain each are those still on a stack of the ranges
+/// seen.
+fn parents(partitions: &[Partition]) -> Vec<Option<usize>> {
+    let mut stack: Vec<usize> = Vec::new();
+    let mut parents = Vec::with_capacity(partitions.len());
+    for (i, p) in partitions.iter().enumerate() {
+        while let Some(&top) = stack.last() {
+            let q = &partitions[top];
+            if q.upper <= p.upper && p.lower <= q.lower {
+                break;
+            }
+            stack.pop();
+        }
+        parents.push(stack.last().cloned());
+        stack.push(i);
+    }
+    parents
+}
+
+fn dot(partitions: &[Partition], parents: &[Option<usize>]) -> String {
+    let mut out = String::from("digraph partitions {\n    node [shape=box];\n");
+    for (i, p) in partitions.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    n{} [label=\"{} values, [{}, {}]\\npivots at {:?}\"];",
+            i,
+            p.lower - p.upper + 1,
+            p.upper,
+            p.lower,
+            p.pivots
+        );
+        if let Some(parent) = parents[i] {
+            let _ = writeln!(out, "    n{} -> n{};", parent, i);
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn json(partitions: &[Partition], parents: &[Option<usize>]) -> String {
+    let mut out = String::from("[");
+    for (i, p) in partitions.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let parent = match parents[i] {
+            Some(parent) => parent.to_string(),
+            None => "null".to_string(),
+        };
+        let pivots: Vec<String> = p.pivots.iter().map(|x| x.to_string()).collect();
+        let _ = write!(
+            out,
+            "{{\"id\":{},\"parent\":{},\"lower\":{},\"upper\":{},\"pivots\":[{}]}}",
+            i,
+            parent,
+            p.lower,
+            p.upper,
+            pivots.join(",")
+        );
+    }
+    out.push(']');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Sorted;
+    use super::{export_partition_tree, start_recording, Format};
+
+    #[test]
+    fn export_partition_tree_test() {
+        assert_eq!("[]", export_partition_tree(Format::Json));
+
+        let before: Vec<u64> = (0..100).map(|i| (i * 37) % 100).collect();
+        start_recording();
+        let mut iter = before.iter().cloned().sorted();
+        assert_eq!(Some(0), iter.next());
+        let json = export_partition_tree(Format::Json);
+        assert!(json.starts_with("[{\"id\":0,\"parent\":null,\"lower\":99,\"upper\":0,"));
+        assert!(json.contains("\"parent\":0,"));
+
+        start_recording();
+        iter.next();
+        let dot = export_partition_tree(Format::Dot);
+        assert!(dot.starts_with("digraph partitions {"));
+        assert!(!export_partition_tree(Format::Dot).contains("n0"));
+    }
+}
@@ -0,0 +1,171 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Weighted medians and quantiles.
+//!
+//! These use the same partitioning as the lazy sorts, but rather than
+//! following the lowest values each step keeps only the side of the pivot on
+//! which the cumulative weight crosses the target, so on average the work is
+//! linear in the number of values and nothing is ever fully sorted.
+//!
+//! The weighted `phi` quantile is the lowest value at which the cumulative
+//! weight, counting from the lowest value up, reaches `phi` of the total.
+//! Values whose weight is not positive (including NaN) are ignored.
+
+use std::cmp::Ordering;
+
+use super::{partition, pivot};
+
+/// Find the position in `data[upper..=lower]` of the value at which the
+/// cumulative weight reaches `target`, given that the values at the positions
+/// after `lower` weigh `before` in total.  Returns the position, and the
+/// weight of all the values after it.
+fn select<T, F>(
+    by: &F,
+    data: &mut [(T, f64)],
+    mut lower: usize,
+    mut upper: usize,
+    mut before: f64,
+    target: f64,
+) -> (usize, f64)
+where
+    F: Fn(&(T, f64), &(T, f64)) -> Ordering,
+{
+    loop {
+        if lower == upper {
+            return (lower, before);
+        }
+        let q = partition(by, data, lower, upper, pivot(lower, upper));
+        let below: f64 = data[q + 1..=lower].iter().map(|x| x.1).sum();
+        if q < lower && before + below >= target {
+            upper = q + 1;
+        } else if before + below + data[q].1 >= target || q == upper {
+            return (q, before + below);
+        } else {
+            before += below + data[q].1;
+            lower = q - 1;
+        }
+    }
+}
+
+fn weigh<I, W>(iter: I, mut weight: W) -> (Vec<(I::Item, f64)>, f64)
+where
+    I: Iterator,
+    W: FnMut(&I::Item) -> f64,
+{
+    let data: Vec<(I::Item, f64)> = iter
+        .map(|x| {
+            let w = weight(&x);
+            (x, w)
+        })
+        .filter(|x| x.1 > 0.0)
+        .collect();
+    let total = data.iter().map(|x| x.1).sum();
+    (data, total)
+}
+
+pub trait WeightedQuantiles: IntoIterator + Sized {
+    /// The lowest value at which the cumulative weight reaches half of the
+    /// total, or `None` if nothing has a positive weight.
+    fn weighted_median<W>(self, weight: W) -> Option<Self::Item>
+    where
+        Self::Item: Ord,
+        W: FnMut(&Self::Item) -> f64,
+    {
+        let (mut data, total) = weigh(self.into_iter(), weight);
+        if data.is_empty() {
+            return None;
+        }
+        let by = |a: &(Self::Item, f64), b: &(Self::Item, f64)| a.0.cmp(&b.0);
+        let lower = data.len() - 1;
+        let (q, _) = select(&by, &mut data, lower, 0, 0.0, total * 0.5);
+        Some(data.swap_remove(q).0)
+    }
+
+    /// The weighted quantile for each of `phis`, which are clamped to between
+    /// 0 and 1, in the same order as `phis`.  Empty if nothing has a positive
+    /// weight.
+    fn weighted_quantiles<W>(self, weight: W, phis: &[f64]) -> Vec<Self::Item>
+    where
+        Self::Item: Ord + Clone,
+        W: FnMut(&Self::Item) -> f64,
+    {
+        let (mut data, total) = weigh(self.into_iter(), weight);
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let by = |a: &(Self::Item, f64), b: &(Self::Item, f64)| a.0.cmp(&b.0);
+
+        // Each quantile is found in the part of the data left above the
+        // previous one, so they are taken in increasing order.
+        let mut order: Vec<usize> = (0..phis.len()).collect();
+        order.sort_by(|&a, &b| phis[a].total_cmp(&phis[b]));
+
+        let mut found = Vec::with_capacity(phis.len());
+        let mut lower = data.len() - 1;
+        let mut before = 0.0;
+        for i in order {
+            let target = phis[i].clamp(0.0, 1.0) * total;
+            let (q, after) = select(&by, &mut data, lower, 0, before, target);
+            // Later partitions may move the value, so it's taken now.
+            found.push((i, data[q].0.clone()));
+            lower = q;
+            before = after;
+        }
+        found.sort_unstable_by_key(|x| x.0);
+        found.into_iter().map(|x| x.1).collect()
+    }
+}
+
+impl<I> WeightedQuantiles for I where I: IntoIterator {}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use self::rand::Rng;
+
+    use super::WeightedQuantiles;
+
+    fn expected(values: &[(u32, u32)], phi: f64) -> u32 {
+        let mut sorted: Vec<(u32, u32)> = values.iter().cloned().filter(|x| x.1 > 0).collect();
+        sorted.sort_by_key(|x| x.0);
+        let target = phi * sorted.iter().map(|x| x.1).sum::<u32>() as f64;
+        let mut sum = 0.0;
+        for &(v, w) in sorted.iter() {
+            sum += w as f64;
+            if sum >= target {
+                return v;
+            }
+        }
+        sorted.last().unwrap().0
+    }
+
+    #[test]
+    fn weighted_quantiles_test() {
+        let values = vec![(1, 1), (2, 1), (3, 5), (4, 0), (5, 1)];
+        assert_eq!(Some((3, 5)), values.weighted_median(|x| x.1 as f64));
+        assert_eq!(None, vec![(1, 0)].weighted_median(|x| x.1 as f64));
+
+        let mut rng = rand::thread_rng();
+        let values: Vec<(u32, u32)> = (0..1000)
+            .map(|_| (rng.gen_range(0, 500), rng.gen_range(0, 10)))
+            .collect();
+        let phis = [0.9, 0.1, 0.5, 0.0, 1.0, 0.5];
+        let found: Vec<u32> = values
+            .clone()
+            .weighted_quantiles(|x| x.1 as f64, &phis)
+            .into_iter()
+            .map(|x| x.0)
+            .collect();
+        let wanted: Vec<u32> = phis.iter().map(|&phi| expected(&values, phi)).collect();
+        assert_eq!(wanted, found);
+    }
+}
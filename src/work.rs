@@ -0,0 +1,227 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use super::Stack;
+
+/// The number of work entries held inline before spilling to the heap.  The
+/// work list rarely grows much beyond the depth of the quicksort recursion,
+/// so this covers all but very large or adversarial inputs.
+const INLINE: usize = 32;
+
+/// Entries beyond the inline ones.  Indexes are stored as `u32` to halve the
+/// size of the list, until an index that does not fit is pushed, at which
+/// point every entry is moved to a `usize` list.
+#[derive(Clone)]
+enum Spill {
+    Narrow(Vec<(u32, u32)>),
+    Wide(Vec<(usize, usize)>),
+}
+
+/// The work list of a lazy sort: the ranges of the data that are still to be
+/// sorted, with the range holding the next values on top.  The first entries
+/// are held inline, which saves an allocation for most sorts and keeps the
+/// top of the list in the same cache lines as the rest of the iterator.
+///
+/// Ranges are `(lower, upper)` with `lower >= upper`, so an entry with
+/// `lower < upper` is free to mean something else: a run of single values,
+/// each already in its place, from `lower` up to `upper`.  Pushing the ranges
+/// of a sorted run, one value at a time, then takes one entry rather than one
+/// for each value, and popping them yields the same ranges as before.
+#[derive(Clone)]
+pub(crate) struct WorkStack {
+    inline: [(u32, u32); INLINE],
+    len: usize,
+    spill: Spill,
+}
+
+impl WorkStack {
+    pub(crate) fn new() -> Self {
+        WorkStack {
+            inline: [(0, 0); INLINE],
+            len: 0,
+            spill: Spill::Narrow(Vec::new()),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        match self.spill {
+            Spill::Wide(ref wide) => wide.is_empty(),
+            Spill::Narrow(ref narrow) => self.len == 0 && narrow.is_empty(),
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+        self.spill = Spill::Narrow(Vec::new());
+    }
+
+    /// Whether the top `n` ranges each hold a single value, which is then
+    /// already in its place.
+    pub(crate) fn in_place(&self, n: usize) -> bool {
+        fn settled<I: Iterator<Item = (usize, usize)>>(entries: I, n: usize) -> bool {
+            let mut count = 0;
+            for (lower, upper) in entries {
+                if lower > upper {
+                    break;
+                }
+                count += upper - lower + 1;
+                if count >= n {
+                    return true;
+                }
+            }
+            n == 0
+        }
+        match self.spill {
+            Spill::Wide(ref wide) => settled(wide.iter().rev().cloned(), n),
+            Spill::Narrow(ref narrow) => {
+                let entries = narrow
+                    .iter()
+                    .rev()
+                    .chain(self.inline[..self.len].iter().rev())
+                    .map(|&(lower, upper)| (lower as usize, upper as usize));
+                settled(entries, n)
+            }
+        }
+    }
+
+    #[cold]
+    fn widen(&mut self) {
+        let mut wide: Vec<(usize, usize)> = self.inline[..self.len]
+            .iter()
+            .map(|&(lower, upper)| (lower as usize, upper as usize))
+            .collect();
+        if let Spill::Narrow(ref narrow) = self.spill {
+            wide.extend(
+                narrow
+                    .iter()
+                    .map(|&(lower, upper)| (lower as usize, upper as usize)),
+            );
+        }
+        self.len = 0;
+        self.spill = Spill::Wide(wide);
+    }
+
+    /// Push an entry as it is, which must fit the current spill.
+    #[inline]
+    fn push_entry(&mut self, value: (usize, usize)) {
+        // The spill is only used once the inline entries are full, and is
+        // emptied before any inline entry is popped.
+        match self.spill {
+            Spill::Wide(ref mut wide) => wide.push(value),
+            Spill::Narrow(ref mut narrow) => {
+                let value = (value.0 as u32, value.1 as u32);
+                if self.len < INLINE {
+                    self.inline[self.len] = value;
+                    self.len += 1;
+                } else {
+                    narrow.push(value);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn pop_entry(&mut self) -> Option<(usize, usize)> {
+        let value = match self.spill {
+            Spill::Wide(ref mut wide) => return wide.pop(),
+            Spill::Narrow(ref mut narrow) => match narrow.pop() {
+                Some(value) => value,
+                None if self.len == 0 => return None,
+                None => {
+                    self.len -= 1;
+                    self.inline[self.len]
+                }
+            },
+        };
+        Some((value.0 as usize, value.1 as usize))
+    }
+}
+
+impl Stack<(usize, usize)> for WorkStack {
+    #[inline]
+    fn push(&mut self, value: (usize, usize)) {
+        // Ranges are pushed as `(lower, upper)` with `lower >= upper`, so only
+        // `lower` needs checking.
+        if value.0 > u32::MAX as usize {
+            if let Spill::Narrow(_) = self.spill {
+                self.widen();
+            }
+        }
+        if value.0 == value.1 {
+            if let Some((lower, upper)) = self.pop_entry() {
+                if lower <= upper && upper + 1 == value.0 {
+                    self.push_entry((lower, value.0));
+                    return;
+                }
+                self.push_entry((lower, upper));
+            }
+        }
+        self.push_entry(value);
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<(usize, usize)> {
+        let (lower, upper) = self.pop_entry()?;
+        if lower < upper {
+            self.push_entry((lower, upper - 1));
+            return Some((upper, upper));
+        }
+        Some((lower, upper))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Stack;
+    use super::{Spill, WorkStack, INLINE};
+
+    #[test]
+    fn spill_test() {
+        let mut work = WorkStack::new();
+        for i in 0..INLINE * 3 {
+            work.push((2 * i + 1, 2 * i));
+        }
+        for i in (0..INLINE * 3).rev() {
+            assert_eq!(Some((2 * i + 1, 2 * i)), work.pop());
+        }
+        assert_eq!(None, work.pop());
+    }
+
+    #[test]
+    fn run_test() {
+        let mut work = WorkStack::new();
+        work.push((9, 0));
+        for i in 10..1000 {
+            work.push((i, i));
+        }
+        assert_eq!(2, work.len);
+        assert!(work.in_place(990));
+        assert!(!work.in_place(991));
+        assert_eq!(Some((999, 999)), work.pop());
+        work.push((999, 999));
+        for i in (10..1000).rev() {
+            assert_eq!(Some((i, i)), work.pop());
+        }
+        assert_eq!(Some((9, 0)), work.pop());
+        assert_eq!(None, work.pop());
+    }
+
+    #[test]
+    fn widen_test() {
+        let big = u32::MAX as usize + 10;
+        let mut work = WorkStack::new();
+        work.push((5, 0));
+        work.push((big, 6));
+        assert!(matches!(work.spill, Spill::Wide(_)));
+        assert_eq!(Some((big, 6)), work.pop());
+        assert_eq!(Some((5, 0)), work.pop());
+        assert_eq!(None, work.pop());
+    }
+}
@@ -0,0 +1,55 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn lazysort(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lazysort"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn sort_stdin_test() {
+    assert_eq!("a\nb\nc\n", lazysort(&[], "c\na\nb\n"));
+}
+
+#[test]
+fn spilled_take_test() {
+    let input: String = (0..2000).rev().map(|x| format!("{}\n", x)).collect();
+    assert_eq!(
+        "0\n1\n2\n",
+        lazysort(&["-n", "-S", "1K", "--take", "3"], &input)
+    );
+}
+
+#[test]
+fn key_field_test() {
+    let input = "b,3\na,10\nc,2\n";
+    assert_eq!(
+        "a,10\nb,3\nc,2\n",
+        lazysort(&["-k", "2", "-t", ",", "-V", "-r"], input)
+    );
+}
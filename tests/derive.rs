@@ -0,0 +1,72 @@
+/*
+ * Copyright 2016-2018 rust-lazysort developers
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+#![cfg(feature = "derive")]
+
+extern crate lazysort;
+
+use std::collections::HashMap;
+
+use lazysort::prelude::*;
+use lazysort::{BySortKey, SortKey};
+
+#[derive(Clone, Debug, PartialEq, SortKey)]
+struct Employee {
+    #[sort_key(order = 2)]
+    name: String,
+    #[sort_key(order = 1, desc)]
+    pub salary: u32,
+    #[sort_key]
+    dept: &'static str,
+    #[allow(dead_code)]
+    notes: HashMap<String, Vec<u8>>,
+}
+
+fn employee(name: &str, salary: u32, dept: &'static str) -> Employee {
+    Employee {
+        name: name.to_string(),
+        salary,
+        dept,
+        notes: HashMap::new(),
+    }
+}
+
+#[test]
+fn derive_sort_key_test() {
+    let staff = vec![
+        employee("cy", 100, "ops"),
+        employee("al", 300, "dev"),
+        employee("bo", 100, "dev"),
+        employee("al", 100, "dev"),
+    ];
+    let order: Vec<(String, u32, &str)> = staff
+        .iter()
+        .cloned()
+        .sorted_with(BySortKey)
+        .map(|e| (e.name, e.salary, e.dept))
+        .collect();
+    assert_eq!(
+        vec![
+            ("al".to_string(), 300, "dev"),
+            ("al".to_string(), 100, "dev"),
+            ("bo".to_string(), 100, "dev"),
+            ("cy".to_string(), 100, "ops"),
+        ],
+        order
+    );
+
+    let by_key: Vec<Employee> = staff
+        .iter()
+        .cloned()
+        .sorted_by_cached_key(|e| e.sort_key())
+        .collect();
+    let by_cmp: Vec<Employee> = staff.into_iter().sorted_by(SortKey::sort_key_cmp).collect();
+    assert_eq!(by_cmp, by_key);
+}